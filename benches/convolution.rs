@@ -0,0 +1,52 @@
+use std::time::Instant;
+
+use rust_algorithms::neural_network::{convolutional::Conv2D, core::Layer2D};
+
+fn image(dim: (usize, usize, usize)) -> Vec<Vec<Vec<f32>>> {
+    (0..dim.0)
+        .map(|c| {
+            (0..dim.1)
+                .map(|i| {
+                    (0..dim.2)
+                        .map(|j| ((c * dim.1 * dim.2 + i * dim.2 + j) as f32).sin())
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn time<T>(runs: usize, f: impl Fn() -> T) -> f64 {
+    let start = Instant::now();
+    for _ in 0..runs {
+        std::hint::black_box(f());
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / runs as f64
+}
+
+// cargo bench --bench convolution
+fn main() {
+    let cases = [
+        ("mnist 1x28x28 -> 8, 5x5", Conv2D::new((1, 28, 28), 8, (5, 5), (2, 2), (1, 1))),
+        ("cifar 3x32x32 -> 16, 3x3", Conv2D::new((3, 32, 32), 16, (3, 3), (1, 1), (1, 1))),
+        ("16x16x16 -> 32, 3x3", Conv2D::new((16, 16, 16), 32, (3, 3), (1, 1), (1, 1))),
+        ("3x64x64 -> 16, 3x3 /2", Conv2D::new((3, 64, 64), 16, (3, 3), (1, 1), (2, 2))),
+    ];
+    let runs = 5;
+    println!(
+        "{:<28}{:>14}{:>14}{:>14}{:>14}",
+        "layer", "direct fwd", "im2col fwd", "direct back", "im2col back"
+    );
+    for (name, conv) in cases {
+        let input = image(conv.dim_in);
+        let error = image(conv.dim_out);
+        println!(
+            "{:<28}{:>12.2}ms{:>12.2}ms{:>12.2}ms{:>12.2}ms",
+            name,
+            time(runs, || conv.forward_direct(&input)),
+            time(runs, || conv.forward(&input)),
+            time(runs, || conv.back_direct(&input, &error)),
+            time(runs, || conv.back(&input, &error)),
+        );
+    }
+}
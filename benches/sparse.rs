@@ -0,0 +1,74 @@
+use std::time::Instant;
+
+use rust_algorithms::neural_network::{
+    convolutional::Conv2D,
+    core::{Layer, Layer2D},
+    linear::linear::Linear,
+    pruning::{SparseConv2D, SparseLinear},
+};
+
+fn image(dim: (usize, usize, usize)) -> Vec<Vec<Vec<f32>>> {
+    (0..dim.0)
+        .map(|c| {
+            (0..dim.1)
+                .map(|i| {
+                    (0..dim.2)
+                        .map(|j| ((c * dim.1 * dim.2 + i * dim.2 + j) as f32).sin())
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn time<T>(runs: usize, f: impl Fn() -> T) -> f64 {
+    let start = Instant::now();
+    for _ in 0..runs {
+        std::hint::black_box(f());
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / runs as f64
+}
+
+// zeroes all but every keep-th weight
+fn thin<'a>(weights: impl Iterator<Item = &'a mut f32>, keep: usize) {
+    for (k, w) in weights.enumerate() {
+        if !k.is_multiple_of(keep) {
+            *w = 0.0;
+        }
+    }
+}
+
+// cargo bench --bench sparse
+fn main() {
+    let runs = 20;
+    println!(
+        "{:<28}{:>10}{:>14}{:>14}",
+        "layer", "density", "dense fwd", "sparse fwd"
+    );
+    for keep in [2, 4, 10, 20] {
+        let mut linear = Linear::new(784, 256);
+        thin(linear.weights.iter_mut().flatten(), keep);
+        let sparse = SparseLinear::new(&linear);
+        let input: Vec<f32> = (0..784).map(|k| (k as f32).sin()).collect();
+        println!(
+            "{:<28}{:>10.2}{:>12.3}ms{:>12.3}ms",
+            "linear 784 -> 256",
+            sparse.weights.density(),
+            time(runs, || linear.forward(&input)),
+            time(runs, || sparse.forward(&input)),
+        );
+    }
+    for keep in [2, 4, 10, 20] {
+        let mut conv = Conv2D::new((16, 16, 16), 32, (3, 3), (1, 1), (1, 1));
+        thin(conv.weights.iter_mut().flatten().flatten().flatten(), keep);
+        let sparse = SparseConv2D::new(&conv);
+        let input = image(conv.dim_in);
+        println!(
+            "{:<28}{:>10.2}{:>12.3}ms{:>12.3}ms",
+            "16x16x16 -> 32, 3x3",
+            sparse.density(),
+            time(runs, || conv.forward(&input)),
+            time(runs, || sparse.forward(&input)),
+        );
+    }
+}
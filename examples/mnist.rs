@@ -0,0 +1,85 @@
+// trains a small convolutional network on the MNIST files in a local directory,
+// cargo run --release --example mnist -- path/to/mnist [train samples] [epochs]
+use rust_algorithms::neural_network::{
+    convolutional::{Conv2D, MaxPool2D},
+    core::{cross_entropy, Flatten, Layer, Layer2D, ReLU, Softmax},
+    data::{load_mnist, one_hot, Normalisation},
+    linear::linear::Linear,
+    optimiser::Sgd,
+    quantisation::{compare, quantise_model, Granularity},
+    sequential::{Node, Sequential, Tensor},
+    training::{accuracy, Logger, Trainer},
+};
+
+fn model() -> Sequential {
+    let conv = Conv2D::new((1, 28, 28), 8, (3, 3), (1, 1), (1, 1));
+    let pool = MaxPool2D::new(conv.dim_out(), (2, 2), (0, 0), (2, 2));
+    let flatten = Flatten::new(pool.dim_out());
+    let hidden = Linear::new(flatten.dim_out(), 64);
+    let relu = ReLU::new(hidden.dim_out());
+    let output = Linear::new(relu.dim_out(), 10);
+    let softmax = Softmax::new(output.dim_out());
+    Sequential::new(vec![
+        Node::Spatial(Box::new(conv)),
+        Node::Spatial(Box::new(pool)),
+        Node::Flatten(flatten),
+        Node::Dense(Box::new(hidden)),
+        Node::Dense(Box::new(relu)),
+        Node::Dense(Box::new(output)),
+        Node::Dense(Box::new(softmax)),
+    ])
+}
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let directory = args.next().unwrap_or_else(|| "data/mnist".to_string());
+    let samples: usize = args.next().map_or(6000, |x| x.parse().unwrap());
+    let epochs: usize = args.next().map_or(3, |x| x.parse().unwrap());
+    let (batch_size, learning_rate) = (32, 0.05);
+
+    let (train_images, train_labels) = load_mnist(&directory, true, &Normalisation::Unit)?;
+    let (test_images, test_labels) = load_mnist(&directory, false, &Normalisation::Unit)?;
+    let (inputs, targets): (Vec<Tensor>, Vec<Vec<f32>>) = train_images
+        .into_iter()
+        .zip(train_labels)
+        .take(samples)
+        .map(|(image, label)| (Tensor::Spatial(image), one_hot(label, 10)))
+        .unzip();
+
+    let mut network = model();
+    let mut trainer = Trainer::new(cross_entropy, epochs, batch_size, 0);
+    trainer.validation_split = 0.1;
+    trainer.metrics = vec![("accuracy".to_string(), accuracy)];
+    trainer.fit(
+        &mut network,
+        &mut Sgd::new(learning_rate, 0.0, false),
+        &inputs,
+        &targets,
+        &mut [&mut Logger::new(1)],
+    );
+
+    let test_inputs: Vec<Tensor> = test_images.into_iter().map(Tensor::Spatial).collect();
+    let test_targets: Vec<Vec<f32>> = test_labels
+        .iter()
+        .map(|label| one_hot(*label, 10))
+        .collect();
+    let scores = trainer.evaluate(&network, &test_inputs, &test_targets);
+    println!("test accuracy: {:.2}%", 100.0 * scores[1].1);
+
+    // the same weights with int8 convolution and linear layers
+    let mut copy = model();
+    copy.load(&network.save())?;
+    let quantised = quantise_model(
+        copy,
+        &inputs[..200.min(inputs.len())],
+        Granularity::PerChannel,
+    );
+    let report = compare(&network, &quantised, &test_inputs, &test_targets, accuracy);
+    println!(
+        "int8 test accuracy: {:.2}%, a drop of {:.2}%, agreeing on {:.2}%",
+        100.0 * report.quantised,
+        100.0 * report.drop,
+        100.0 * report.agreement
+    );
+    Ok(())
+}
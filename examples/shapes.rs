@@ -0,0 +1,77 @@
+// trains a small single shot detector on generated shapes and reports the mAP on
+// held out images, cargo run --release --example shapes -- [train images] [epochs]
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rust_algorithms::neural_network::{
+    convolutional::{Conv2D, MaxPool2D},
+    core::{Layer2D, ReLU2D},
+    detection::{
+        mean_average_precision, non_max_suppression, synthetic_shapes, YoloHead, YoloLoss,
+        SHAPE_CLASSES,
+    },
+    sequential::{Node, Sequential, Tensor},
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let samples: usize = args.next().map_or(300, |x| x.parse().unwrap());
+    let epochs: usize = args.next().map_or(15, |x| x.parse().unwrap());
+    let learning_rate = 0.005;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let train = synthetic_shapes(samples, 32, 3, &mut rng);
+    let test = synthetic_shapes(100, 32, 3, &mut rng);
+
+    let first = Conv2D::new((3, 32, 32), 8, (3, 3), (1, 1), (1, 1));
+    let first_relu = ReLU2D::new(first.dim_out());
+    let first_pool = MaxPool2D::new(first_relu.dim_out(), (2, 2), (0, 0), (2, 2));
+    let second = Conv2D::new(first_pool.dim_out(), 16, (3, 3), (1, 1), (1, 1));
+    let second_relu = ReLU2D::new(second.dim_out());
+    let second_pool = MaxPool2D::new(second_relu.dim_out(), (4, 4), (0, 0), (4, 4));
+    let head = YoloHead::new(
+        second_pool.dim_out(),
+        vec![(0.25, 0.25), (0.45, 0.45)],
+        SHAPE_CLASSES.len(),
+    );
+    let grid = head.grid.clone();
+    let mut model = Sequential::new(vec![
+        Node::Spatial(Box::new(first)),
+        Node::Spatial(Box::new(first_relu)),
+        Node::Spatial(Box::new(first_pool)),
+        Node::Spatial(Box::new(second)),
+        Node::Spatial(Box::new(second_relu)),
+        Node::Spatial(Box::new(second_pool)),
+        Node::Spatial(Box::new(head)),
+    ]);
+    let loss = YoloLoss::new(5.0, 0.5);
+
+    for epoch in 0..epochs {
+        let mut total = 0.0;
+        for (image, objects) in &train {
+            let input = Tensor::Spatial(image.clone());
+            let output = model.forward(&input);
+            let (value, error) = loss.loss(&grid, output.spatial(), &grid.encode(objects));
+            let (_, gradients) = model.backward(&input, &Tensor::Spatial(error));
+            model.update(&gradients, learning_rate);
+            total += value;
+        }
+        println!(
+            "epoch {}: mean loss {:.4}",
+            epoch + 1,
+            total / train.len() as f32
+        );
+    }
+
+    let detections: Vec<_> = test
+        .iter()
+        .map(|(image, _)| {
+            let output = model.forward(&Tensor::Spatial(image.clone()));
+            non_max_suppression(&grid.decode(output.spatial(), 0.1), 0.4)
+        })
+        .collect();
+    let objects: Vec<_> = test.iter().map(|(_, objects)| objects.clone()).collect();
+    println!(
+        "mAP@0.5: {:.3}",
+        mean_average_precision(&detections, &objects, SHAPE_CLASSES.len(), 0.5)
+    );
+}
@@ -92,12 +92,26 @@ fn test_id3() {
         ("10".to_string(), (3, 0.5, Some(0.6))),
         ("11".to_string(), (3, 0.5, Some(0.8))),
     ]);
-    let sample = vec![0.4, 0.6];
-    assert_eq!(id3(sample, tree), 0.4);
+    let samples = vec![
+        vec![0.4, 0.3],
+        vec![0.4, 0.6],
+        vec![0.7, 0.2],
+        vec![0.7, 0.9],
+    ];
+    let predicted: Vec<usize> = samples
+        .into_iter()
+        .map(|x| leaf_class(id3(x, &tree)))
+        .collect();
+    assert_eq!(crate::metrics::accuracy(&predicted, &[0, 1, 2, 3]), 1.0);
+}
+
+// the quarter of the unit square a leaf value of 0.2, 0.4, 0.6 or 0.8 stands for
+fn leaf_class(y: f32) -> usize {
+    ((y - 0.2) / 0.2).round().clamp(0.0, 3.0) as usize
 }
 
-fn id3(sample: Vec<f32>, tree: DecisionTree) -> f32 {
-    let mut current_node = String::from("0");
+fn id3(sample: Vec<f32>, tree: &DecisionTree) -> f32 {
+    let mut current_node = String::from("root");
     loop {
         let split = tree.tree.get(&current_node.clone()).unwrap();
         if let Some(z) = split.2 {
@@ -150,8 +164,13 @@ fn test_id3_train() {
         data.push(x);
     }
 
-    let tree = id3_train(data);
-    assert!(id3(vec![0.1, 0.1, 0.1], tree) < 0.3);
+    let tree = id3_train(data.clone());
+    let predicted: Vec<usize> = data
+        .into_iter()
+        .map(|x| leaf_class(id3(x, &tree)))
+        .collect();
+    let actual: Vec<usize> = labels.into_iter().map(leaf_class).collect();
+    assert!(crate::metrics::accuracy(&predicted, &actual) > 0.9);
 }
 
 fn id3_train(data: Vec<Vec<f32>>) -> DecisionTree {
@@ -1,2 +1,3 @@
+pub mod metrics;
 pub mod neural_network;
 pub mod reinforcement;
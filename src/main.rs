@@ -1,7 +1,9 @@
 mod decision_tree;
+mod metrics;
 mod minimax;
 mod neural_network;
 mod search;
+mod svm;
 
 fn main() {
     println!("Hello, world!");
@@ -0,0 +1,12 @@
+// scores shared by every predictor, whatever it is built from: class labels
+// are indices, and scores, probabilities and values are anything that widens
+// to f64, so the f64 output of svm_predict, the f32 of id3 and the rows out of
+// a network all go in as they are
+pub mod classification;
+pub mod ranking;
+pub mod regression;
+mod test;
+
+pub use classification::*;
+pub use ranking::*;
+pub use regression::*;
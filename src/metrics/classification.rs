@@ -0,0 +1,176 @@
+// the index of the largest entry, the first of any tie
+pub fn argmax<T: Copy + Into<f64>>(x: &[T]) -> usize {
+    (0..x.len())
+        .rev()
+        .max_by(|i, j| x[*i].into().total_cmp(&x[*j].into()))
+        .unwrap()
+}
+
+// the class of every row of scores or of one hot targets
+pub fn classes_of<T: Copy + Into<f64>>(rows: &[Vec<T>]) -> Vec<usize> {
+    rows.iter().map(|row| argmax(row)).collect()
+}
+
+// the class of a signed output such as svm_predict gives, 1 for positive
+pub fn class_of_sign<T: Copy + Into<f64>>(x: T) -> usize {
+    (x.into() > 0.0) as usize
+}
+
+pub fn accuracy(predicted: &[usize], actual: &[usize]) -> f64 {
+    assert_eq!(predicted.len(), actual.len());
+    let correct = predicted
+        .iter()
+        .zip(actual.iter())
+        .filter(|(p, a)| p == a)
+        .count();
+    correct as f64 / predicted.len().max(1) as f64
+}
+
+// the fraction whose actual class is among the k highest scores
+pub fn top_k_accuracy<T: Copy + Into<f64>>(scores: &[Vec<T>], actual: &[usize], k: usize) -> f64 {
+    assert_eq!(scores.len(), actual.len());
+    let hits = scores
+        .iter()
+        .zip(actual.iter())
+        .filter(|(row, a)| {
+            let score = row[**a].into();
+            row.iter().filter(|x| (**x).into() > score).count() < k
+        })
+        .count();
+    hits as f64 / scores.len().max(1) as f64
+}
+
+// the mean negative log probability of the actual class, clipped away from
+// zero so a confident mistake costs a lot rather than infinity
+pub fn log_loss<T: Copy + Into<f64>>(probabilities: &[Vec<T>], actual: &[usize]) -> f64 {
+    assert_eq!(probabilities.len(), actual.len());
+    let total: f64 = probabilities
+        .iter()
+        .zip(actual.iter())
+        .map(|(row, a)| -row[*a].into().clamp(1e-15, 1.0).ln())
+        .sum();
+    total / probabilities.len().max(1) as f64
+}
+
+// how per class scores are combined: the plain mean over classes, the score of
+// the pooled counts, or the mean weighted by how many of each class there are
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Average {
+    Macro,
+    Micro,
+    Weighted,
+}
+
+// counts[actual][predicted]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfusionMatrix {
+    pub counts: Vec<Vec<usize>>,
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn harmonic(precision: f64, recall: f64) -> f64 {
+    if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    }
+}
+
+impl ConfusionMatrix {
+    pub fn new(predicted: &[usize], actual: &[usize], classes: usize) -> Self {
+        assert_eq!(predicted.len(), actual.len());
+        let mut counts = vec![vec![0; classes]; classes];
+        for (p, a) in predicted.iter().zip(actual.iter()) {
+            counts[*a][*p] += 1;
+        }
+        ConfusionMatrix { counts }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    pub fn true_positives(&self, class: usize) -> usize {
+        self.counts[class][class]
+    }
+
+    // everything predicted as the class
+    pub fn predicted(&self, class: usize) -> usize {
+        self.counts.iter().map(|row| row[class]).sum()
+    }
+
+    // everything that is the class, its support
+    pub fn actual(&self, class: usize) -> usize {
+        self.counts[class].iter().sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        let correct = (0..self.classes()).map(|c| self.true_positives(c)).sum();
+        ratio(correct, self.total())
+    }
+
+    // precision, recall and F1 of each class are zero when nothing was
+    // predicted or is in the class
+    pub fn class_precision(&self, class: usize) -> f64 {
+        ratio(self.true_positives(class), self.predicted(class))
+    }
+
+    pub fn class_recall(&self, class: usize) -> f64 {
+        ratio(self.true_positives(class), self.actual(class))
+    }
+
+    pub fn class_f1(&self, class: usize) -> f64 {
+        harmonic(self.class_precision(class), self.class_recall(class))
+    }
+
+    fn average(&self, average: Average, score: impl Fn(usize) -> f64) -> f64 {
+        let classes = 0..self.classes();
+        match average {
+            Average::Macro => classes.map(score).sum::<f64>() / self.classes().max(1) as f64,
+            // every prediction is a positive for one class and a false positive
+            // or negative elsewhere, so the pooled scores are all the accuracy
+            Average::Micro => self.accuracy(),
+            Average::Weighted => {
+                classes
+                    .map(|c| score(c) * self.actual(c) as f64)
+                    .sum::<f64>()
+                    / self.total().max(1) as f64
+            }
+        }
+    }
+
+    pub fn precision(&self, average: Average) -> f64 {
+        self.average(average, |c| self.class_precision(c))
+    }
+
+    pub fn recall(&self, average: Average) -> f64 {
+        self.average(average, |c| self.class_recall(c))
+    }
+
+    pub fn f1(&self, average: Average) -> f64 {
+        self.average(average, |c| self.class_f1(c))
+    }
+}
+
+pub fn precision(predicted: &[usize], actual: &[usize], classes: usize, average: Average) -> f64 {
+    ConfusionMatrix::new(predicted, actual, classes).precision(average)
+}
+
+pub fn recall(predicted: &[usize], actual: &[usize], classes: usize, average: Average) -> f64 {
+    ConfusionMatrix::new(predicted, actual, classes).recall(average)
+}
+
+pub fn f1_score(predicted: &[usize], actual: &[usize], classes: usize, average: Average) -> f64 {
+    ConfusionMatrix::new(predicted, actual, classes).f1(average)
+}
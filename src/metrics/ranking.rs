@@ -0,0 +1,107 @@
+// the counts of true and false positives above each distinct score, highest
+// first, so tied scores move the curves in one step rather than in whatever
+// order they happen to be listed
+fn thresholds<T: Copy + Into<f64>>(scores: &[T], positives: &[bool]) -> Vec<(f64, usize, usize)> {
+    assert_eq!(scores.len(), positives.len());
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|i, j| scores[*j].into().total_cmp(&scores[*i].into()));
+    let mut steps: Vec<(f64, usize, usize)> = Vec::new();
+    let (mut tp, mut fp) = (0, 0);
+    for (n, i) in order.iter().enumerate() {
+        if positives[*i] {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+        let score = scores[*i].into();
+        let last = n + 1 == order.len() || scores[order[n + 1]].into() != score;
+        if last {
+            steps.push((score, tp, fp));
+        }
+    }
+    steps
+}
+
+// (threshold, false positive rate, true positive rate) from the top down,
+// starting at the origin with an infinite threshold; None without both
+// positives and negatives to draw it from
+pub fn roc_curve<T: Copy + Into<f64>>(
+    scores: &[T],
+    positives: &[bool],
+) -> Option<Vec<(f64, f64, f64)>> {
+    let steps = thresholds(scores, positives);
+    let (_, p, n) = *steps.last()?;
+    if p == 0 || n == 0 {
+        return None;
+    }
+    let mut curve = vec![(f64::INFINITY, 0.0, 0.0)];
+    curve.extend(
+        steps
+            .iter()
+            .map(|(score, tp, fp)| (*score, *fp as f64 / n as f64, *tp as f64 / p as f64)),
+    );
+    Some(curve)
+}
+
+// the area under the ROC curve by the trapezium rule, the chance that a random
+// positive outscores a random negative with ties counting half
+pub fn roc_auc<T: Copy + Into<f64>>(scores: &[T], positives: &[bool]) -> Option<f64> {
+    let curve = roc_curve(scores, positives)?;
+    Some(
+        curve
+            .windows(2)
+            .map(|w| (w[1].1 - w[0].1) * (w[1].2 + w[0].2) / 2.0)
+            .sum(),
+    )
+}
+
+// the mean of roc_auc of each class against the rest over the classes that
+// have one, for rows of scores per class
+pub fn roc_auc_ovr<T: Copy + Into<f64>>(scores: &[Vec<T>], actual: &[usize]) -> Option<f64> {
+    assert_eq!(scores.len(), actual.len());
+    let classes = scores.first().map_or(0, |row| row.len());
+    let areas: Vec<f64> = (0..classes)
+        .filter_map(|c| {
+            let column: Vec<f64> = scores.iter().map(|row| row[c].into()).collect();
+            let positives: Vec<bool> = actual.iter().map(|a| *a == c).collect();
+            roc_auc(&column, &positives)
+        })
+        .collect();
+    if areas.is_empty() {
+        None
+    } else {
+        Some(areas.iter().sum::<f64>() / areas.len() as f64)
+    }
+}
+
+// (threshold, recall, precision) from the top down; None without positives
+pub fn precision_recall_curve<T: Copy + Into<f64>>(
+    scores: &[T],
+    positives: &[bool],
+) -> Option<Vec<(f64, f64, f64)>> {
+    let steps = thresholds(scores, positives);
+    let (_, p, _) = *steps.last()?;
+    if p == 0 {
+        return None;
+    }
+    Some(
+        steps
+            .iter()
+            .map(|(score, tp, fp)| (*score, *tp as f64 / p as f64, *tp as f64 / (tp + fp) as f64))
+            .collect(),
+    )
+}
+
+// the area under the precision recall curve as average precision, the
+// precision at each threshold weighted by the recall it adds, which unlike the
+// trapezium rule does not reward interpolating between far apart points
+pub fn pr_auc<T: Copy + Into<f64>>(scores: &[T], positives: &[bool]) -> Option<f64> {
+    let curve = precision_recall_curve(scores, positives)?;
+    let mut recall = 0.0;
+    let mut area = 0.0;
+    for (_, r, p) in curve {
+        area += (r - recall) * p;
+        recall = r;
+    }
+    Some(area)
+}
@@ -0,0 +1,32 @@
+fn errors<'a, T: Copy + Into<f64>>(
+    predicted: &'a [T],
+    actual: &'a [T],
+) -> impl Iterator<Item = f64> + 'a {
+    assert_eq!(predicted.len(), actual.len());
+    predicted
+        .iter()
+        .zip(actual.iter())
+        .map(|(p, a)| (*p).into() - (*a).into())
+}
+
+pub fn mean_squared_error<T: Copy + Into<f64>>(predicted: &[T], actual: &[T]) -> f64 {
+    errors(predicted, actual).map(|e| e * e).sum::<f64>() / predicted.len().max(1) as f64
+}
+
+pub fn mean_absolute_error<T: Copy + Into<f64>>(predicted: &[T], actual: &[T]) -> f64 {
+    errors(predicted, actual).map(f64::abs).sum::<f64>() / predicted.len().max(1) as f64
+}
+
+// the fraction of the variance of actual that the predictions account for, 1
+// for a perfect fit and 0 for always predicting the mean; a constant actual
+// has no variance, so anything short of a perfect fit scores 0
+pub fn r2_score<T: Copy + Into<f64>>(predicted: &[T], actual: &[T]) -> f64 {
+    let residual: f64 = errors(predicted, actual).map(|e| e * e).sum();
+    let mean = actual.iter().map(|a| (*a).into()).sum::<f64>() / actual.len().max(1) as f64;
+    let total: f64 = actual.iter().map(|a| ((*a).into() - mean).powi(2)).sum();
+    match (residual == 0.0, total == 0.0) {
+        (true, _) => 1.0,
+        (false, true) => 0.0,
+        (false, false) => 1.0 - residual / total,
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod test_metrics {
+    use crate::metrics::{
+        accuracy, argmax, class_of_sign, classes_of, f1_score, log_loss, mean_absolute_error,
+        mean_squared_error, pr_auc, precision, precision_recall_curve, r2_score, recall, roc_auc,
+        roc_auc_ovr, roc_curve, top_k_accuracy, Average, ConfusionMatrix,
+    };
+
+    fn close(x: f64, y: f64) -> bool {
+        (x - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_classes() {
+        assert_eq!(argmax(&[0.1f32, 0.7, 0.7, 0.2]), 1);
+        assert_eq!(
+            classes_of(&[vec![0.0, 1.0], vec![3.0f64, -1.0]]),
+            vec![1, 0]
+        );
+        assert_eq!(class_of_sign(-1.0f64), 0);
+        assert_eq!(class_of_sign(1.0f64), 1);
+        assert!(close(accuracy(&[0, 1, 2, 2], &[0, 1, 1, 2]), 0.75));
+    }
+
+    #[test]
+    fn test_confusion_matrix() {
+        let predicted = [0, 0, 1, 1, 1, 2, 2, 0, 2, 1];
+        let actual = [0, 0, 0, 1, 1, 1, 2, 2, 2, 2];
+        let matrix = ConfusionMatrix::new(&predicted, &actual, 3);
+        assert_eq!(
+            matrix.counts,
+            vec![vec![2, 1, 0], vec![0, 2, 1], vec![1, 1, 2]]
+        );
+        assert_eq!(matrix.total(), 10);
+        assert!(close(matrix.accuracy(), 0.6));
+        assert!(close(matrix.class_precision(0), 2.0 / 3.0));
+        assert!(close(matrix.class_recall(2), 0.5));
+        assert!(close(matrix.class_f1(1), 4.0 / 7.0));
+
+        let precisions = [2.0 / 3.0, 0.5, 2.0 / 3.0];
+        let recalls = [2.0 / 3.0, 2.0 / 3.0, 0.5];
+        let macro_precision = precisions.iter().sum::<f64>() / 3.0;
+        assert!(close(matrix.precision(Average::Macro), macro_precision));
+        let weighted_recall = (recalls[0] * 3.0 + recalls[1] * 3.0 + recalls[2] * 4.0) / 10.0;
+        assert!(close(matrix.recall(Average::Weighted), weighted_recall));
+        // pooled over the classes every score is the accuracy
+        assert!(close(
+            precision(&predicted, &actual, 3, Average::Micro),
+            0.6
+        ));
+        assert!(close(recall(&predicted, &actual, 3, Average::Micro), 0.6));
+        assert!(close(f1_score(&predicted, &actual, 3, Average::Micro), 0.6));
+        let f1s: Vec<f64> = (0..3).map(|c| matrix.class_f1(c)).collect();
+        assert!(close(
+            f1_score(&predicted, &actual, 3, Average::Macro),
+            f1s.iter().sum::<f64>() / 3.0
+        ));
+
+        // a class never predicted scores zero rather than dividing by it
+        let matrix = ConfusionMatrix::new(&[0, 0], &[0, 1], 2);
+        assert_eq!(matrix.class_precision(1), 0.0);
+        assert_eq!(matrix.class_f1(1), 0.0);
+    }
+
+    #[test]
+    fn test_scores() {
+        let scores = vec![
+            vec![0.7f32, 0.2, 0.1],
+            vec![0.3, 0.4, 0.3],
+            vec![0.5, 0.1, 0.4],
+            vec![0.1, 0.05, 0.85],
+        ];
+        let actual = [0, 0, 2, 1];
+        assert!(close(top_k_accuracy(&scores, &actual, 1), 0.25));
+        assert!(close(top_k_accuracy(&scores, &actual, 2), 0.75));
+        assert!(close(top_k_accuracy(&scores, &actual, 3), 1.0));
+
+        let expected = -(0.7f32 as f64).ln()
+            - (0.3f32 as f64).ln()
+            - (0.4f32 as f64).ln()
+            - (0.05f32 as f64).ln();
+        assert!(close(log_loss(&scores, &actual), expected / 4.0));
+        // a certain mistake is clipped rather than infinite
+        assert!(log_loss(&[vec![1.0f64, 0.0]], &[1]).is_finite());
+    }
+
+    #[test]
+    fn test_roc() {
+        let scores = [0.9f64, 0.8, 0.7, 0.6, 0.55, 0.5, 0.4, 0.3];
+        let positives = [true, true, false, true, false, true, false, false];
+        // 16 positive negative pairs of which 3 are the wrong way round
+        assert!(close(roc_auc(&scores, &positives).unwrap(), 13.0 / 16.0));
+        let curve = roc_curve(&scores, &positives).unwrap();
+        assert_eq!(curve.len(), 9);
+        assert_eq!(curve[0].1, 0.0);
+        assert_eq!(curve[8].1, 1.0);
+        assert_eq!(curve[8].2, 1.0);
+
+        // ties count half, and nothing separated is no better than chance
+        assert!(close(
+            roc_auc(&[0.5f32; 4], &[true, false, true, false]).unwrap(),
+            0.5
+        ));
+        assert!(close(
+            roc_auc(&[1.0, 0.5, 0.5, 0.0], &[true, true, false, false]).unwrap(),
+            0.875
+        ));
+        assert_eq!(roc_auc(&[0.1, 0.2], &[true, true]), None);
+
+        let scores = vec![
+            vec![0.8, 0.1, 0.1],
+            vec![0.2, 0.7, 0.1],
+            vec![0.1, 0.2, 0.7],
+            vec![0.6, 0.3, 0.1],
+        ];
+        assert!(close(roc_auc_ovr(&scores, &[0, 1, 2, 0]).unwrap(), 1.0));
+    }
+
+    #[test]
+    fn test_precision_recall() {
+        let scores = [0.9f64, 0.8, 0.7, 0.6, 0.55, 0.5, 0.4, 0.3];
+        let positives = [true, true, false, true, false, true, false, false];
+        let curve = precision_recall_curve(&scores, &positives).unwrap();
+        assert_eq!(curve[2], (0.7, 0.5, 2.0 / 3.0));
+        // precision 1, 1, 3 / 4 and 4 / 6 where each positive is recalled
+        let expected = (1.0 + 1.0 + 0.75 + 4.0 / 6.0) / 4.0;
+        assert!(close(pr_auc(&scores, &positives).unwrap(), expected));
+        assert!(close(pr_auc(&[0.9, 0.1], &[true, false]).unwrap(), 1.0));
+        assert_eq!(pr_auc(&[0.9, 0.1], &[false, false]), None);
+    }
+
+    #[test]
+    fn test_regression() {
+        let actual = [3.0f32, -0.5, 2.0, 7.0];
+        let predicted = [2.5f32, 0.0, 2.0, 8.0];
+        assert!(close(mean_squared_error(&predicted, &actual), 0.375));
+        assert!(close(mean_absolute_error(&predicted, &actual), 0.5));
+        assert!((r2_score(&predicted, &actual) - 0.948_608_137).abs() < 1e-6);
+        assert_eq!(r2_score(&actual, &actual), 1.0);
+        let mean = [3.0f64; 3];
+        assert!(close(r2_score(&mean, &[2.0, 3.0, 4.0]), 0.0));
+        assert_eq!(r2_score(&[1.0, 2.0], &[2.0, 2.0]), 0.0);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::neural_network::core::{he_initialise, Back1D, Layer1D};
+
+use super::{conv_back_nd, conv_forward_nd, dilated_size, padded_output_size, Padding1D, Window};
+
+// weights are [out_channel][in_channel][kernel] and sequences are (channels, length)
+#[derive(Clone)]
+pub struct Conv1D {
+    pub dim_in: (usize, usize),
+    pub dim_out: (usize, usize),
+    pub kernel: usize,
+    pub padding: Padding1D,
+    pub stride: usize,
+    pub dilation: usize,
+    pub weights: Vec<Vec<Vec<f32>>>,
+    pub bias: Vec<f32>,
+}
+
+impl Conv1D {
+    pub fn new(
+        dim_in: (usize, usize),
+        out_channels: usize,
+        kernel: usize,
+        padding: impl Into<Padding1D>,
+        stride: usize,
+        dilation: usize,
+    ) -> Self {
+        let padding = padding.into();
+        let span = dilated_size(kernel, dilation);
+        let sides = padding.sides(dim_in.1, span, stride);
+        Conv1D {
+            dim_in,
+            dim_out: (
+                out_channels,
+                padded_output_size(dim_in.1, span, sides, stride),
+            ),
+            kernel,
+            padding,
+            stride,
+            dilation,
+            weights: (0..out_channels)
+                .map(|_| {
+                    (0..dim_in.0)
+                        .map(|_| he_initialise(dim_in.0 * kernel, kernel))
+                        .collect()
+                })
+                .collect(),
+            bias: vec![0.0; out_channels],
+        }
+    }
+
+    pub fn sides(&self) -> (usize, usize) {
+        self.padding.sides(
+            self.dim_in.1,
+            dilated_size(self.kernel, self.dilation),
+            self.stride,
+        )
+    }
+
+    fn window(&self) -> Window {
+        Window::new(
+            vec![self.kernel],
+            vec![self.sides()],
+            vec![self.stride],
+            vec![self.dilation],
+        )
+    }
+
+    fn weight_matrix(&self) -> Vec<Vec<f32>> {
+        self.weights
+            .iter()
+            .map(|filters| filters.iter().flatten().copied().collect())
+            .collect()
+    }
+}
+
+impl Layer1D for Conv1D {
+    fn dim_in(&self) -> (usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        conv_forward_nd(
+            input,
+            &[self.dim_in.1],
+            &self.window(),
+            &self.weight_matrix(),
+            &self.bias,
+        )
+    }
+    fn back(&self, input: &[Vec<f32>], error: &[Vec<f32>]) -> Back1D {
+        let (input_error, weight_error, bias_error) = conv_back_nd(
+            input,
+            error,
+            &[self.dim_in.1],
+            &self.window(),
+            &self.weight_matrix(),
+        );
+        let filter_error = weight_error
+            .iter()
+            .flat_map(|row| row.chunks(self.kernel).map(|filter| filter.to_vec()))
+            .collect();
+        (input_error, Some(filter_error), Some(bias_error))
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters: Vec<&mut [f32]> = self
+            .weights
+            .iter_mut()
+            .flatten()
+            .map(|filter| filter.as_mut_slice())
+            .collect();
+        parameters.push(&mut self.bias);
+        parameters
+    }
+}
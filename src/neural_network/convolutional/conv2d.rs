@@ -1,14 +1,220 @@
-use crate::neural_network::core::Layer2D;
+use crate::neural_network::core::{he_initialise, matmul, stack, unstack, Back2D, Layer2D};
 
-use super::{convolution, matrix_op, matrix_rotate, pad_around, pad_right_within};
+use super::{
+    col2im, convolution, crop, dilated_size, im2col, matrix_op, matrix_rotate, pad, pad_around,
+    padded_output_size, unpad, Padding, PaddingMode, Sides,
+};
 
+// weights are [out_channel][in_channel / groups][row][col] and dimensions are
+// (channels, rows, cols); with groups the channels are split into that many
+// contiguous blocks and output block g only sees input block g; mode can be
+// changed after construction as it does not affect the output shape
 #[derive(Clone)]
-struct Conv2D {
-    dim_in: (usize, usize, usize),
-    dim_out: (usize, usize, usize),
-    filters: Vec<Vec<Vec<f32>>>,
-    padding: (usize, usize),
-    stride: (usize, usize),
+pub struct Conv2D {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+    pub kernel: (usize, usize),
+    pub padding: Padding,
+    pub mode: PaddingMode,
+    pub stride: (usize, usize),
+    pub dilation: (usize, usize),
+    pub groups: usize,
+    pub weights: Vec<Vec<Vec<Vec<f32>>>>,
+    pub bias: Vec<f32>,
+}
+
+impl Conv2D {
+    pub fn new(
+        dim_in: (usize, usize, usize),
+        out_channels: usize,
+        kernel: (usize, usize),
+        padding: impl Into<Padding>,
+        stride: (usize, usize),
+    ) -> Self {
+        Conv2D::grouped(dim_in, out_channels, kernel, padding, stride, (1, 1), 1)
+    }
+
+    // a dilation of (1, 1) is an ordinary convolution
+    pub fn grouped(
+        dim_in: (usize, usize, usize),
+        out_channels: usize,
+        kernel: (usize, usize),
+        padding: impl Into<Padding>,
+        stride: (usize, usize),
+        dilation: (usize, usize),
+        groups: usize,
+    ) -> Self {
+        assert_eq!(dim_in.0 % groups, 0);
+        assert_eq!(out_channels % groups, 0);
+        let padding = padding.into();
+        let in_channels = dim_in.0 / groups;
+        let fan_in = in_channels * kernel.0 * kernel.1;
+        let span = (
+            dilated_size(kernel.0, dilation.0),
+            dilated_size(kernel.1, dilation.1),
+        );
+        let sides = padding.sides((dim_in.1, dim_in.2), span, stride);
+        Conv2D {
+            dim_in,
+            dim_out: (
+                out_channels,
+                padded_output_size(dim_in.1, span.0, sides.0, stride.0),
+                padded_output_size(dim_in.2, span.1, sides.1, stride.1),
+            ),
+            kernel,
+            padding,
+            mode: PaddingMode::Zero,
+            stride,
+            dilation,
+            groups,
+            weights: (0..out_channels)
+                .map(|_| {
+                    (0..in_channels)
+                        .map(|_| {
+                            (0..kernel.0)
+                                .map(|_| he_initialise(fan_in, kernel.1))
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect(),
+            bias: vec![0.0; out_channels],
+        }
+    }
+
+    // input and output channels per group
+    pub fn group_channels(&self) -> (usize, usize) {
+        (self.dim_in.0 / self.groups, self.dim_out.0 / self.groups)
+    }
+
+    fn span(&self) -> (usize, usize) {
+        (
+            dilated_size(self.kernel.0, self.dilation.0),
+            dilated_size(self.kernel.1, self.dilation.1),
+        )
+    }
+
+    pub fn sides(&self) -> Sides {
+        self.padding
+            .sides((self.dim_in.1, self.dim_in.2), self.span(), self.stride)
+    }
+
+    pub fn padded_size(&self) -> (usize, usize) {
+        let ((top, bottom), (left, right)) = self.sides();
+        (self.dim_in.1 + top + bottom, self.dim_in.2 + left + right)
+    }
+
+    pub fn pad_input(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let sides = self.sides();
+        input.iter().map(|x| pad(x, sides, self.mode)).collect()
+    }
+
+    pub fn unpad_error(&self, error: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (size, sides) = ((self.dim_in.1, self.dim_in.2), self.sides());
+        error
+            .iter()
+            .map(|e| unpad(e, size, sides, self.mode))
+            .collect()
+    }
+
+    // one row per output channel, matching the row order of im2col
+    fn weight_matrix(&self) -> Vec<Vec<f32>> {
+        self.weights
+            .iter()
+            .map(|filters| filters.iter().flatten().flatten().copied().collect())
+            .collect()
+    }
+
+    fn dilate(&self, filter: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        pad_around(
+            filter.to_vec(),
+            (0, 0),
+            (self.dilation.0 - 1, self.dilation.1 - 1),
+        )
+    }
+
+    // the nested loop convolution, kept as a reference for the im2col path
+    pub fn forward_direct(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (in_channels, out_channels) = self.group_channels();
+        let input = self.pad_input(input);
+        self.weights
+            .iter()
+            .zip(self.bias.iter())
+            .enumerate()
+            .map(|(o, (filters, b))| {
+                let group = o / out_channels;
+                input[(group * in_channels)..((group + 1) * in_channels)]
+                    .iter()
+                    .zip(filters.iter())
+                    .map(|(piece, filter)| {
+                        convolution(piece, &self.dilate(filter), (0, 0), self.stride)
+                    })
+                    .reduce(|acc, x| matrix_op(&acc, &x, |y, z| y + z))
+                    .unwrap()
+                    .iter()
+                    .map(|row| row.iter().map(|x| x + b).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn back_direct(&self, input: &[Vec<Vec<f32>>], error: &[Vec<Vec<f32>>]) -> Back2D {
+        let (in_channels, out_channels) = self.group_channels();
+        let input = self.pad_input(input);
+        let span = self.span();
+        let stride_gap = (self.stride.0 - 1, self.stride.1 - 1);
+        let dilated_error: Vec<Vec<Vec<f32>>> = error
+            .iter()
+            .map(|x| pad_around(x.clone(), (0, 0), stride_gap))
+            .collect();
+        let full_error: Vec<Vec<Vec<f32>>> = error
+            .iter()
+            .map(|x| pad_around(x.clone(), (span.0 - 1, span.1 - 1), stride_gap))
+            .collect();
+
+        // a full convolution of the error with the rotated filters gives the
+        // error of the padded input, rows and columns the stride skipped over get none
+        let padded_error: Vec<Vec<Vec<f32>>> = (0..self.dim_in.0)
+            .map(|c| {
+                let group = c / in_channels;
+                let outputs = (group * out_channels)..((group + 1) * out_channels);
+                let padded_error = self.weights[outputs.clone()]
+                    .iter()
+                    .zip(full_error[outputs].iter())
+                    .map(|(filters, e)| {
+                        let filter = self.dilate(&filters[c - group * in_channels]);
+                        convolution(e, &matrix_rotate(&filter), (0, 0), (1, 1))
+                    })
+                    .reduce(|acc, x| matrix_op(&acc, &x, |y, z| y + z))
+                    .unwrap();
+                crop(&padded_error, (0, 0), self.padded_size())
+            })
+            .collect();
+        let error_by_input = self.unpad_error(&padded_error);
+
+        // each filter entry meets the input at the positions picked out by the
+        // dilated error, a dilated filter only keeps every dilation-th entry
+        let filter_error = dilated_error
+            .iter()
+            .enumerate()
+            .flat_map(|(o, e)| {
+                let group = o / out_channels;
+                input[(group * in_channels)..((group + 1) * in_channels)]
+                    .iter()
+                    .map(move |piece| {
+                        let full = crop(&convolution(piece, e, (0, 0), (1, 1)), (0, 0), span);
+                        full.iter()
+                            .step_by(self.dilation.0)
+                            .map(|row| row.iter().step_by(self.dilation.1).copied().collect())
+                            .collect()
+                    })
+            })
+            .collect();
+
+        let bias_error = error.iter().map(|e| e.iter().flatten().sum()).collect();
+
+        (error_by_input, Some(filter_error), Some(bias_error))
+    }
 }
 
 impl Layer2D for Conv2D {
@@ -19,17 +225,34 @@ impl Layer2D for Conv2D {
         self.dim_out
     }
     fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
-        self.filters
-            .iter()
-            .map(|filter| {
-                input
-                    .iter()
-                    .map(|piece| convolution(piece, filter, self.padding, self.stride))
-                    .reduce(|acc, x| matrix_op(&acc, &x, |y, z| y + z))
-                    .unwrap()
+        let (in_channels, out_channels) = self.group_channels();
+        let weights = self.weight_matrix();
+        let input = self.pad_input(input);
+        (0..self.groups)
+            .flat_map(|g| {
+                let columns = im2col(
+                    &input[(g * in_channels)..((g + 1) * in_channels)],
+                    self.kernel,
+                    (0, 0),
+                    self.stride,
+                    self.dilation,
+                );
+                matmul(
+                    &weights[(g * out_channels)..((g + 1) * out_channels)],
+                    &columns,
+                    None,
+                    false,
+                )
+            })
+            .zip(self.bias.iter())
+            .map(|(row, b)| {
+                let shifted: Vec<f32> = row.iter().map(|x| x + b).collect();
+                stack(&shifted, (self.dim_out.1, self.dim_out.2))
             })
             .collect()
     }
+
+    // the filter error is flattened to [out_channel * in_channels_per_group + in_channel][row][col]
     fn back(
         &self,
         input: &[Vec<Vec<f32>>],
@@ -39,71 +262,60 @@ impl Layer2D for Conv2D {
         Option<Vec<Vec<Vec<f32>>>>,
         Option<Vec<f32>>,
     ) {
-        let adjusted_error: Vec<Vec<Vec<f32>>> = error
-            .iter()
-            .map(|x| pad_right_within(&x, (self.stride.0 - 1, self.stride.1 - 1)))
-            .collect();
+        let (in_channels, out_channels) = self.group_channels();
+        let error_matrix: Vec<Vec<f32>> = error.iter().map(|e| unstack(e)).collect();
+        let weights = self.weight_matrix();
+        let input = self.pad_input(input);
+        let padded_size = self.padded_size();
+        let mut padded_error = Vec::new();
+        let mut filter_error = Vec::new();
+        for g in 0..self.groups {
+            let columns = im2col(
+                &input[(g * in_channels)..((g + 1) * in_channels)],
+                self.kernel,
+                (0, 0),
+                self.stride,
+                self.dilation,
+            );
+            let outputs = (g * out_channels)..((g + 1) * out_channels);
+            let group_weights = &weights[outputs.clone()];
+            let weights_t: Vec<Vec<f32>> = (0..group_weights[0].len())
+                .map(|j| group_weights.iter().map(|row| row[j]).collect())
+                .collect();
 
-        let error_by_input = input
-            .iter()
-            .map(|i| {
-                self.filters
+            padded_error.extend(col2im(
+                &matmul(&weights_t, &error_matrix[outputs.clone()], None, false),
+                (in_channels, padded_size.0, padded_size.1),
+                self.kernel,
+                (0, 0),
+                self.stride,
+                self.dilation,
+            ));
+            filter_error.extend(
+                matmul(&error_matrix[outputs], &columns, None, true)
                     .iter()
-                    // get contribution of each input to the error by
-                    // calculating the convolution of the input by the filter
-                    // then taking the entrywise product with the relevant error;
-                    // this is then convolved with the filter to get the input_error
-                    // then summed entrywise for that input
-                    .zip(adjusted_error.iter())
-                    .map(|(filter, filter_error)| {
-                        matrix_op(
-                            &convolution(i, filter, self.padding, self.stride),
-                            filter_error,
-                            |x, y| x * y,
-                        )
-                    })
-                    .zip(self.filters.iter())
-                    .map(|(error_channel, filter)| {
-                        convolution(
-                            &pad_around(
-                                error_channel,
-                                (filter.len() - 1, filter[0].len() - 1),
-                                (self.stride.0 - 1, self.stride.1 - 1),
-                            ),
-                            &matrix_rotate(filter),
-                            (0, 0),
-                            (1, 1),
-                        )
-                    })
-                    .reduce(|acc, err| matrix_op(&acc, &err, |x, y| x + y))
-                    .unwrap()
-            })
-            .collect();
+                    .flat_map(|row| row.chunks(self.kernel.0 * self.kernel.1))
+                    .map(|filter| stack(filter, self.kernel)),
+            );
+        }
+        let bias_error = error_matrix.iter().map(|row| row.iter().sum()).collect();
 
-        let filter_error = self
-            .filters
-            .iter()
-            .zip(adjusted_error.iter())
-            .map(|(filter, filter_error)| {
-                input
-                    .iter()
-                    .map(|input_piece| {
-                        convolution(
-                            input_piece,
-                            &matrix_op(
-                                &convolution(input_piece, filter, self.padding, self.stride),
-                                &filter_error,
-                                |x, y| x * y,
-                            ),
-                            self.padding,
-                            (1, 1),
-                        )
-                    })
-                    .reduce(|acc, x| matrix_op(&acc, &x, |y, z| y + z))
-                    .unwrap()
-            })
-            .collect();
+        (
+            self.unpad_error(&padded_error),
+            Some(filter_error),
+            Some(bias_error),
+        )
+    }
 
-        (error_by_input, Some(filter_error), None)
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters: Vec<&mut [f32]> = self
+            .weights
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .map(|row| row.as_mut_slice())
+            .collect();
+        parameters.push(&mut self.bias);
+        parameters
     }
 }
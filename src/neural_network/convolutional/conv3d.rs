@@ -0,0 +1,164 @@
+use crate::neural_network::core::{he_initialise, stack_volume, unstack_volume, Back3D, Layer3D};
+
+use super::{
+    conv_back_nd, conv_forward_nd, dilated_size, padded_output_size, Padding3D, Sides3D, Window,
+};
+
+fn spans(kernel: (usize, usize, usize), dilation: (usize, usize, usize)) -> (usize, usize, usize) {
+    (
+        dilated_size(kernel.0, dilation.0),
+        dilated_size(kernel.1, dilation.1),
+        dilated_size(kernel.2, dilation.2),
+    )
+}
+
+// weights are [out_channel][in_channel][depth][row][col] and volumes are
+// (channels, depth, rows, cols)
+#[derive(Clone)]
+pub struct Conv3D {
+    pub dim_in: (usize, usize, usize, usize),
+    pub dim_out: (usize, usize, usize, usize),
+    pub kernel: (usize, usize, usize),
+    pub padding: Padding3D,
+    pub stride: (usize, usize, usize),
+    pub dilation: (usize, usize, usize),
+    pub weights: Vec<Vec<Vec<Vec<Vec<f32>>>>>,
+    pub bias: Vec<f32>,
+}
+
+impl Conv3D {
+    pub fn new(
+        dim_in: (usize, usize, usize, usize),
+        out_channels: usize,
+        kernel: (usize, usize, usize),
+        padding: impl Into<Padding3D>,
+        stride: (usize, usize, usize),
+        dilation: (usize, usize, usize),
+    ) -> Self {
+        let padding = padding.into();
+        let fan_in = dim_in.0 * kernel.0 * kernel.1 * kernel.2;
+        let span = spans(kernel, dilation);
+        let sides = padding.sides((dim_in.1, dim_in.2, dim_in.3), span, stride);
+        Conv3D {
+            dim_in,
+            dim_out: (
+                out_channels,
+                padded_output_size(dim_in.1, span.0, sides.0, stride.0),
+                padded_output_size(dim_in.2, span.1, sides.1, stride.1),
+                padded_output_size(dim_in.3, span.2, sides.2, stride.2),
+            ),
+            kernel,
+            padding,
+            stride,
+            dilation,
+            weights: (0..out_channels)
+                .map(|_| {
+                    (0..dim_in.0)
+                        .map(|_| {
+                            (0..kernel.0)
+                                .map(|_| {
+                                    (0..kernel.1)
+                                        .map(|_| he_initialise(fan_in, kernel.2))
+                                        .collect()
+                                })
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect(),
+            bias: vec![0.0; out_channels],
+        }
+    }
+
+    fn size(&self) -> [usize; 3] {
+        [self.dim_in.1, self.dim_in.2, self.dim_in.3]
+    }
+
+    pub fn sides(&self) -> Sides3D {
+        self.padding.sides(
+            (self.dim_in.1, self.dim_in.2, self.dim_in.3),
+            spans(self.kernel, self.dilation),
+            self.stride,
+        )
+    }
+
+    fn window(&self) -> Window {
+        let sides = self.sides();
+        Window::new(
+            vec![self.kernel.0, self.kernel.1, self.kernel.2],
+            vec![sides.0, sides.1, sides.2],
+            vec![self.stride.0, self.stride.1, self.stride.2],
+            vec![self.dilation.0, self.dilation.1, self.dilation.2],
+        )
+    }
+
+    fn weight_matrix(&self) -> Vec<Vec<f32>> {
+        self.weights
+            .iter()
+            .map(|filters| filters.iter().flat_map(|f| unstack_volume(f)).collect())
+            .collect()
+    }
+}
+
+impl Layer3D for Conv3D {
+    fn dim_in(&self) -> (usize, usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<Vec<f32>>>]) -> Vec<Vec<Vec<Vec<f32>>>> {
+        let flat: Vec<Vec<f32>> = input.iter().map(|x| unstack_volume(x)).collect();
+        let (_, d, r, c) = self.dim_out;
+        conv_forward_nd(
+            &flat,
+            &self.size(),
+            &self.window(),
+            &self.weight_matrix(),
+            &self.bias,
+        )
+        .iter()
+        .map(|channel| stack_volume(channel, (d, r, c)))
+        .collect()
+    }
+
+    // the filter error is flattened to [out_channel * in_channels + in_channel][depth][row][col]
+    fn back(&self, input: &[Vec<Vec<Vec<f32>>>], error: &[Vec<Vec<Vec<f32>>>]) -> Back3D {
+        let flat: Vec<Vec<f32>> = input.iter().map(|x| unstack_volume(x)).collect();
+        let flat_error: Vec<Vec<f32>> = error.iter().map(|x| unstack_volume(x)).collect();
+        let (input_error, weight_error, bias_error) = conv_back_nd(
+            &flat,
+            &flat_error,
+            &self.size(),
+            &self.window(),
+            &self.weight_matrix(),
+        );
+        let (_, d, r, c) = self.dim_in;
+        let filter_error = weight_error
+            .iter()
+            .flat_map(|row| row.chunks(self.kernel.0 * self.kernel.1 * self.kernel.2))
+            .map(|filter| stack_volume(filter, self.kernel))
+            .collect();
+        (
+            input_error
+                .iter()
+                .map(|channel| stack_volume(channel, (d, r, c)))
+                .collect(),
+            Some(filter_error),
+            Some(bias_error),
+        )
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters: Vec<&mut [f32]> = self
+            .weights
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .flatten()
+            .map(|row| row.as_mut_slice())
+            .collect();
+        parameters.push(&mut self.bias);
+        parameters
+    }
+}
@@ -2,14 +2,18 @@ use rand_distr::{Distribution, Normal};
 
 use crate::neural_network::core::{stack, unstack, Activation, DEPRECATEDLayer, Function};
 
-use super::{convolution, pad_around, pad_right_within};
+use super::{
+    convolution, crop, matrix_rotate, pad, pad_around, pad_right_within, unpad, Padding,
+    PaddingMode, Sides,
+};
 
 #[derive(Clone)]
 pub struct ConvolutionLayer {
     pub dim_in: (usize, usize),
     pub dim_out: (usize, usize),
     pub kernel: (usize, usize),
-    pub padding: (usize, usize),
+    pub padding: Padding,
+    pub mode: PaddingMode,
     pub stride: (usize, usize),
     pub a: Vec<Vec<f32>>,
     pub b: Vec<f32>,
@@ -21,7 +25,7 @@ impl ConvolutionLayer {
         dim_in: (usize, usize),
         dim_out: (usize, usize),
         kernel: (usize, usize),
-        padding: (usize, usize),
+        padding: impl Into<Padding>,
         stride: (usize, usize),
         cap: Function,
     ) -> Self {
@@ -31,7 +35,8 @@ impl ConvolutionLayer {
             dim_in,
             dim_out,
             kernel,
-            padding,
+            padding: padding.into(),
+            mode: PaddingMode::Zero,
             stride,
             a: (0..kernel.0)
                 .map(|_| {
@@ -46,6 +51,10 @@ impl ConvolutionLayer {
             cap,
         }
     }
+
+    pub fn sides(&self) -> Sides {
+        self.padding.sides(self.dim_in, self.kernel, self.stride)
+    }
 }
 
 impl DEPRECATEDLayer for ConvolutionLayer {
@@ -55,19 +64,20 @@ impl DEPRECATEDLayer for ConvolutionLayer {
 
     // (row, col)
     fn forward(&self, input: &[f32]) -> Vec<f32> {
-        let data = stack(input, self.dim_in);
-        let result = convolution(&data, &self.a, self.padding, self.stride);
+        let data = pad(&stack(input, self.dim_in), self.sides(), self.mode);
+        let result = convolution(&data, &self.a, (0, 0), self.stride);
         let output: Vec<f32> = unstack(&result).iter().map(|x| x + self.b[0]).collect();
         (self.cap).activation()(&output[..])
     }
 
     fn back(&self, input: &[f32], error: &[f32]) -> (Vec<Vec<f32>>, Vec<f32>, Vec<f32>) {
-        let input_matrix = stack(input, self.dim_in);
+        let sides = self.sides();
+        let input_matrix = pad(&stack(input, self.dim_in), sides, self.mode);
         let mut error_matrix = stack(error, self.dim_out);
         if self.stride.0 > 1 || self.stride.1 > 1 {
             error_matrix = pad_right_within(&error_matrix, (self.stride.0 - 1, self.stride.1 - 1));
         }
-        let partial = convolution(&input_matrix, &error_matrix, self.padding, (1, 1));
+        let partial = convolution(&input_matrix, &error_matrix, (0, 0), (1, 1));
         let delta_bias =
             error.iter().fold(0.0, |acc, x| acc + x) / (self.dim_out.0 * self.dim_out.1) as f32;
         let new_error = convolution(
@@ -76,10 +86,18 @@ impl DEPRECATEDLayer for ConvolutionLayer {
                 (self.kernel.0 - 1, self.kernel.1 - 1),
                 (self.stride.0 - 1, self.stride.1 - 1),
             ),
-            &self.a,
+            &matrix_rotate(&self.a),
             (0, 0),
             (1, 1),
         );
+        // the error reaches the padded input, fold it back onto the positions it was read from
+        let padded_size = (input_matrix.len(), input_matrix[0].len());
+        let new_error = unpad(
+            &crop(&new_error, (0, 0), padded_size),
+            self.dim_in,
+            sides,
+            self.mode,
+        );
         (partial, vec![delta_bias], unstack(&new_error))
     }
 }
@@ -0,0 +1,202 @@
+use crate::neural_network::core::{he_initialise, matmul, stack, unstack, Layer2D};
+
+use super::{
+    col2im, dilated_size, im2col, pad, transposed_output_size, unpad, Padding, PaddingMode, Sides,
+};
+
+// the adjoint of a Conv2D with the same kernel, padding, stride and dilation, so
+// it maps a Conv2D output shape back to its input shape; weights are
+// [in_channel][out_channel][row][col] and output_padding adds rows and columns
+// at the bottom and right to pick between the input shapes a strided Conv2D
+// would map to the same output; Same pads like a Conv2D over stride times the
+// input size, so the output is that size
+#[derive(Clone)]
+pub struct ConvTranspose2D {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+    pub kernel: (usize, usize),
+    pub padding: Padding,
+    pub output_padding: (usize, usize),
+    pub stride: (usize, usize),
+    pub dilation: (usize, usize),
+    pub weights: Vec<Vec<Vec<Vec<f32>>>>,
+    pub bias: Vec<f32>,
+}
+
+// the rows and columns cropped off the full scatter of the kernels
+fn transposed_sides(
+    padding: Padding,
+    dim_in: (usize, usize, usize),
+    span: (usize, usize),
+    stride: (usize, usize),
+) -> Sides {
+    padding.sides((dim_in.1 * stride.0, dim_in.2 * stride.1), span, stride)
+}
+
+impl ConvTranspose2D {
+    pub fn new(
+        dim_in: (usize, usize, usize),
+        out_channels: usize,
+        kernel: (usize, usize),
+        padding: impl Into<Padding>,
+        output_padding: (usize, usize),
+        stride: (usize, usize),
+        dilation: (usize, usize),
+    ) -> Self {
+        assert!(output_padding.0 < stride.0 && output_padding.1 < stride.1);
+        let padding = padding.into();
+        let span = (
+            dilated_size(kernel.0, dilation.0),
+            dilated_size(kernel.1, dilation.1),
+        );
+        let sides = transposed_sides(padding, dim_in, span, stride);
+        assert!(
+            (dim_in.1 - 1) * stride.0 + span.0 + output_padding.0 > sides.0 .0 + sides.0 .1
+                && (dim_in.2 - 1) * stride.1 + span.1 + output_padding.1 > sides.1 .0 + sides.1 .1
+        );
+        let fan_in = dim_in.0 * kernel.0 * kernel.1;
+        ConvTranspose2D {
+            dim_in,
+            dim_out: (
+                out_channels,
+                transposed_output_size(dim_in.1, span.0, sides.0, stride.0, output_padding.0),
+                transposed_output_size(dim_in.2, span.1, sides.1, stride.1, output_padding.1),
+            ),
+            kernel,
+            padding,
+            output_padding,
+            stride,
+            dilation,
+            weights: (0..dim_in.0)
+                .map(|_| {
+                    (0..out_channels)
+                        .map(|_| {
+                            (0..kernel.0)
+                                .map(|_| he_initialise(fan_in, kernel.1))
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect(),
+            bias: vec![0.0; out_channels],
+        }
+    }
+
+    pub fn sides(&self) -> Sides {
+        let span = (
+            dilated_size(self.kernel.0, self.dilation.0),
+            dilated_size(self.kernel.1, self.dilation.1),
+        );
+        transposed_sides(self.padding, self.dim_in, span, self.stride)
+    }
+
+    // the output before it is cropped
+    fn padded_dim(&self) -> (usize, usize, usize) {
+        let ((top, bottom), (left, right)) = self.sides();
+        (
+            self.dim_out.0,
+            self.dim_out.1 + top + bottom,
+            self.dim_out.2 + left + right,
+        )
+    }
+
+    // one row per input channel, matching the row order of im2col on the output
+    fn weight_matrix(&self) -> Vec<Vec<f32>> {
+        self.weights
+            .iter()
+            .map(|filters| filters.iter().flatten().flatten().copied().collect())
+            .collect()
+    }
+}
+
+impl Layer2D for ConvTranspose2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim_out
+    }
+
+    // every input pixel scatters a weighted copy of the kernel into the output,
+    // which is col2im applied to the weights times the input
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let weights = self.weight_matrix();
+        let weights_t: Vec<Vec<f32>> = (0..weights[0].len())
+            .map(|j| weights.iter().map(|row| row[j]).collect())
+            .collect();
+        let input_matrix: Vec<Vec<f32>> = input.iter().map(|x| unstack(x)).collect();
+        let sides = self.sides();
+        col2im(
+            &matmul(&weights_t, &input_matrix, None, false),
+            self.padded_dim(),
+            self.kernel,
+            (0, 0),
+            self.stride,
+            self.dilation,
+        )
+        .iter()
+        .zip(self.bias.iter())
+        .map(|(channel, b)| {
+            unpad(
+                channel,
+                (self.dim_out.1, self.dim_out.2),
+                sides,
+                PaddingMode::Zero,
+            )
+            .iter()
+            .map(|row| row.iter().map(|x| x + b).collect())
+            .collect()
+        })
+        .collect()
+    }
+
+    // the filter error is flattened to [in_channel * out_channels + out_channel][row][col]
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let sides = self.sides();
+        let padded_error: Vec<Vec<Vec<f32>>> = error
+            .iter()
+            .map(|e| pad(e, sides, PaddingMode::Zero))
+            .collect();
+        let columns = im2col(
+            &padded_error,
+            self.kernel,
+            (0, 0),
+            self.stride,
+            self.dilation,
+        );
+        let input_matrix: Vec<Vec<f32>> = input.iter().map(|x| unstack(x)).collect();
+
+        let error_by_input = matmul(&self.weight_matrix(), &columns, None, false)
+            .iter()
+            .map(|row| stack(row, (self.dim_in.1, self.dim_in.2)))
+            .collect();
+        let filter_error = matmul(&input_matrix, &columns, None, true)
+            .iter()
+            .flat_map(|row| row.chunks(self.kernel.0 * self.kernel.1))
+            .map(|filter| stack(filter, self.kernel))
+            .collect();
+        let bias_error = error.iter().map(|e| e.iter().flatten().sum()).collect();
+
+        (error_by_input, Some(filter_error), Some(bias_error))
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters: Vec<&mut [f32]> = self
+            .weights
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .map(|row| row.as_mut_slice())
+            .collect();
+        parameters.push(&mut self.bias);
+        parameters
+    }
+}
@@ -0,0 +1,239 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+// transforms run in f64 so rounding stays well below the f32 inputs
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    pub fn conj(&self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    // e^(i theta)
+    pub fn expi(theta: f64) -> Self {
+        Complex::new(theta.cos(), theta.sin())
+    }
+
+    pub fn scale(&self, c: f64) -> Self {
+        Complex::new(self.re * c, self.im * c)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+// in place radix 2 transform, the length must be a power of two; the inverse
+// includes the 1/n normalisation
+pub fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    assert!(n.is_power_of_two());
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::expi(sign * 2.0 * PI / len as f64);
+        for start in (0..n).step_by(len) {
+            let mut w = Complex::new(1.0, 0.0);
+            for k in 0..(len / 2) {
+                let even = data[start + k];
+                let odd = data[start + k + len / 2] * w;
+                data[start + k] = even + odd;
+                data[start + k + len / 2] = even - odd;
+                w = w * step;
+            }
+        }
+        len <<= 1;
+    }
+    if inverse {
+        for x in data.iter_mut() {
+            *x = x.scale(1.0 / n as f64);
+        }
+    }
+}
+
+// e^(-2 pi i k / n) for k up to n / 2, shared by every row of a 2d transform
+fn twiddles(n: usize) -> Vec<Complex> {
+    (0..=(n / 2))
+        .map(|k| Complex::expi(-2.0 * PI * k as f64 / n as f64))
+        .collect()
+}
+
+// the n / 2 + 1 non redundant bins of a real signal, computed with a half length
+// complex transform of the signal packed as (even, odd) pairs
+pub fn rfft(signal: &[f32]) -> Vec<Complex> {
+    rfft_with(signal, &twiddles(signal.len()))
+}
+
+fn rfft_with(signal: &[f32], twiddles: &[Complex]) -> Vec<Complex> {
+    let n = signal.len();
+    assert!(n >= 2 && n.is_power_of_two());
+    let half = n / 2;
+    let mut packed: Vec<Complex> = signal
+        .chunks(2)
+        .map(|pair| Complex::new(pair[0] as f64, pair[1] as f64))
+        .collect();
+    fft(&mut packed, false);
+    (0..=half)
+        .map(|k| {
+            let z = packed[k % half];
+            let mirror = packed[(half - k) % half].conj();
+            let even = (z + mirror).scale(0.5);
+            let odd = (z - mirror) * Complex::new(0.0, -0.5);
+            even + twiddles[k] * odd
+        })
+        .collect()
+}
+
+// the inverse of rfft for a real signal of length n
+pub fn irfft(spectrum: &[Complex], n: usize) -> Vec<f32> {
+    irfft_with(spectrum, n, &twiddles(n))
+}
+
+fn irfft_with(spectrum: &[Complex], n: usize, twiddles: &[Complex]) -> Vec<f32> {
+    let half = n / 2;
+    assert_eq!(spectrum.len(), half + 1);
+    let mut packed: Vec<Complex> = (0..half)
+        .map(|k| {
+            let x = spectrum[k];
+            let mirror = spectrum[half - k].conj();
+            let even = (x + mirror).scale(0.5);
+            let odd = (x - mirror).scale(0.5) * twiddles[k].conj();
+            even + Complex::new(0.0, 1.0) * odd
+        })
+        .collect();
+    fft(&mut packed, true);
+    packed
+        .iter()
+        .flat_map(|z| [z.re as f32, z.im as f32])
+        .collect()
+}
+
+// real transform along the rows then a complex transform down each column,
+// giving rows x (cols / 2 + 1) bins
+pub fn rfft2(matrix: &[Vec<f32>]) -> Vec<Vec<Complex>> {
+    let twiddles = twiddles(matrix[0].len());
+    let mut spectrum: Vec<Vec<Complex>> = matrix
+        .iter()
+        .map(|row| {
+            // zero padded grids are mostly empty rows, whose transform is zero
+            if row.iter().all(|x| *x == 0.0) {
+                vec![Complex::default(); twiddles.len()]
+            } else {
+                rfft_with(row, &twiddles)
+            }
+        })
+        .collect();
+    let mut column = vec![Complex::default(); spectrum.len()];
+    for j in 0..spectrum[0].len() {
+        for (c, row) in column.iter_mut().zip(spectrum.iter()) {
+            *c = row[j];
+        }
+        fft(&mut column, false);
+        for (c, row) in column.iter().zip(spectrum.iter_mut()) {
+            row[j] = *c;
+        }
+    }
+    spectrum
+}
+
+pub fn irfft2(spectrum: &[Vec<Complex>], cols: usize) -> Vec<Vec<f32>> {
+    let mut spectrum = spectrum.to_vec();
+    let mut column = vec![Complex::default(); spectrum.len()];
+    for j in 0..spectrum[0].len() {
+        for (c, row) in column.iter_mut().zip(spectrum.iter()) {
+            *c = row[j];
+        }
+        fft(&mut column, true);
+        for (c, row) in column.iter().zip(spectrum.iter_mut()) {
+            row[j] = *c;
+        }
+    }
+    let twiddles = twiddles(cols);
+    spectrum
+        .iter()
+        .map(|row| irfft_with(row, cols, &twiddles))
+        .collect()
+}
+
+// the same cross correlation as direct_convolution, computed as a product of
+// spectra; the grid is large enough that the circular product never wraps
+pub fn fft_convolution(
+    data: &[Vec<f32>],
+    filter: &[Vec<f32>],
+    padding: (usize, usize),
+    stride: (usize, usize),
+) -> Vec<Vec<f32>> {
+    let padded = (data.len() + 2 * padding.0, data[0].len() + 2 * padding.1);
+    let kernel = (filter.len(), filter[0].len());
+    let grid = (
+        (padded.0 + kernel.0 - 1).next_power_of_two().max(2),
+        (padded.1 + kernel.1 - 1).next_power_of_two().max(2),
+    );
+
+    let mut signal = vec![vec![0.0; grid.1]; grid.0];
+    for (i, row) in data.iter().enumerate() {
+        signal[i + padding.0][padding.1..(padding.1 + row.len())].copy_from_slice(row);
+    }
+    // correlating with the filter is convolving with the filter rotated
+    let mut response = vec![vec![0.0; grid.1]; grid.0];
+    for (i, row) in filter.iter().enumerate() {
+        for (j, x) in row.iter().enumerate() {
+            response[kernel.0 - 1 - i][kernel.1 - 1 - j] = *x;
+        }
+    }
+
+    let product: Vec<Vec<Complex>> = rfft2(&signal)
+        .iter()
+        .zip(rfft2(&response).iter())
+        .map(|(a, b)| a.iter().zip(b.iter()).map(|(x, y)| *x * *y).collect())
+        .collect();
+    let full = irfft2(&product, grid.1);
+
+    (0..((padded.0 - kernel.0) / stride.0 + 1))
+        .map(|i| {
+            (0..((padded.1 - kernel.1) / stride.1 + 1))
+                .map(|j| full[i * stride.0 + kernel.0 - 1][j * stride.1 + kernel.1 - 1])
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,52 @@
+use crate::neural_network::core::{stack, unstack};
+
+use super::{col2im_nd, im2col_nd, Window};
+
+// the extent of a kernel once dilated
+pub fn dilated_size(kernel: usize, dilation: usize) -> usize {
+    dilation * (kernel - 1) + 1
+}
+
+// lays every receptive field out as a column, rows run over (channel, kernel row, kernel col)
+// and columns over the output positions; padding is read as zero rather than copied in
+pub fn im2col(
+    input: &[Vec<Vec<f32>>],
+    kernel: (usize, usize),
+    padding: (usize, usize),
+    stride: (usize, usize),
+    dilation: (usize, usize),
+) -> Vec<Vec<f32>> {
+    let flat: Vec<Vec<f32>> = input.iter().map(|channel| unstack(channel)).collect();
+    let size = [input[0].len(), input[0][0].len()];
+    im2col_nd(&flat, &size, &window(kernel, padding, stride, dilation))
+}
+
+// the adjoint of im2col, overlapping receptive fields are summed and padding is dropped
+pub fn col2im(
+    columns: &[Vec<f32>],
+    dim: (usize, usize, usize),
+    kernel: (usize, usize),
+    padding: (usize, usize),
+    stride: (usize, usize),
+    dilation: (usize, usize),
+) -> Vec<Vec<Vec<f32>>> {
+    let window = window(kernel, padding, stride, dilation);
+    col2im_nd(columns, dim.0, &[dim.1, dim.2], &window)
+        .iter()
+        .map(|channel| stack(channel, (dim.1, dim.2)))
+        .collect()
+}
+
+fn window(
+    kernel: (usize, usize),
+    padding: (usize, usize),
+    stride: (usize, usize),
+    dilation: (usize, usize),
+) -> Window {
+    Window::new(
+        vec![kernel.0, kernel.1],
+        vec![(padding.0, padding.0), (padding.1, padding.1)],
+        vec![stride.0, stride.1],
+        vec![dilation.0, dilation.1],
+    )
+}
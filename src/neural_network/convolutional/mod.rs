@@ -1,8 +1,31 @@
 pub mod cnn;
+pub mod conv1d;
 pub mod conv2d;
+pub mod conv3d;
 pub mod conv_layer;
+pub mod conv_transpose;
+pub mod fft;
+pub mod im2col;
+pub mod nd;
+pub mod normalisation;
+pub mod padding;
+pub mod pooling;
+pub mod residual;
+pub mod separable;
 mod test;
 pub mod utilities;
 
+pub use conv1d::*;
+pub use conv2d::*;
+pub use conv3d::*;
 pub use conv_layer::*;
+pub use conv_transpose::*;
+pub use fft::*;
+pub use im2col::*;
+pub use nd::*;
+pub use normalisation::*;
+pub use padding::*;
+pub use pooling::*;
+pub use residual::*;
+pub use separable::*;
 pub use utilities::*;
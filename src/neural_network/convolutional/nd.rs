@@ -0,0 +1,253 @@
+use crate::neural_network::core::matmul;
+
+use super::{dilated_size, padded_output_size};
+
+// a kernel sliding over an N dimensional grid, one entry per dimension; a
+// dilation of 1 is an ordinary convolution and padding is (before, after)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window {
+    pub kernel: Vec<usize>,
+    pub padding: Vec<(usize, usize)>,
+    pub stride: Vec<usize>,
+    pub dilation: Vec<usize>,
+}
+
+impl Window {
+    pub fn new(
+        kernel: Vec<usize>,
+        padding: Vec<(usize, usize)>,
+        stride: Vec<usize>,
+        dilation: Vec<usize>,
+    ) -> Self {
+        assert!(
+            kernel.len() == padding.len()
+                && kernel.len() == stride.len()
+                && kernel.len() == dilation.len()
+        );
+        Window {
+            kernel,
+            padding,
+            stride,
+            dilation,
+        }
+    }
+
+    pub fn output_shape(&self, size: &[usize]) -> Vec<usize> {
+        (0..size.len())
+            .map(|d| {
+                padded_output_size(
+                    size[d],
+                    dilated_size(self.kernel[d], self.dilation[d]),
+                    self.padding[d],
+                    self.stride[d],
+                )
+            })
+            .collect()
+    }
+
+    // for every kernel entry, the flat input index each output position reads,
+    // None where it reads padding; both run in row major order
+    pub fn gather(&self, size: &[usize]) -> Vec<Vec<Option<usize>>> {
+        let out = self.output_shape(size);
+        let mut rows = vec![vec![Some(0)]];
+        for d in 0..size.len() {
+            let coordinates: Vec<Vec<Option<usize>>> = (0..self.kernel[d])
+                .map(|k| {
+                    (0..out[d])
+                        .map(|p| {
+                            let x = (p * self.stride[d] + k * self.dilation[d])
+                                .wrapping_sub(self.padding[d].0);
+                            (x < size[d]).then_some(x)
+                        })
+                        .collect()
+                })
+                .collect();
+            rows = rows
+                .iter()
+                .flat_map(|row| {
+                    coordinates.iter().map(move |along| {
+                        row.iter()
+                            .flat_map(|base| {
+                                along.iter().map(move |x| match (base, x) {
+                                    (Some(b), Some(x)) => Some(b * size[d] + x),
+                                    _ => None,
+                                })
+                            })
+                            .collect()
+                    })
+                })
+                .collect();
+        }
+        rows
+    }
+}
+
+// im2col over channels holding flattened grids of the given size
+pub fn im2col_nd(input: &[Vec<f32>], size: &[usize], window: &Window) -> Vec<Vec<f32>> {
+    let gather = window.gather(size);
+    input
+        .iter()
+        .flat_map(|channel| {
+            gather.iter().map(move |row| {
+                row.iter()
+                    .map(|index| index.map_or(0.0, |i| channel[i]))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+pub fn col2im_nd(
+    columns: &[Vec<f32>],
+    channels: usize,
+    size: &[usize],
+    window: &Window,
+) -> Vec<Vec<f32>> {
+    let gather = window.gather(size);
+    let mut image = vec![vec![0.0; size.iter().product()]; channels];
+    for (r, row) in columns.iter().enumerate() {
+        let channel = &mut image[r / gather.len()];
+        for (index, value) in gather[r % gather.len()].iter().zip(row.iter()) {
+            if let Some(i) = index {
+                channel[*i] += value;
+            }
+        }
+    }
+    image
+}
+
+// the convolution shared by the 1 and 3 dimensional layers, weights have one
+// row per output channel running over (input channel, kernel entries)
+pub fn conv_forward_nd(
+    input: &[Vec<f32>],
+    size: &[usize],
+    window: &Window,
+    weights: &[Vec<f32>],
+    bias: &[f32],
+) -> Vec<Vec<f32>> {
+    matmul(weights, &im2col_nd(input, size, window), None, false)
+        .iter()
+        .zip(bias.iter())
+        .map(|(row, b)| row.iter().map(|x| x + b).collect())
+        .collect()
+}
+
+// returns the input, weight and bias errors, the weight error shaped like weights
+pub fn conv_back_nd(
+    input: &[Vec<f32>],
+    error: &[Vec<f32>],
+    size: &[usize],
+    window: &Window,
+    weights: &[Vec<f32>],
+) -> (Vec<Vec<f32>>, Vec<Vec<f32>>, Vec<f32>) {
+    let weights_t: Vec<Vec<f32>> = (0..weights[0].len())
+        .map(|j| weights.iter().map(|row| row[j]).collect())
+        .collect();
+    let input_error = col2im_nd(
+        &matmul(&weights_t, error, None, false),
+        input.len(),
+        size,
+        window,
+    );
+    let weight_error = matmul(error, &im2col_nd(input, size, window), None, true);
+    let bias_error = error.iter().map(|row| row.iter().sum()).collect();
+    (input_error, weight_error, bias_error)
+}
+
+// the flat indices inside each pooling window, given the (start, end) of the
+// windows along every dimension
+pub fn pool_boxes(size: &[usize], windows: &[Vec<(usize, usize)>]) -> Vec<Vec<usize>> {
+    let mut boxes = vec![vec![0]];
+    for d in 0..size.len() {
+        boxes = boxes
+            .iter()
+            .flat_map(|members| {
+                windows[d].iter().map(move |(start, end)| {
+                    members
+                        .iter()
+                        .flat_map(|b| (*start..*end).map(move |x| b * size[d] + x))
+                        .collect()
+                })
+            })
+            .collect();
+    }
+    boxes
+}
+
+pub fn max_pool_nd(input: &[Vec<f32>], boxes: &[Vec<usize>]) -> Vec<Vec<f32>> {
+    input
+        .iter()
+        .map(|channel| {
+            boxes
+                .iter()
+                .map(|members| {
+                    members
+                        .iter()
+                        .fold(f32::NEG_INFINITY, |acc, i| acc.max(channel[*i]))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// each error goes to the first position holding the maximum of its window
+pub fn max_pool_back_nd(
+    input: &[Vec<f32>],
+    error: &[Vec<f32>],
+    boxes: &[Vec<usize>],
+) -> Vec<Vec<f32>> {
+    input
+        .iter()
+        .zip(error.iter())
+        .map(|(channel, channel_error)| {
+            let mut result = vec![0.0; channel.len()];
+            for (members, e) in boxes.iter().zip(channel_error.iter()) {
+                let mut argmax = members[0];
+                for i in members.iter() {
+                    if channel[*i] > channel[argmax] {
+                        argmax = *i;
+                    }
+                }
+                result[argmax] += e;
+            }
+            result
+        })
+        .collect()
+}
+
+// area is the fixed divisor for padded pooling, None divides by the window size
+pub fn avg_pool_nd(input: &[Vec<f32>], boxes: &[Vec<usize>], area: Option<usize>) -> Vec<Vec<f32>> {
+    input
+        .iter()
+        .map(|channel| {
+            boxes
+                .iter()
+                .map(|members| {
+                    members.iter().map(|i| channel[*i]).sum::<f32>()
+                        / area.unwrap_or(members.len()) as f32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn avg_pool_back_nd(
+    len: usize,
+    error: &[Vec<f32>],
+    boxes: &[Vec<usize>],
+    area: Option<usize>,
+) -> Vec<Vec<f32>> {
+    error
+        .iter()
+        .map(|channel_error| {
+            let mut result = vec![0.0; len];
+            for (members, e) in boxes.iter().zip(channel_error.iter()) {
+                let share = e / area.unwrap_or(members.len()) as f32;
+                for i in members.iter() {
+                    result[*i] += share;
+                }
+            }
+            result
+        })
+        .collect()
+}
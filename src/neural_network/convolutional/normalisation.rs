@@ -0,0 +1,151 @@
+use crate::neural_network::core::{Layer2D, ParameterKind};
+
+// normalises each contiguous block of channels of a single image to zero mean
+// and unit variance and then scales and shifts every channel by gamma and beta;
+// unlike batch norm it needs no batch statistics, one group is layer norm and a
+// group per channel is instance norm
+#[derive(Clone)]
+pub struct GroupNorm {
+    pub dim: (usize, usize, usize),
+    pub groups: usize,
+    pub epsilon: f32,
+    pub gamma: Vec<f32>,
+    pub beta: Vec<f32>,
+}
+
+impl GroupNorm {
+    pub fn new(dim: (usize, usize, usize), groups: usize) -> Self {
+        assert_eq!(dim.0 % groups, 0);
+        GroupNorm {
+            dim,
+            groups,
+            epsilon: 1e-5,
+            gamma: vec![1.0; dim.0],
+            beta: vec![0.0; dim.0],
+        }
+    }
+
+    // the normalised input and the inverse standard deviation of every group
+    fn normalise(&self, input: &[Vec<Vec<f32>>]) -> (Vec<Vec<Vec<f32>>>, Vec<f32>) {
+        let size = self.dim.0 / self.groups;
+        let mut normalised = Vec::new();
+        let mut inverse = Vec::new();
+        for group in input.chunks(size) {
+            let count = (size * self.dim.1 * self.dim.2) as f32;
+            let mean = group.iter().flatten().flatten().sum::<f32>() / count;
+            let variance = group
+                .iter()
+                .flatten()
+                .flatten()
+                .map(|x| (x - mean).powi(2))
+                .sum::<f32>()
+                / count;
+            let scale = 1.0 / (variance + self.epsilon).sqrt();
+            normalised.extend(group.iter().map(|channel| {
+                channel
+                    .iter()
+                    .map(|row| row.iter().map(|x| (x - mean) * scale).collect())
+                    .collect::<Vec<Vec<f32>>>()
+            }));
+            inverse.push(scale);
+        }
+        (normalised, inverse)
+    }
+}
+
+impl Layer2D for GroupNorm {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim
+    }
+
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (normalised, _) = self.normalise(input);
+        normalised
+            .iter()
+            .zip(self.gamma.iter().zip(self.beta.iter()))
+            .map(|(channel, (g, b))| {
+                channel
+                    .iter()
+                    .map(|row| row.iter().map(|x| g * x + b).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    // the filter error is the single row of gamma errors, the bias error is beta's
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let (normalised, inverse) = self.normalise(input);
+        let gamma_error = normalised
+            .iter()
+            .zip(error.iter())
+            .map(|(x, e)| {
+                x.iter()
+                    .flatten()
+                    .zip(e.iter().flatten())
+                    .map(|(a, b)| a * b)
+                    .sum()
+            })
+            .collect();
+        let beta_error = error.iter().map(|e| e.iter().flatten().sum()).collect();
+
+        // with e the error of the normalised input, the input error is
+        // (e - mean(e) - x * mean(e * x)) / std over each group
+        let size = self.dim.0 / self.groups;
+        let mut input_error = Vec::new();
+        for (g, scale) in inverse.iter().enumerate() {
+            let channels = (g * size)..((g + 1) * size);
+            let scaled: Vec<Vec<Vec<f32>>> = error[channels.clone()]
+                .iter()
+                .zip(self.gamma[channels.clone()].iter())
+                .map(|(e, gamma)| {
+                    e.iter()
+                        .map(|row| row.iter().map(|x| x * gamma).collect())
+                        .collect()
+                })
+                .collect();
+            let x = &normalised[channels];
+            let count = (size * self.dim.1 * self.dim.2) as f32;
+            let mean = scaled.iter().flatten().flatten().sum::<f32>() / count;
+            let projection = scaled
+                .iter()
+                .flatten()
+                .flatten()
+                .zip(x.iter().flatten().flatten())
+                .map(|(e, x)| e * x)
+                .sum::<f32>()
+                / count;
+            input_error.extend(scaled.iter().zip(x.iter()).map(|(e, x)| {
+                e.iter()
+                    .zip(x.iter())
+                    .map(|(es, xs)| {
+                        es.iter()
+                            .zip(xs.iter())
+                            .map(|(ei, xi)| (ei - mean - xi * projection) * scale)
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<f32>>>()
+            }));
+        }
+
+        (input_error, Some(vec![vec![gamma_error]]), Some(beta_error))
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        vec![ParameterKind::Norm; 2]
+    }
+}
@@ -0,0 +1,187 @@
+// what the padded border holds; reflect mirrors about the edge without repeating
+// it, replicate repeats the edge and circular wraps around to the far side
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PaddingMode {
+    Zero,
+    Reflect,
+    Replicate,
+    Circular,
+}
+
+// how much to pad along each side; Same pads so the output has ceil(size / stride)
+// positions, putting any odd amount on the bottom and right
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Padding {
+    Valid,
+    Same,
+    // ((top, bottom), (left, right))
+    Explicit((usize, usize), (usize, usize)),
+}
+
+// the symmetric padding the layers have always taken
+impl From<(usize, usize)> for Padding {
+    fn from(padding: (usize, usize)) -> Self {
+        Padding::Explicit((padding.0, padding.0), (padding.1, padding.1))
+    }
+}
+
+pub type Sides = ((usize, usize), (usize, usize));
+
+impl Padding {
+    // the amount added before and after along rows and columns, kernel is the
+    // extent of the (dilated) kernel
+    pub fn sides(
+        &self,
+        size: (usize, usize),
+        kernel: (usize, usize),
+        stride: (usize, usize),
+    ) -> Sides {
+        match self {
+            Padding::Valid => ((0, 0), (0, 0)),
+            Padding::Same => (
+                same_sides(size.0, kernel.0, stride.0),
+                same_sides(size.1, kernel.1, stride.1),
+            ),
+            Padding::Explicit(rows, cols) => (*rows, *cols),
+        }
+    }
+}
+
+// the same along the length of a sequence, Explicit is (before, after)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Padding1D {
+    Valid,
+    Same,
+    Explicit(usize, usize),
+}
+
+impl From<usize> for Padding1D {
+    fn from(padding: usize) -> Self {
+        Padding1D::Explicit(padding, padding)
+    }
+}
+
+impl Padding1D {
+    pub fn sides(&self, size: usize, kernel: usize, stride: usize) -> (usize, usize) {
+        match self {
+            Padding1D::Valid => (0, 0),
+            Padding1D::Same => same_sides(size, kernel, stride),
+            Padding1D::Explicit(before, after) => (*before, *after),
+        }
+    }
+}
+
+// and across volumes, Explicit is ((front, back), (top, bottom), (left, right))
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Padding3D {
+    Valid,
+    Same,
+    Explicit((usize, usize), (usize, usize), (usize, usize)),
+}
+
+impl From<(usize, usize, usize)> for Padding3D {
+    fn from(padding: (usize, usize, usize)) -> Self {
+        Padding3D::Explicit(
+            (padding.0, padding.0),
+            (padding.1, padding.1),
+            (padding.2, padding.2),
+        )
+    }
+}
+
+pub type Sides3D = ((usize, usize), (usize, usize), (usize, usize));
+
+impl Padding3D {
+    pub fn sides(
+        &self,
+        size: (usize, usize, usize),
+        kernel: (usize, usize, usize),
+        stride: (usize, usize, usize),
+    ) -> Sides3D {
+        match self {
+            Padding3D::Valid => ((0, 0), (0, 0), (0, 0)),
+            Padding3D::Same => (
+                same_sides(size.0, kernel.0, stride.0),
+                same_sides(size.1, kernel.1, stride.1),
+                same_sides(size.2, kernel.2, stride.2),
+            ),
+            Padding3D::Explicit(depth, rows, cols) => (*depth, *rows, *cols),
+        }
+    }
+}
+
+fn same_sides(size: usize, kernel: usize, stride: usize) -> (usize, usize) {
+    let total = ((size.div_ceil(stride) - 1) * stride + kernel).saturating_sub(size);
+    (total / 2, total - total / 2)
+}
+
+// number of positions a kernel fits along one dimension once padded
+pub fn padded_output_size(
+    size: usize,
+    kernel: usize,
+    sides: (usize, usize),
+    stride: usize,
+) -> usize {
+    (size + sides.0 + sides.1 - kernel) / stride + 1
+}
+
+// the position of the input a padded position reads, None for zero padding
+fn source(position: usize, before: usize, size: usize, mode: PaddingMode) -> Option<usize> {
+    let x = position as isize - before as isize;
+    let n = size as isize;
+    if (0..n).contains(&x) {
+        return Some(x as usize);
+    }
+    match mode {
+        PaddingMode::Zero => None,
+        PaddingMode::Replicate => Some(x.clamp(0, n - 1) as usize),
+        PaddingMode::Circular => Some(x.rem_euclid(n) as usize),
+        PaddingMode::Reflect => {
+            let period = 2 * (n - 1);
+            let folded = x.rem_euclid(period.max(1));
+            Some(if folded < n { folded } else { period - folded } as usize)
+        }
+    }
+}
+
+fn sources(size: usize, sides: (usize, usize), mode: PaddingMode) -> Vec<Option<usize>> {
+    (0..(size + sides.0 + sides.1))
+        .map(|p| source(p, sides.0, size, mode))
+        .collect()
+}
+
+pub fn pad(matrix: &[Vec<f32>], sides: Sides, mode: PaddingMode) -> Vec<Vec<f32>> {
+    let rows = sources(matrix.len(), sides.0, mode);
+    let cols = sources(matrix[0].len(), sides.1, mode);
+    rows.iter()
+        .map(|r| {
+            cols.iter()
+                .map(|c| match (r, c) {
+                    (Some(i), Some(j)) => matrix[*i][*j],
+                    _ => 0.0,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// the adjoint of pad, the error at every padded position goes back to the input
+// position it was read from
+pub fn unpad(
+    error: &[Vec<f32>],
+    size: (usize, usize),
+    sides: Sides,
+    mode: PaddingMode,
+) -> Vec<Vec<f32>> {
+    let rows = sources(size.0, sides.0, mode);
+    let cols = sources(size.1, sides.1, mode);
+    let mut result = vec![vec![0.0; size.1]; size.0];
+    for (r, error_row) in rows.iter().zip(error.iter()) {
+        for (c, e) in cols.iter().zip(error_row.iter()) {
+            if let (Some(i), Some(j)) = (r, c) {
+                result[*i][*j] += e;
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,764 @@
+use crate::neural_network::core::{
+    stack, stack_volume, unstack, unstack_volume, Back1D, Back3D, Layer1D, Layer2D, Layer3D,
+};
+
+use super::{
+    avg_pool_back_nd, avg_pool_nd, max_pool_back_nd, max_pool_nd, pad, padded_output_size,
+    pool_boxes, unpad, Padding, Padding1D, Padding3D, PaddingMode, Sides, Sides3D,
+};
+
+type Windows = Vec<(usize, usize)>;
+
+// the input positions covered by each output position along one dimension;
+// windows follow the convolution conventions, padding is never selected by a
+// max and counts as zero in an average
+fn windows(size: usize, kernel: usize, sides: (usize, usize), stride: usize) -> Windows {
+    (0..padded_output_size(size, kernel, sides, stride))
+        .map(|i| {
+            let start = (i * stride).saturating_sub(sides.0);
+            let end = (i * stride + kernel).saturating_sub(sides.0).min(size);
+            (start, end)
+        })
+        .collect()
+}
+
+// split size into out windows of near equal size, neighbouring windows may overlap
+fn adaptive_windows(size: usize, out: usize) -> Windows {
+    (0..out)
+        .map(|i| (i * size / out, ((i + 1) * size).div_ceil(out)))
+        .collect()
+}
+
+fn boxes(dim: (usize, usize), rows: &[(usize, usize)], cols: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    pool_boxes(&[dim.0, dim.1], &[rows.to_vec(), cols.to_vec()])
+}
+
+fn flatten(input: &[Vec<Vec<f32>>]) -> Vec<Vec<f32>> {
+    input.iter().map(|channel| unstack(channel)).collect()
+}
+
+fn unflatten(flat: &[Vec<f32>], dim: (usize, usize)) -> Vec<Vec<Vec<f32>>> {
+    flat.iter().map(|channel| stack(channel, dim)).collect()
+}
+
+fn max_forward(
+    input: &[Vec<Vec<f32>>],
+    rows: &[(usize, usize)],
+    cols: &[(usize, usize)],
+) -> Vec<Vec<Vec<f32>>> {
+    let dim = (input[0].len(), input[0][0].len());
+    let boxes = boxes(dim, rows, cols);
+    unflatten(
+        &max_pool_nd(&flatten(input), &boxes),
+        (rows.len(), cols.len()),
+    )
+}
+
+fn max_back(
+    input: &[Vec<Vec<f32>>],
+    error: &[Vec<Vec<f32>>],
+    rows: &[(usize, usize)],
+    cols: &[(usize, usize)],
+) -> Vec<Vec<Vec<f32>>> {
+    let dim = (input[0].len(), input[0][0].len());
+    let boxes = boxes(dim, rows, cols);
+    unflatten(
+        &max_pool_back_nd(&flatten(input), &flatten(error), &boxes),
+        dim,
+    )
+}
+
+fn avg_forward(
+    input: &[Vec<Vec<f32>>],
+    rows: &[(usize, usize)],
+    cols: &[(usize, usize)],
+    area: Option<usize>,
+) -> Vec<Vec<Vec<f32>>> {
+    let dim = (input[0].len(), input[0][0].len());
+    let boxes = boxes(dim, rows, cols);
+    unflatten(
+        &avg_pool_nd(&flatten(input), &boxes, area),
+        (rows.len(), cols.len()),
+    )
+}
+
+fn avg_back(
+    dim_in: (usize, usize, usize),
+    error: &[Vec<Vec<f32>>],
+    rows: &[(usize, usize)],
+    cols: &[(usize, usize)],
+    area: Option<usize>,
+) -> Vec<Vec<Vec<f32>>> {
+    let dim = (dim_in.1, dim_in.2);
+    let boxes = boxes(dim, rows, cols);
+    unflatten(
+        &avg_pool_back_nd(dim.0 * dim.1, &flatten(error), &boxes, area),
+        dim,
+    )
+}
+
+// zero padding is left to the windows so a max never selects it, the other
+// modes pad the input and pool over the padded grid
+fn padded_windows(
+    dim_in: (usize, usize, usize),
+    kernel: (usize, usize),
+    sides: Sides,
+    stride: (usize, usize),
+    mode: PaddingMode,
+) -> (Windows, Windows) {
+    match mode {
+        PaddingMode::Zero => (
+            windows(dim_in.1, kernel.0, sides.0, stride.0),
+            windows(dim_in.2, kernel.1, sides.1, stride.1),
+        ),
+        _ => (
+            windows(
+                dim_in.1 + sides.0 .0 + sides.0 .1,
+                kernel.0,
+                (0, 0),
+                stride.0,
+            ),
+            windows(
+                dim_in.2 + sides.1 .0 + sides.1 .1,
+                kernel.1,
+                (0, 0),
+                stride.1,
+            ),
+        ),
+    }
+}
+
+fn pad_channels(input: &[Vec<Vec<f32>>], sides: Sides, mode: PaddingMode) -> Vec<Vec<Vec<f32>>> {
+    match mode {
+        PaddingMode::Zero => input.to_vec(),
+        _ => input.iter().map(|x| pad(x, sides, mode)).collect(),
+    }
+}
+
+fn unpad_channels(
+    error: Vec<Vec<Vec<f32>>>,
+    dim_in: (usize, usize, usize),
+    sides: Sides,
+    mode: PaddingMode,
+) -> Vec<Vec<Vec<f32>>> {
+    match mode {
+        PaddingMode::Zero => error,
+        _ => error
+            .iter()
+            .map(|e| unpad(e, (dim_in.1, dim_in.2), sides, mode))
+            .collect(),
+    }
+}
+
+fn pooled_dim(
+    dim_in: (usize, usize, usize),
+    kernel: (usize, usize),
+    sides: Sides,
+    stride: (usize, usize),
+) -> (usize, usize, usize) {
+    // every window has to contain at least one real position
+    assert!(
+        2 * sides.0 .0.max(sides.0 .1) <= kernel.0 && 2 * sides.1 .0.max(sides.1 .1) <= kernel.1
+    );
+    (
+        dim_in.0,
+        padded_output_size(dim_in.1, kernel.0, sides.0, stride.0),
+        padded_output_size(dim_in.2, kernel.1, sides.1, stride.1),
+    )
+}
+
+// mode can be changed after construction as it does not affect the output shape
+#[derive(Clone)]
+pub struct MaxPool2D {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+    pub kernel: (usize, usize),
+    pub padding: Padding,
+    pub mode: PaddingMode,
+    pub stride: (usize, usize),
+}
+
+impl MaxPool2D {
+    pub fn new(
+        dim_in: (usize, usize, usize),
+        kernel: (usize, usize),
+        padding: impl Into<Padding>,
+        stride: (usize, usize),
+    ) -> Self {
+        let padding = padding.into();
+        let sides = padding.sides((dim_in.1, dim_in.2), kernel, stride);
+        MaxPool2D {
+            dim_in,
+            dim_out: pooled_dim(dim_in, kernel, sides, stride),
+            kernel,
+            padding,
+            mode: PaddingMode::Zero,
+            stride,
+        }
+    }
+
+    pub fn sides(&self) -> Sides {
+        self.padding
+            .sides((self.dim_in.1, self.dim_in.2), self.kernel, self.stride)
+    }
+
+    fn windows(&self) -> (Windows, Windows) {
+        padded_windows(
+            self.dim_in,
+            self.kernel,
+            self.sides(),
+            self.stride,
+            self.mode,
+        )
+    }
+}
+
+impl Layer2D for MaxPool2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = self.windows();
+        max_forward(&pad_channels(input, self.sides(), self.mode), &rows, &cols)
+    }
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let (rows, cols) = self.windows();
+        let sides = self.sides();
+        let padded = pad_channels(input, sides, self.mode);
+        let padded_error = max_back(&padded, error, &rows, &cols);
+        (
+            unpad_channels(padded_error, self.dim_in, sides, self.mode),
+            None,
+            None,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AvgPool2D {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+    pub kernel: (usize, usize),
+    pub padding: Padding,
+    pub mode: PaddingMode,
+    pub stride: (usize, usize),
+}
+
+impl AvgPool2D {
+    pub fn new(
+        dim_in: (usize, usize, usize),
+        kernel: (usize, usize),
+        padding: impl Into<Padding>,
+        stride: (usize, usize),
+    ) -> Self {
+        let padding = padding.into();
+        let sides = padding.sides((dim_in.1, dim_in.2), kernel, stride);
+        AvgPool2D {
+            dim_in,
+            dim_out: pooled_dim(dim_in, kernel, sides, stride),
+            kernel,
+            padding,
+            mode: PaddingMode::Zero,
+            stride,
+        }
+    }
+
+    pub fn sides(&self) -> Sides {
+        self.padding
+            .sides((self.dim_in.1, self.dim_in.2), self.kernel, self.stride)
+    }
+
+    fn windows(&self) -> (Windows, Windows) {
+        padded_windows(
+            self.dim_in,
+            self.kernel,
+            self.sides(),
+            self.stride,
+            self.mode,
+        )
+    }
+}
+
+impl Layer2D for AvgPool2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = self.windows();
+        let padded = pad_channels(input, self.sides(), self.mode);
+        avg_forward(&padded, &rows, &cols, Some(self.kernel.0 * self.kernel.1))
+    }
+    fn back(
+        &self,
+        _input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let (rows, cols) = self.windows();
+        let sides = self.sides();
+        let area = Some(self.kernel.0 * self.kernel.1);
+        let grid = match self.mode {
+            PaddingMode::Zero => self.dim_in,
+            _ => (
+                self.dim_in.0,
+                self.dim_in.1 + sides.0 .0 + sides.0 .1,
+                self.dim_in.2 + sides.1 .0 + sides.1 .1,
+            ),
+        };
+        let padded_error = avg_back(grid, error, &rows, &cols, area);
+        (
+            unpad_channels(padded_error, self.dim_in, sides, self.mode),
+            None,
+            None,
+        )
+    }
+}
+
+// pools each channel down to a fixed output size whatever the input size
+#[derive(Clone)]
+pub struct AdaptiveMaxPool2D {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+}
+
+impl AdaptiveMaxPool2D {
+    pub fn new(dim_in: (usize, usize, usize), output: (usize, usize)) -> Self {
+        AdaptiveMaxPool2D {
+            dim_in,
+            dim_out: (dim_in.0, output.0, output.1),
+        }
+    }
+
+    fn windows(&self) -> (Windows, Windows) {
+        (
+            adaptive_windows(self.dim_in.1, self.dim_out.1),
+            adaptive_windows(self.dim_in.2, self.dim_out.2),
+        )
+    }
+}
+
+impl Layer2D for AdaptiveMaxPool2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = self.windows();
+        max_forward(input, &rows, &cols)
+    }
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let (rows, cols) = self.windows();
+        (max_back(input, error, &rows, &cols), None, None)
+    }
+}
+
+#[derive(Clone)]
+pub struct AdaptiveAvgPool2D {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+}
+
+impl AdaptiveAvgPool2D {
+    pub fn new(dim_in: (usize, usize, usize), output: (usize, usize)) -> Self {
+        AdaptiveAvgPool2D {
+            dim_in,
+            dim_out: (dim_in.0, output.0, output.1),
+        }
+    }
+
+    fn windows(&self) -> (Windows, Windows) {
+        (
+            adaptive_windows(self.dim_in.1, self.dim_out.1),
+            adaptive_windows(self.dim_in.2, self.dim_out.2),
+        )
+    }
+}
+
+impl Layer2D for AdaptiveAvgPool2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = self.windows();
+        avg_forward(input, &rows, &cols, None)
+    }
+    fn back(
+        &self,
+        _input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let (rows, cols) = self.windows();
+        (avg_back(self.dim_in, error, &rows, &cols, None), None, None)
+    }
+}
+
+// global pooling is adaptive pooling down to a single value per channel
+#[derive(Clone)]
+pub struct GlobalMaxPool2D(pub AdaptiveMaxPool2D);
+
+impl GlobalMaxPool2D {
+    pub fn new(dim_in: (usize, usize, usize)) -> Self {
+        GlobalMaxPool2D(AdaptiveMaxPool2D::new(dim_in, (1, 1)))
+    }
+}
+
+impl Layer2D for GlobalMaxPool2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.0.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.0.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        self.0.forward(input)
+    }
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        self.0.back(input, error)
+    }
+}
+
+#[derive(Clone)]
+pub struct GlobalAvgPool2D(pub AdaptiveAvgPool2D);
+
+impl GlobalAvgPool2D {
+    pub fn new(dim_in: (usize, usize, usize)) -> Self {
+        GlobalAvgPool2D(AdaptiveAvgPool2D::new(dim_in, (1, 1)))
+    }
+}
+
+impl Layer2D for GlobalAvgPool2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.0.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.0.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        self.0.forward(input)
+    }
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        self.0.back(input, error)
+    }
+}
+
+// every window has to contain at least one real position
+fn pooled_length(size: usize, kernel: usize, sides: (usize, usize), stride: usize) -> usize {
+    assert!(2 * sides.0.max(sides.1) <= kernel);
+    padded_output_size(size, kernel, sides, stride)
+}
+
+#[derive(Clone)]
+pub struct MaxPool1D {
+    pub dim_in: (usize, usize),
+    pub dim_out: (usize, usize),
+    pub kernel: usize,
+    pub padding: Padding1D,
+    pub stride: usize,
+}
+
+impl MaxPool1D {
+    pub fn new(
+        dim_in: (usize, usize),
+        kernel: usize,
+        padding: impl Into<Padding1D>,
+        stride: usize,
+    ) -> Self {
+        let padding = padding.into();
+        let sides = padding.sides(dim_in.1, kernel, stride);
+        MaxPool1D {
+            dim_in,
+            dim_out: (dim_in.0, pooled_length(dim_in.1, kernel, sides, stride)),
+            kernel,
+            padding,
+            stride,
+        }
+    }
+
+    pub fn sides(&self) -> (usize, usize) {
+        self.padding.sides(self.dim_in.1, self.kernel, self.stride)
+    }
+
+    fn boxes(&self) -> Vec<Vec<usize>> {
+        let along = windows(self.dim_in.1, self.kernel, self.sides(), self.stride);
+        pool_boxes(&[self.dim_in.1], &[along])
+    }
+}
+
+impl Layer1D for MaxPool1D {
+    fn dim_in(&self) -> (usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        max_pool_nd(input, &self.boxes())
+    }
+    fn back(&self, input: &[Vec<f32>], error: &[Vec<f32>]) -> Back1D {
+        (max_pool_back_nd(input, error, &self.boxes()), None, None)
+    }
+}
+
+#[derive(Clone)]
+pub struct AvgPool1D {
+    pub dim_in: (usize, usize),
+    pub dim_out: (usize, usize),
+    pub kernel: usize,
+    pub padding: Padding1D,
+    pub stride: usize,
+}
+
+impl AvgPool1D {
+    pub fn new(
+        dim_in: (usize, usize),
+        kernel: usize,
+        padding: impl Into<Padding1D>,
+        stride: usize,
+    ) -> Self {
+        let padding = padding.into();
+        let sides = padding.sides(dim_in.1, kernel, stride);
+        AvgPool1D {
+            dim_in,
+            dim_out: (dim_in.0, pooled_length(dim_in.1, kernel, sides, stride)),
+            kernel,
+            padding,
+            stride,
+        }
+    }
+
+    pub fn sides(&self) -> (usize, usize) {
+        self.padding.sides(self.dim_in.1, self.kernel, self.stride)
+    }
+
+    fn boxes(&self) -> Vec<Vec<usize>> {
+        let along = windows(self.dim_in.1, self.kernel, self.sides(), self.stride);
+        pool_boxes(&[self.dim_in.1], &[along])
+    }
+}
+
+impl Layer1D for AvgPool1D {
+    fn dim_in(&self) -> (usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        avg_pool_nd(input, &self.boxes(), Some(self.kernel))
+    }
+    fn back(&self, _input: &[Vec<f32>], error: &[Vec<f32>]) -> Back1D {
+        let input_error = avg_pool_back_nd(self.dim_in.1, error, &self.boxes(), Some(self.kernel));
+        (input_error, None, None)
+    }
+}
+
+fn volume_boxes(
+    dim_in: (usize, usize, usize, usize),
+    kernel: (usize, usize, usize),
+    sides: Sides3D,
+    stride: (usize, usize, usize),
+) -> Vec<Vec<usize>> {
+    pool_boxes(
+        &[dim_in.1, dim_in.2, dim_in.3],
+        &[
+            windows(dim_in.1, kernel.0, sides.0, stride.0),
+            windows(dim_in.2, kernel.1, sides.1, stride.1),
+            windows(dim_in.3, kernel.2, sides.2, stride.2),
+        ],
+    )
+}
+
+fn pooled_volume(
+    dim_in: (usize, usize, usize, usize),
+    kernel: (usize, usize, usize),
+    sides: Sides3D,
+    stride: (usize, usize, usize),
+) -> (usize, usize, usize, usize) {
+    (
+        dim_in.0,
+        pooled_length(dim_in.1, kernel.0, sides.0, stride.0),
+        pooled_length(dim_in.2, kernel.1, sides.1, stride.1),
+        pooled_length(dim_in.3, kernel.2, sides.2, stride.2),
+    )
+}
+
+fn flatten_volumes(input: &[Vec<Vec<Vec<f32>>>]) -> Vec<Vec<f32>> {
+    input
+        .iter()
+        .map(|channel| unstack_volume(channel))
+        .collect()
+}
+
+fn unflatten_volumes(flat: &[Vec<f32>], dim: (usize, usize, usize)) -> Vec<Vec<Vec<Vec<f32>>>> {
+    flat.iter()
+        .map(|channel| stack_volume(channel, dim))
+        .collect()
+}
+
+#[derive(Clone)]
+pub struct MaxPool3D {
+    pub dim_in: (usize, usize, usize, usize),
+    pub dim_out: (usize, usize, usize, usize),
+    pub kernel: (usize, usize, usize),
+    pub padding: Padding3D,
+    pub stride: (usize, usize, usize),
+}
+
+impl MaxPool3D {
+    pub fn new(
+        dim_in: (usize, usize, usize, usize),
+        kernel: (usize, usize, usize),
+        padding: impl Into<Padding3D>,
+        stride: (usize, usize, usize),
+    ) -> Self {
+        let padding = padding.into();
+        let sides = padding.sides((dim_in.1, dim_in.2, dim_in.3), kernel, stride);
+        MaxPool3D {
+            dim_in,
+            dim_out: pooled_volume(dim_in, kernel, sides, stride),
+            kernel,
+            padding,
+            stride,
+        }
+    }
+
+    pub fn sides(&self) -> Sides3D {
+        self.padding.sides(
+            (self.dim_in.1, self.dim_in.2, self.dim_in.3),
+            self.kernel,
+            self.stride,
+        )
+    }
+}
+
+impl Layer3D for MaxPool3D {
+    fn dim_in(&self) -> (usize, usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<Vec<f32>>>]) -> Vec<Vec<Vec<Vec<f32>>>> {
+        let boxes = volume_boxes(self.dim_in, self.kernel, self.sides(), self.stride);
+        let (_, d, r, c) = self.dim_out;
+        unflatten_volumes(&max_pool_nd(&flatten_volumes(input), &boxes), (d, r, c))
+    }
+    fn back(&self, input: &[Vec<Vec<Vec<f32>>>], error: &[Vec<Vec<Vec<f32>>>]) -> Back3D {
+        let boxes = volume_boxes(self.dim_in, self.kernel, self.sides(), self.stride);
+        let (_, d, r, c) = self.dim_in;
+        let input_error =
+            max_pool_back_nd(&flatten_volumes(input), &flatten_volumes(error), &boxes);
+        (unflatten_volumes(&input_error, (d, r, c)), None, None)
+    }
+}
+
+#[derive(Clone)]
+pub struct AvgPool3D {
+    pub dim_in: (usize, usize, usize, usize),
+    pub dim_out: (usize, usize, usize, usize),
+    pub kernel: (usize, usize, usize),
+    pub padding: Padding3D,
+    pub stride: (usize, usize, usize),
+}
+
+impl AvgPool3D {
+    pub fn new(
+        dim_in: (usize, usize, usize, usize),
+        kernel: (usize, usize, usize),
+        padding: impl Into<Padding3D>,
+        stride: (usize, usize, usize),
+    ) -> Self {
+        let padding = padding.into();
+        let sides = padding.sides((dim_in.1, dim_in.2, dim_in.3), kernel, stride);
+        AvgPool3D {
+            dim_in,
+            dim_out: pooled_volume(dim_in, kernel, sides, stride),
+            kernel,
+            padding,
+            stride,
+        }
+    }
+
+    pub fn sides(&self) -> Sides3D {
+        self.padding.sides(
+            (self.dim_in.1, self.dim_in.2, self.dim_in.3),
+            self.kernel,
+            self.stride,
+        )
+    }
+
+    fn area(&self) -> Option<usize> {
+        Some(self.kernel.0 * self.kernel.1 * self.kernel.2)
+    }
+}
+
+impl Layer3D for AvgPool3D {
+    fn dim_in(&self) -> (usize, usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<Vec<f32>>>]) -> Vec<Vec<Vec<Vec<f32>>>> {
+        let boxes = volume_boxes(self.dim_in, self.kernel, self.sides(), self.stride);
+        let (_, d, r, c) = self.dim_out;
+        let output = avg_pool_nd(&flatten_volumes(input), &boxes, self.area());
+        unflatten_volumes(&output, (d, r, c))
+    }
+    fn back(&self, _input: &[Vec<Vec<Vec<f32>>>], error: &[Vec<Vec<Vec<f32>>>]) -> Back3D {
+        let boxes = volume_boxes(self.dim_in, self.kernel, self.sides(), self.stride);
+        let (_, d, r, c) = self.dim_in;
+        let input_error = avg_pool_back_nd(d * r * c, &flatten_volumes(error), &boxes, self.area());
+        (unflatten_volumes(&input_error, (d, r, c)), None, None)
+    }
+}
@@ -0,0 +1,253 @@
+use crate::neural_network::core::{Flatten, Layer, Layer2D, ParameterKind, ReLU2D, Softmax};
+use crate::neural_network::linear::linear::Linear;
+use crate::neural_network::sequential::{Node, Sequential};
+
+use super::{Conv2D, GlobalAvgPool2D, GroupNorm};
+
+// relu(main(x) + shortcut(x)) where both branches are chains of layers and an
+// empty shortcut is the identity; the parameters are those of the main branch
+// followed by those of the shortcut
+pub struct ResidualBlock {
+    pub main: Vec<Box<dyn Layer2D>>,
+    pub shortcut: Vec<Box<dyn Layer2D>>,
+    pub relu: ReLU2D,
+}
+
+// conv -> norm, with the output shape of the conv
+fn conv_norm(
+    dim_in: (usize, usize, usize),
+    out_channels: usize,
+    kernel: usize,
+    stride: usize,
+    groups: usize,
+) -> Vec<Box<dyn Layer2D>> {
+    let conv = Conv2D::new(
+        dim_in,
+        out_channels,
+        (kernel, kernel),
+        (kernel / 2, kernel / 2),
+        (stride, stride),
+    );
+    let norm = GroupNorm::new(conv.dim_out, groups);
+    vec![Box::new(conv), Box::new(norm)]
+}
+
+fn then_relu(mut layers: Vec<Box<dyn Layer2D>>) -> Vec<Box<dyn Layer2D>> {
+    let dim = layers[layers.len() - 1].dim_out();
+    layers.push(Box::new(ReLU2D::new(dim)));
+    layers
+}
+
+impl ResidualBlock {
+    pub fn new(main: Vec<Box<dyn Layer2D>>, shortcut: Vec<Box<dyn Layer2D>>) -> Self {
+        let dim_in = main[0].dim_in();
+        let dim_out = main[main.len() - 1].dim_out();
+        for pair in main.windows(2).chain(shortcut.windows(2)) {
+            assert_eq!(pair[0].dim_out(), pair[1].dim_in());
+        }
+        match (shortcut.first(), shortcut.last()) {
+            (Some(first), Some(last)) => {
+                assert_eq!((first.dim_in(), last.dim_out()), (dim_in, dim_out))
+            }
+            _ => assert_eq!(dim_in, dim_out),
+        }
+        ResidualBlock {
+            main,
+            shortcut,
+            relu: ReLU2D::new(dim_out),
+        }
+    }
+
+    // the projection shortcut, a strided 1x1 conv and a norm, whenever the shape
+    // changes and the identity otherwise
+    fn shortcut(
+        dim_in: (usize, usize, usize),
+        dim_out: (usize, usize, usize),
+        stride: usize,
+        groups: usize,
+    ) -> Vec<Box<dyn Layer2D>> {
+        if dim_in == dim_out {
+            Vec::new()
+        } else {
+            conv_norm(dim_in, dim_out.0, 1, stride, groups)
+        }
+    }
+
+    // two 3x3 convs, the first strided; groups is the number of norm groups
+    pub fn basic(
+        dim_in: (usize, usize, usize),
+        out_channels: usize,
+        stride: usize,
+        groups: usize,
+    ) -> Self {
+        let mut main = then_relu(conv_norm(dim_in, out_channels, 3, stride, groups));
+        let hidden = main[main.len() - 1].dim_out();
+        main.extend(conv_norm(hidden, out_channels, 3, 1, groups));
+        let dim_out = main[main.len() - 1].dim_out();
+        ResidualBlock::new(
+            main,
+            ResidualBlock::shortcut(dim_in, dim_out, stride, groups),
+        )
+    }
+
+    // a 1x1 conv down to width channels, a strided 3x3 conv and a 1x1 conv up
+    // to 4 * width channels
+    pub fn bottleneck(
+        dim_in: (usize, usize, usize),
+        width: usize,
+        stride: usize,
+        groups: usize,
+    ) -> Self {
+        let mut main = then_relu(conv_norm(dim_in, width, 1, 1, groups));
+        let reduced = main[main.len() - 1].dim_out();
+        main.extend(then_relu(conv_norm(reduced, width, 3, stride, groups)));
+        let hidden = main[main.len() - 1].dim_out();
+        main.extend(conv_norm(hidden, 4 * width, 1, 1, groups));
+        let dim_out = main[main.len() - 1].dim_out();
+        ResidualBlock::new(
+            main,
+            ResidualBlock::shortcut(dim_in, dim_out, stride, groups),
+        )
+    }
+
+    // the pre activation sum of the two branches with the activations of each
+    fn branches(&self, input: &[Vec<Vec<f32>>]) -> BranchActivations {
+        let main = chain(&self.main, input);
+        let shortcut = chain(&self.shortcut, input);
+        let sum = main[main.len() - 1]
+            .iter()
+            .zip(shortcut[shortcut.len() - 1].iter())
+            .map(|(a, b)| {
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(x, y)| x.iter().zip(y.iter()).map(|(p, q)| p + q).collect())
+                    .collect()
+            })
+            .collect();
+        (sum, main, shortcut)
+    }
+}
+
+type BranchActivations = (
+    Vec<Vec<Vec<f32>>>,
+    Vec<Vec<Vec<Vec<f32>>>>,
+    Vec<Vec<Vec<Vec<f32>>>>,
+);
+
+// the input to every layer followed by the output
+fn chain(layers: &[Box<dyn Layer2D>], input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<Vec<f32>>>> {
+    let mut activations = vec![input.to_vec()];
+    for layer in layers {
+        activations.push(layer.forward(&activations[activations.len() - 1]));
+    }
+    activations
+}
+
+fn chain_gradients(
+    layers: &[Box<dyn Layer2D>],
+    activations: &[Vec<Vec<Vec<f32>>>],
+    error: &[Vec<Vec<f32>>],
+) -> (Vec<Vec<Vec<f32>>>, Vec<Vec<f32>>) {
+    let mut error = error.to_vec();
+    let mut rows = Vec::new();
+    for (layer, input) in layers.iter().zip(activations.iter()).rev() {
+        let (input_error, layer_rows) = layer.gradients(input, &error);
+        rows.insert(0, layer_rows);
+        error = input_error;
+    }
+    (error, rows.concat())
+}
+
+impl Layer2D for ResidualBlock {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.main[0].dim_in()
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.relu.dim
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        self.relu.forward(&self.branches(input).0)
+    }
+
+    // only the input error, the parameter gradients come from gradients
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        (self.gradients(input, error).0, None, None)
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        self.main
+            .iter_mut()
+            .chain(self.shortcut.iter_mut())
+            .flat_map(|layer| layer.parameters())
+            .collect()
+    }
+
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        self.main
+            .iter_mut()
+            .chain(self.shortcut.iter_mut())
+            .flat_map(|layer| layer.parameter_kinds())
+            .collect()
+    }
+
+    // the error after the relu goes down both branches and their input errors add
+    fn gradients(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (Vec<Vec<Vec<f32>>>, Vec<Vec<f32>>) {
+        let (sum, main, shortcut) = self.branches(input);
+        let (error, _, _) = self.relu.back(&sum, error);
+        let (main_error, mut rows) = chain_gradients(&self.main, &main, &error);
+        let (shortcut_error, shortcut_rows) = chain_gradients(&self.shortcut, &shortcut, &error);
+        rows.extend(shortcut_rows);
+        let input_error = main_error
+            .iter()
+            .zip(shortcut_error.iter())
+            .map(|(a, b)| {
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(x, y)| x.iter().zip(y.iter()).map(|(p, q)| p + q).collect())
+                    .collect()
+            })
+            .collect();
+        (input_error, rows)
+    }
+}
+
+// the CIFAR ResNet of He et al. with 6n + 2 layers: a 3x3 conv to 16 channels,
+// three stages of n basic blocks at 16, 32 and 64 channels with the last two
+// halving the size, then global average pooling and a softmax classifier
+pub fn cifar_resnet(dim_in: (usize, usize, usize), blocks: usize, classes: usize) -> Sequential {
+    let groups = 4;
+    let mut nodes: Vec<Node> = then_relu(conv_norm(dim_in, 16, 3, 1, groups))
+        .into_iter()
+        .map(Node::Spatial)
+        .collect();
+    let mut dim = (16, dim_in.1, dim_in.2);
+    for (stage, channels) in [16, 32, 64].iter().enumerate() {
+        for b in 0..blocks {
+            let stride = if stage > 0 && b == 0 { 2 } else { 1 };
+            let block = ResidualBlock::basic(dim, *channels, stride, groups);
+            dim = block.dim_out();
+            nodes.push(Node::Spatial(Box::new(block)));
+        }
+    }
+    let pool = GlobalAvgPool2D::new(dim);
+    let flatten = Flatten::new(pool.dim_out());
+    let linear = Linear::new(flatten.dim_out(), classes);
+    let softmax = Softmax::new(linear.dim_out());
+    nodes.push(Node::Spatial(Box::new(pool)));
+    nodes.push(Node::Flatten(flatten));
+    nodes.push(Node::Dense(Box::new(linear)));
+    nodes.push(Node::Dense(Box::new(softmax)));
+    Sequential::new(nodes)
+}
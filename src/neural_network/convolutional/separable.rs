@@ -0,0 +1,85 @@
+use crate::neural_network::core::{Layer2D, ParameterKind};
+
+use super::{Conv2D, Padding};
+
+// a depthwise convolution, one filter per input channel, followed by a 1x1
+// pointwise convolution that mixes the channels
+#[derive(Clone)]
+pub struct SeparableConv2D {
+    pub depthwise: Conv2D,
+    pub pointwise: Conv2D,
+}
+
+impl SeparableConv2D {
+    pub fn new(
+        dim_in: (usize, usize, usize),
+        out_channels: usize,
+        kernel: (usize, usize),
+        padding: impl Into<Padding>,
+        stride: (usize, usize),
+        dilation: (usize, usize),
+    ) -> Self {
+        let depthwise = Conv2D::grouped(
+            dim_in, dim_in.0, kernel, padding, stride, dilation, dim_in.0,
+        );
+        let pointwise = Conv2D::new(depthwise.dim_out, out_channels, (1, 1), (0, 0), (1, 1));
+        SeparableConv2D {
+            depthwise,
+            pointwise,
+        }
+    }
+}
+
+impl Layer2D for SeparableConv2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.depthwise.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.pointwise.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        self.pointwise.forward(&self.depthwise.forward(input))
+    }
+
+    // filter and bias errors are the depthwise ones followed by the pointwise ones
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let hidden = self.depthwise.forward(input);
+        let (hidden_error, pointwise_filters, pointwise_bias) = self.pointwise.back(&hidden, error);
+        let (input_error, mut filters, mut bias) = self.depthwise.back(input, &hidden_error);
+        filters.as_mut().unwrap().extend(pointwise_filters.unwrap());
+        bias.as_mut().unwrap().extend(pointwise_bias.unwrap());
+        (input_error, filters, bias)
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters = self.depthwise.parameters();
+        parameters.extend(self.pointwise.parameters());
+        parameters
+    }
+
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        let mut kinds = self.depthwise.parameter_kinds();
+        kinds.extend(self.pointwise.parameter_kinds());
+        kinds
+    }
+
+    fn gradients(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (Vec<Vec<Vec<f32>>>, Vec<Vec<f32>>) {
+        let hidden = self.depthwise.forward(input);
+        let (hidden_error, pointwise) = self.pointwise.gradients(&hidden, error);
+        let (input_error, mut rows) = self.depthwise.gradients(input, &hidden_error);
+        rows.extend(pointwise);
+        (input_error, rows)
+    }
+}
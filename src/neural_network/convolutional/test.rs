@@ -2,11 +2,132 @@
 mod test_conv {
     use crate::neural_network::{
         convolutional::{
-            convolution, matrix_rotate, pad_around, pad_right_within, ConvolutionLayer,
+            cifar_resnet, col2im, convolution, crop, direct_convolution, fft_convolution, im2col,
+            irfft, irfft2, matrix_rotate, output_size, pad, pad_around, pad_right_within, rfft,
+            rfft2, unpad, AdaptiveAvgPool2D, AdaptiveMaxPool2D, AvgPool1D, AvgPool2D, AvgPool3D,
+            Conv1D, Conv2D, Conv3D, ConvTranspose2D, ConvolutionLayer, GlobalAvgPool2D,
+            GlobalMaxPool2D, GroupNorm, MaxPool1D, MaxPool2D, MaxPool3D, Padding, Padding1D,
+            Padding3D, PaddingMode, ResidualBlock, SeparableConv2D, Window, FFT_KERNEL_AREA,
         },
-        core::{stack, DEPRECATEDLayer, Function},
+        core::{stack, DEPRECATEDLayer, Function, Layer1D, Layer2D, Layer3D},
+        sequential::Tensor,
     };
 
+    fn image(dim: (usize, usize, usize), offset: f32) -> Vec<Vec<Vec<f32>>> {
+        (0..dim.0)
+            .map(|c| {
+                (0..dim.1)
+                    .map(|i| {
+                        (0..dim.2)
+                            .map(|j| (offset + (c * dim.1 * dim.2 + i * dim.2 + j) as f32).sin())
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn dot(a: &[Vec<Vec<f32>>], b: &[Vec<Vec<f32>>]) -> f32 {
+        a.iter()
+            .flatten()
+            .flatten()
+            .zip(b.iter().flatten().flatten())
+            .map(|(x, y)| x * y)
+            .sum()
+    }
+
+    // compares the input gradient of sum(error * forward(input)) with central differences
+    fn check_input_gradient(layer: &impl Layer2D) {
+        let epsilon = 1e-3;
+        let input = image(layer.dim_in(), 0.0);
+        let error = image(layer.dim_out(), 1.0);
+        let (input_error, _, _) = layer.back(&input, &error);
+        for c in 0..input.len() {
+            for i in 0..input[c].len() {
+                for j in 0..input[c][i].len() {
+                    let mut up = input.clone();
+                    let mut down = input.clone();
+                    up[c][i][j] += epsilon;
+                    down[c][i][j] -= epsilon;
+                    let expected = (dot(&layer.forward(&up), &error)
+                        - dot(&layer.forward(&down), &error))
+                        / (2.0 * epsilon);
+                    assert!((expected - input_error[c][i][j]).abs() < 1e-2);
+                }
+            }
+        }
+    }
+
+    // compares the rows from gradients() with central differences over parameters()
+    fn check_parameter_gradients(layer: &mut impl Layer2D) {
+        check_parameter_gradients_at(layer, 1e-2);
+    }
+
+    // layers with norms between convs curve too much for the default step
+    fn check_parameter_gradients_at(layer: &mut impl Layer2D, epsilon: f32) {
+        let input = image(layer.dim_in(), 0.0);
+        let error = image(layer.dim_out(), 1.0);
+        let (_, rows) = layer.gradients(&input, &error);
+        let sizes: Vec<usize> = layer.parameters().iter().map(|x| x.len()).collect();
+        assert_eq!(rows.iter().map(|x| x.len()).collect::<Vec<_>>(), sizes);
+        for (r, row) in rows.iter().enumerate() {
+            for (j, actual) in row.iter().enumerate() {
+                layer.parameters()[r][j] += epsilon;
+                let up = dot(&layer.forward(&input), &error);
+                layer.parameters()[r][j] -= 2.0 * epsilon;
+                let down = dot(&layer.forward(&input), &error);
+                layer.parameters()[r][j] += epsilon;
+                assert!(((up - down) / (2.0 * epsilon) - actual).abs() < 1e-2);
+            }
+        }
+    }
+
+    fn check_conv2d_gradients(conv: &Conv2D) {
+        let epsilon = 1e-2;
+        let input = image(conv.dim_in, 0.0);
+        let error = image(conv.dim_out, 1.0);
+        let (input_error, filter_error, bias_error) = conv.back(&input, &error);
+        let (filter_error, bias_error) = (filter_error.unwrap(), bias_error.unwrap());
+
+        for c in 0..conv.dim_in.0 {
+            for i in 0..conv.dim_in.1 {
+                for j in 0..conv.dim_in.2 {
+                    let mut up = input.clone();
+                    let mut down = input.clone();
+                    up[c][i][j] += epsilon;
+                    down[c][i][j] -= epsilon;
+                    let expected = (dot(&conv.forward(&up), &error)
+                        - dot(&conv.forward(&down), &error))
+                        / (2.0 * epsilon);
+                    assert!((expected - input_error[c][i][j]).abs() < 1e-2);
+                }
+            }
+        }
+        let mut perturbed = conv.clone();
+        for o in 0..conv.dim_out.0 {
+            for c in 0..conv.weights[o].len() {
+                let filter = &filter_error[o * conv.weights[o].len() + c];
+                for (i, row) in filter.iter().enumerate() {
+                    for (j, actual) in row.iter().enumerate() {
+                        perturbed.weights[o][c][i][j] += epsilon;
+                        let up = dot(&perturbed.forward(&input), &error);
+                        perturbed.weights[o][c][i][j] -= 2.0 * epsilon;
+                        let down = dot(&perturbed.forward(&input), &error);
+                        perturbed.weights[o][c][i][j] += epsilon;
+                        let expected = (up - down) / (2.0 * epsilon);
+                        assert!((expected - actual).abs() < 1e-2);
+                    }
+                }
+            }
+            perturbed.bias[o] += epsilon;
+            let up = dot(&perturbed.forward(&input), &error);
+            perturbed.bias[o] -= 2.0 * epsilon;
+            let down = dot(&perturbed.forward(&input), &error);
+            perturbed.bias[o] += epsilon;
+            assert!(((up - down) / (2.0 * epsilon) - bias_error[o]).abs() < 1e-2);
+        }
+    }
+
     #[test]
     fn test_pad_right_within() {
         let mut matrix = Vec::new();
@@ -89,4 +210,948 @@ mod test_conv {
         let x_rotated = vec![vec![4.0, 3.0], vec![2.0, 1.0]];
         assert_eq!(matrix_rotate(&x), x_rotated)
     }
+
+    #[test]
+    fn test_output_size() {
+        assert_eq!(output_size(4, 2, 1, 2), 3);
+        assert_eq!(output_size(5, 3, 0, 2), 2);
+        assert_eq!(output_size(28, 5, 2, 1), 28);
+    }
+
+    #[test]
+    fn test_crop() {
+        let x = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        assert_eq!(crop(&x, (1, 1), (2, 2)), [[5.0, 6.0], [0.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_conv2d_shape() {
+        let conv = Conv2D::new((3, 7, 6), 4, (3, 2), (1, 0), (2, 2));
+        assert_eq!(conv.dim_out(), (4, 4, 3));
+        assert_eq!(conv.weights.len(), 4);
+        assert_eq!(conv.weights[0].len(), 3);
+        assert_eq!(conv.weights[0][0].len(), 3);
+        assert_eq!(conv.weights[0][0][0].len(), 2);
+        let output = conv.forward(&image((3, 7, 6), 0.0));
+        assert_eq!(output.len(), 4);
+        assert_eq!(output[0].len(), 4);
+        assert_eq!(output[0][0].len(), 3);
+    }
+
+    #[test]
+    fn test_conv2d_forward() {
+        let mut conv = Conv2D::new((2, 3, 3), 2, (2, 2), (0, 0), (1, 1));
+        conv.weights = vec![
+            vec![
+                vec![vec![1.0, 0.0], vec![0.0, 0.0]],
+                vec![vec![0.0, 0.0], vec![0.0, 1.0]],
+            ],
+            vec![vec![vec![1.0, 1.0], vec![1.0, 1.0]], vec![vec![0.0; 2]; 2]],
+        ];
+        conv.bias = vec![0.5, -1.0];
+        let input = vec![
+            vec![
+                vec![1.0, 2.0, 3.0],
+                vec![4.0, 5.0, 6.0],
+                vec![7.0, 8.0, 9.0],
+            ],
+            vec![
+                vec![1.0, 1.0, 1.0],
+                vec![1.0, 2.0, 1.0],
+                vec![1.0, 1.0, 3.0],
+            ],
+        ];
+        let output = conv.forward(&input);
+        assert_eq!(output[0], [[3.5, 3.5], [5.5, 8.5]]);
+        assert_eq!(output[1], [[11.0, 15.0], [23.0, 27.0]]);
+    }
+
+    #[test]
+    fn test_conv2d_back() {
+        check_conv2d_gradients(&Conv2D::new((2, 5, 4), 3, (2, 2), (0, 0), (1, 1)));
+        check_conv2d_gradients(&Conv2D::new((2, 6, 5), 2, (3, 2), (1, 1), (2, 2)));
+        check_conv2d_gradients(&Conv2D::new((1, 7, 7), 2, (3, 3), (2, 0), (3, 2)));
+        check_conv2d_gradients(&Conv2D::grouped(
+            (2, 8, 7),
+            2,
+            (2, 3),
+            (1, 1),
+            (1, 2),
+            (3, 2),
+            1,
+        ));
+        check_conv2d_gradients(&Conv2D::grouped(
+            (4, 6, 6),
+            2,
+            (3, 3),
+            (1, 1),
+            (2, 2),
+            (1, 1),
+            2,
+        ));
+    }
+
+    #[test]
+    fn test_grouped_conv2d_matches_split() {
+        let conv = Conv2D::grouped((4, 6, 5), 6, (3, 2), (1, 0), (1, 1), (1, 2), 2);
+        let input = image(conv.dim_in, 0.0);
+        let output = conv.forward(&input);
+        for g in 0..2 {
+            let mut half = Conv2D::grouped((2, 6, 5), 3, (3, 2), (1, 0), (1, 1), (1, 2), 1);
+            half.weights = conv.weights[(3 * g)..(3 * g + 3)].to_vec();
+            half.bias = conv.bias[(3 * g)..(3 * g + 3)].to_vec();
+            let expected = half.forward(&input[(2 * g)..(2 * g + 2)]);
+            assert_close(&output[(3 * g)..(3 * g + 3)], &expected);
+        }
+    }
+
+    #[test]
+    fn test_dilated_conv2d_shape() {
+        let conv = Conv2D::grouped((1, 9, 9), 1, (3, 3), (0, 2), (1, 1), (2, 2), 1);
+        assert_eq!(conv.dim_out, (1, 5, 9));
+    }
+
+    #[test]
+    fn test_separable_conv2d() {
+        let mut layer = SeparableConv2D::new((3, 7, 6), 4, (3, 3), (2, 2), (1, 2), (2, 2));
+        assert_eq!(layer.depthwise.weights[0].len(), 1);
+        assert_eq!(layer.dim_out(), (4, 7, 3));
+        check_input_gradient(&layer);
+        check_parameter_gradients(&mut layer);
+    }
+
+    #[test]
+    fn test_conv_transpose2d_shape() {
+        let layer = ConvTranspose2D::new((2, 5, 5), 3, (3, 3), (1, 1), (1, 1), (2, 2), (1, 1));
+        assert_eq!(layer.dim_out, (3, 10, 10));
+        let layer = ConvTranspose2D::new((1, 4, 6), 1, (3, 2), (0, 1), (0, 0), (1, 3), (2, 1));
+        assert_eq!(layer.dim_out, (1, 8, 15));
+        assert_eq!(layer.forward(&image(layer.dim_in, 0.0))[0].len(), 8);
+    }
+
+    #[test]
+    fn test_conv_transpose2d_is_conv2d_adjoint() {
+        let layer = ConvTranspose2D::new((3, 4, 5), 2, (3, 2), (1, 1), (1, 0), (2, 2), (1, 2));
+        let mut conv = Conv2D::grouped(layer.dim_out, 3, (3, 2), (1, 1), (2, 2), (1, 2), 1);
+        assert_eq!(conv.dim_out, layer.dim_in);
+        conv.weights = layer.weights.clone();
+        let input = image(layer.dim_in, 0.0);
+        let (expected, _, _) = conv.back(&image(conv.dim_in, 1.0), &input);
+        assert_close(&layer.forward(&input), &expected);
+    }
+
+    #[test]
+    fn test_conv_transpose2d_back() {
+        for mut layer in [
+            ConvTranspose2D::new((2, 3, 4), 3, (2, 2), (0, 0), (0, 0), (1, 1), (1, 1)),
+            ConvTranspose2D::new((2, 4, 3), 2, (3, 3), (1, 1), (1, 0), (2, 2), (1, 1)),
+            ConvTranspose2D::new((1, 3, 3), 2, (2, 3), (1, 2), (0, 1), (1, 2), (2, 1)),
+        ] {
+            check_input_gradient(&layer);
+            check_parameter_gradients(&mut layer);
+        }
+    }
+
+    fn assert_close(a: &[Vec<Vec<f32>>], b: &[Vec<Vec<f32>>]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert_eq!(x.len(), y.len());
+            for (i, j) in x.iter().zip(y.iter()) {
+                assert_eq!(i.len(), j.len());
+                assert!(i.iter().zip(j.iter()).all(|(p, q)| (p - q).abs() < 1e-4));
+            }
+        }
+    }
+
+    #[test]
+    fn test_im2col() {
+        let input = vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]]];
+        let columns = im2col(&input, (2, 2), (1, 1), (2, 2), (1, 1));
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[0], [0.0, 0.0, 0.0, 4.0]);
+        assert_eq!(columns[3], [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_col2im_is_adjoint() {
+        let dim = (2, 5, 6);
+        let (kernel, padding, stride, dilation) = ((3, 2), (1, 2), (2, 3), (2, 1));
+        let input = image(dim, 0.0);
+        let columns = im2col(&input, kernel, padding, stride, dilation);
+        let other: Vec<Vec<f32>> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, row)| (0..row.len()).map(|j| ((i * 7 + j) as f32).cos()).collect())
+            .collect();
+        let image_side = dot(
+            &input,
+            &col2im(&other, dim, kernel, padding, stride, dilation),
+        );
+        let column_side = dot(&[columns], &[other]);
+        assert!((image_side - column_side).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_conv2d_matches_direct() {
+        for conv in [
+            Conv2D::new((3, 8, 8), 4, (3, 3), (1, 1), (1, 1)),
+            Conv2D::new((2, 7, 9), 3, (2, 3), (0, 2), (2, 2)),
+            Conv2D::new((1, 6, 6), 2, (5, 5), (2, 2), (3, 1)),
+            Conv2D::grouped((2, 9, 8), 2, (3, 2), (1, 0), (1, 2), (2, 3), 1),
+            Conv2D::grouped((4, 7, 7), 6, (3, 3), (2, 2), (2, 1), (2, 2), 2),
+            Conv2D::grouped((3, 6, 6), 3, (2, 2), (1, 1), (1, 1), (1, 1), 3),
+        ] {
+            let input = image(conv.dim_in, 0.0);
+            let error = image(conv.dim_out, 1.0);
+            assert_close(&conv.forward(&input), &conv.forward_direct(&input));
+            let (input_error, filter_error, bias_error) = conv.back(&input, &error);
+            let (direct_input, direct_filter, direct_bias) = conv.back_direct(&input, &error);
+            assert_close(&input_error, &direct_input);
+            assert_close(&filter_error.unwrap(), &direct_filter.unwrap());
+            let (bias_error, direct_bias) = (bias_error.unwrap(), direct_bias.unwrap());
+            assert!(bias_error
+                .iter()
+                .zip(direct_bias.iter())
+                .all(|(x, y)| (x - y).abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn test_max_pool() {
+        let pool = MaxPool2D::new((1, 4, 4), (2, 2), (0, 0), (2, 2));
+        let input = vec![vec![
+            vec![1.0, 2.0, 0.0, 1.0],
+            vec![4.0, 3.0, 5.0, 1.0],
+            vec![0.0, 0.0, 1.0, 1.0],
+            vec![0.0, 9.0, 1.0, 2.0],
+        ]];
+        assert_eq!(pool.dim_out(), (1, 2, 2));
+        assert_eq!(pool.forward(&input), [[[4.0, 5.0], [9.0, 2.0]]]);
+        let (input_error, weights, bias) =
+            pool.back(&input, &[vec![vec![1.0, 2.0], vec![3.0, 4.0]]]);
+        assert_eq!(
+            input_error[0],
+            [
+                [0.0, 0.0, 0.0, 0.0],
+                [1.0, 0.0, 2.0, 0.0],
+                [0.0, 0.0, 0.0, 0.0],
+                [0.0, 3.0, 0.0, 4.0]
+            ]
+        );
+        assert!(weights.is_none() && bias.is_none());
+    }
+
+    #[test]
+    fn test_padded_pool() {
+        let input = vec![vec![vec![-1.0, -2.0], vec![-3.0, -4.0]]];
+        let max = MaxPool2D::new((1, 2, 2), (2, 2), (1, 1), (2, 2));
+        let avg = AvgPool2D::new((1, 2, 2), (2, 2), (1, 1), (2, 2));
+        // padding never wins a max but counts towards an average
+        assert_eq!(max.forward(&input), [[[-1.0, -2.0], [-3.0, -4.0]]]);
+        assert_eq!(avg.forward(&input), [[[-0.25, -0.5], [-0.75, -1.0]]]);
+    }
+
+    #[test]
+    fn test_avg_pool() {
+        let pool = AvgPool2D::new((2, 3, 3), (2, 2), (0, 0), (1, 1));
+        let input = vec![
+            vec![
+                vec![1.0, 2.0, 3.0],
+                vec![4.0, 5.0, 6.0],
+                vec![7.0, 8.0, 9.0],
+            ],
+            vec![vec![1.0; 3]; 3],
+        ];
+        assert_eq!(
+            pool.forward(&input),
+            [[[3.0, 4.0], [6.0, 7.0]], [[1.0, 1.0], [1.0, 1.0]]]
+        );
+        let (input_error, _, _) =
+            pool.back(&input, &[vec![vec![4.0; 2]; 2], vec![vec![0.0; 2]; 2]]);
+        assert_eq!(
+            input_error[0],
+            [[1.0, 2.0, 1.0], [2.0, 4.0, 2.0], [1.0, 2.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn test_adaptive_pool() {
+        let input = vec![vec![(0..5).map(|x| x as f32).collect::<Vec<f32>>(); 2]];
+        let avg = AdaptiveAvgPool2D::new((1, 2, 5), (1, 3));
+        let max = AdaptiveMaxPool2D::new((1, 2, 5), (1, 3));
+        // windows [0, 2), [1, 4) and [3, 5)
+        assert_eq!(avg.forward(&input), [[[0.5, 2.0, 3.5]]]);
+        assert_eq!(max.forward(&input), [[[1.0, 3.0, 4.0]]]);
+    }
+
+    #[test]
+    fn test_global_pool() {
+        let input = image((3, 4, 5), 0.0);
+        let avg = GlobalAvgPool2D::new((3, 4, 5));
+        let max = GlobalMaxPool2D::new((3, 4, 5));
+        assert_eq!(avg.dim_out(), (3, 1, 1));
+        for (c, channel) in input.iter().enumerate() {
+            let values: Vec<f32> = channel.iter().flatten().copied().collect();
+            let mean = values.iter().sum::<f32>() / 20.0;
+            assert!((avg.forward(&input)[c][0][0] - mean).abs() < 1e-6);
+            assert_eq!(
+                max.forward(&input)[c][0][0],
+                values.iter().copied().fold(f32::MIN, f32::max)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pool_gradients() {
+        check_input_gradient(&MaxPool2D::new((2, 7, 6), (3, 2), (1, 1), (2, 2)));
+        check_input_gradient(&AvgPool2D::new((2, 7, 6), (3, 2), (1, 1), (2, 2)));
+        check_input_gradient(&AvgPool2D::new((1, 5, 5), (2, 2), (0, 0), (1, 1)));
+        check_input_gradient(&AdaptiveMaxPool2D::new((2, 7, 5), (3, 2)));
+        check_input_gradient(&AdaptiveAvgPool2D::new((2, 7, 5), (3, 2)));
+        check_input_gradient(&GlobalAvgPool2D::new((3, 4, 4)));
+        check_input_gradient(&GlobalMaxPool2D::new((3, 4, 4)));
+    }
+
+    fn sequence(dim: (usize, usize), offset: f32) -> Vec<Vec<f32>> {
+        image((1, dim.0, dim.1), offset).remove(0)
+    }
+
+    fn volume(dim: (usize, usize, usize, usize), offset: f32) -> Vec<Vec<Vec<Vec<f32>>>> {
+        (0..dim.0)
+            .map(|c| image((dim.1, dim.2, dim.3), offset + (c * 100) as f32))
+            .collect()
+    }
+
+    fn check_layer1d_gradients(layer: &mut impl Layer1D) {
+        let epsilon = 1e-3;
+        let input = sequence(layer.dim_in(), 0.0);
+        let error = sequence(layer.dim_out(), 1.0);
+        let score = |layer: &dyn Layer1D, x: &[Vec<f32>]| {
+            dot(&[layer.forward(x)], std::slice::from_ref(&error))
+        };
+        let (input_error, rows) = layer.gradients(&input, &error);
+        for c in 0..input.len() {
+            for i in 0..input[c].len() {
+                let mut up = input.clone();
+                let mut down = input.clone();
+                up[c][i] += epsilon;
+                down[c][i] -= epsilon;
+                let expected = (score(layer, &up) - score(layer, &down)) / (2.0 * epsilon);
+                assert!((expected - input_error[c][i]).abs() < 1e-2);
+            }
+        }
+        for (r, row) in rows.iter().enumerate() {
+            for (j, actual) in row.iter().enumerate() {
+                layer.parameters()[r][j] += epsilon;
+                let up = score(layer, &input);
+                layer.parameters()[r][j] -= 2.0 * epsilon;
+                let down = score(layer, &input);
+                layer.parameters()[r][j] += epsilon;
+                assert!(((up - down) / (2.0 * epsilon) - actual).abs() < 1e-2);
+            }
+        }
+    }
+
+    fn check_layer3d_gradients(layer: &mut impl Layer3D) {
+        let epsilon = 1e-3;
+        let input = volume(layer.dim_in(), 0.0);
+        let error = volume(layer.dim_out(), 1.0);
+        let score = |layer: &dyn Layer3D, x: &[Vec<Vec<Vec<f32>>>]| {
+            dot(&layer.forward(x).concat(), &error.concat())
+        };
+        let (input_error, rows) = layer.gradients(&input, &error);
+        for c in 0..input.len() {
+            for d in 0..input[c].len() {
+                for i in 0..input[c][d].len() {
+                    for j in 0..input[c][d][i].len() {
+                        let mut up = input.clone();
+                        let mut down = input.clone();
+                        up[c][d][i][j] += epsilon;
+                        down[c][d][i][j] -= epsilon;
+                        let expected = (score(layer, &up) - score(layer, &down)) / (2.0 * epsilon);
+                        assert!((expected - input_error[c][d][i][j]).abs() < 1e-2);
+                    }
+                }
+            }
+        }
+        for (r, row) in rows.iter().enumerate() {
+            for (j, actual) in row.iter().enumerate() {
+                layer.parameters()[r][j] += epsilon;
+                let up = score(layer, &input);
+                layer.parameters()[r][j] -= 2.0 * epsilon;
+                let down = score(layer, &input);
+                layer.parameters()[r][j] += epsilon;
+                assert!(((up - down) / (2.0 * epsilon) - actual).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_gather() {
+        let window = Window::new(vec![2], vec![(1, 1)], vec![2], vec![1]);
+        assert_eq!(window.output_shape(&[4]), [3]);
+        assert_eq!(
+            window.gather(&[4]),
+            [[None, Some(1), Some(3)], [Some(0), Some(2), None]]
+        );
+    }
+
+    #[test]
+    fn test_conv1d_matches_conv2d() {
+        let conv = Conv1D::new((2, 11), 3, 3, 2, 2, 2);
+        let mut wide = Conv2D::grouped((2, 1, 11), 3, (1, 3), (0, 2), (1, 2), (1, 2), 1);
+        wide.weights = conv
+            .weights
+            .iter()
+            .map(|filters| filters.iter().map(|f| vec![f.clone()]).collect())
+            .collect();
+        assert_eq!(conv.dim_out, (wide.dim_out.0, wide.dim_out.2));
+        let input = sequence(conv.dim_in, 0.0);
+        let expected: Vec<Vec<f32>> = wide
+            .forward(&input.iter().map(|c| vec![c.clone()]).collect::<Vec<_>>())
+            .into_iter()
+            .map(|mut c| c.remove(0))
+            .collect();
+        assert_close(&[conv.forward(&input)], &[expected]);
+    }
+
+    #[test]
+    fn test_conv3d_forward() {
+        let conv = Conv3D::new((2, 4, 5, 3), 2, (2, 3, 2), (1, 1, 0), (2, 1, 1), (1, 1, 2));
+        assert_eq!(conv.dim_out, (2, 3, 5, 1));
+        let input = volume(conv.dim_in, 0.0);
+        let output = conv.forward(&input);
+        let (_, depth, rows, cols) = conv.dim_in;
+        let ((front, _), (top, _), (left, _)) = conv.sides();
+        let direct = |o: usize, z: usize, y: usize, x: usize| {
+            let mut total = conv.bias[o];
+            for (c, filter) in conv.weights[o].iter().enumerate() {
+                for (kd, plane) in filter.iter().enumerate() {
+                    for (kh, row) in plane.iter().enumerate() {
+                        for (kw, w) in row.iter().enumerate() {
+                            let d = (z * conv.stride.0 + kd * conv.dilation.0).wrapping_sub(front);
+                            let i = (y * conv.stride.1 + kh * conv.dilation.1).wrapping_sub(top);
+                            let j = (x * conv.stride.2 + kw * conv.dilation.2).wrapping_sub(left);
+                            if d < depth && i < rows && j < cols {
+                                total += w * input[c][d][i][j];
+                            }
+                        }
+                    }
+                }
+            }
+            total
+        };
+        let expected: Vec<Vec<Vec<Vec<f32>>>> = (0..conv.dim_out.0)
+            .map(|o| {
+                (0..conv.dim_out.1)
+                    .map(|z| {
+                        (0..conv.dim_out.2)
+                            .map(|y| (0..conv.dim_out.3).map(|x| direct(o, z, y, x)).collect())
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_close(&output.concat(), &expected.concat());
+    }
+
+    #[test]
+    fn test_conv1d_conv3d_gradients() {
+        check_layer1d_gradients(&mut Conv1D::new((2, 9), 3, 3, 1, 2, 1));
+        check_layer1d_gradients(&mut Conv1D::new((1, 10), 2, 2, 2, 1, 3));
+        check_layer3d_gradients(&mut Conv3D::new(
+            (2, 3, 4, 4),
+            2,
+            (2, 2, 3),
+            (1, 0, 1),
+            (1, 2, 1),
+            (1, 1, 1),
+        ));
+        check_layer3d_gradients(&mut Conv3D::new(
+            (1, 5, 3, 4),
+            2,
+            (2, 1, 2),
+            (0, 1, 1),
+            (2, 1, 1),
+            (2, 1, 2),
+        ));
+    }
+
+    #[test]
+    fn test_pool1d_pool3d() {
+        let input = sequence((2, 9), 0.0);
+        let as_image: Vec<Vec<Vec<f32>>> = input.iter().map(|c| vec![c.clone()]).collect();
+        let pool = MaxPool1D::new((2, 9), 3, 1, 2);
+        let wide = MaxPool2D::new((2, 1, 9), (1, 3), (0, 1), (1, 2));
+        let expected: Vec<Vec<f32>> = wide
+            .forward(&as_image)
+            .into_iter()
+            .map(|mut c| c.remove(0))
+            .collect();
+        assert_eq!(pool.forward(&input), expected);
+        let pool = AvgPool1D::new((2, 9), 3, 1, 2);
+        let wide = AvgPool2D::new((2, 1, 9), (1, 3), (0, 1), (1, 2));
+        let expected: Vec<Vec<f32>> = wide
+            .forward(&as_image)
+            .into_iter()
+            .map(|mut c| c.remove(0))
+            .collect();
+        assert_close(&[pool.forward(&input)], &[expected]);
+
+        let pool = MaxPool3D::new((2, 4, 4, 4), (2, 2, 2), (0, 0, 0), (2, 2, 2));
+        assert_eq!(pool.dim_out, (2, 2, 2, 2));
+        let input = volume(pool.dim_in, 0.0);
+        let output = pool.forward(&input);
+        let window_max = (0..2)
+            .flat_map(|d| (0..2).flat_map(move |i| (0..2).map(move |j| (d, i, j))))
+            .map(|(d, i, j)| input[1][2 + d][i][2 + j])
+            .fold(f32::NEG_INFINITY, f32::max);
+        assert_eq!(output[1][1][0][1], window_max);
+    }
+
+    #[test]
+    fn test_pool1d_pool3d_gradients() {
+        check_layer1d_gradients(&mut MaxPool1D::new((2, 9), 3, 1, 2));
+        check_layer1d_gradients(&mut AvgPool1D::new((2, 9), 3, 1, 2));
+        check_layer3d_gradients(&mut MaxPool3D::new(
+            (2, 3, 4, 5),
+            (2, 3, 2),
+            (1, 1, 0),
+            (1, 2, 2),
+        ));
+        check_layer3d_gradients(&mut AvgPool3D::new(
+            (2, 3, 4, 5),
+            (2, 3, 2),
+            (1, 1, 0),
+            (1, 2, 2),
+        ));
+    }
+
+    #[test]
+    fn test_rfft() {
+        let signal: Vec<f32> = (0..16)
+            .map(|i| (i as f32 * 0.7).sin() + 0.1 * i as f32)
+            .collect();
+        let spectrum = rfft(&signal);
+        assert_eq!(spectrum.len(), 9);
+        for (k, bin) in spectrum.iter().enumerate() {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (t, x) in signal.iter().enumerate() {
+                let theta = -2.0 * std::f64::consts::PI * (k * t) as f64 / 16.0;
+                re += *x as f64 * theta.cos();
+                im += *x as f64 * theta.sin();
+            }
+            assert!((bin.re - re).abs() < 1e-4 && (bin.im - im).abs() < 1e-4);
+        }
+        let recovered = irfft(&spectrum, 16);
+        assert!(signal
+            .iter()
+            .zip(recovered.iter())
+            .all(|(x, y)| (x - y).abs() < 1e-5));
+
+        let matrix = image((1, 8, 4), 0.0).remove(0);
+        assert_close(&[irfft2(&rfft2(&matrix), 4)], &[matrix]);
+    }
+
+    #[test]
+    fn test_fft_convolution_matches_direct() {
+        for (data_dim, kernel, padding, stride) in [
+            ((8, 8), (3, 3), (0, 0), (1, 1)),
+            ((9, 13), (4, 2), (1, 3), (1, 1)),
+            ((12, 10), (5, 5), (2, 2), (2, 3)),
+            ((7, 7), (7, 7), (0, 0), (1, 1)),
+            ((16, 11), (11, 11), (5, 5), (3, 2)),
+            ((1, 6), (1, 3), (0, 1), (1, 2)),
+        ] {
+            let data = image((1, data_dim.0, data_dim.1), 0.0).remove(0);
+            let filter = image((1, kernel.0, kernel.1), 2.0).remove(0);
+            let expected = direct_convolution(&data, &filter, padding, stride);
+            let actual = fft_convolution(&data, &filter, padding, stride);
+            assert_eq!(actual.len(), expected.len());
+            assert_eq!(actual[0].len(), expected[0].len());
+            assert_close(&[actual], &[expected]);
+        }
+    }
+
+    #[test]
+    fn test_convolution_selects_fft() {
+        let data = image((1, 20, 20), 0.0).remove(0);
+        let small = image((1, 3, 3), 1.0).remove(0);
+        let large = image((1, 10, 10), 1.0).remove(0);
+        assert!(large.len() * large[0].len() >= FFT_KERNEL_AREA);
+        assert_eq!(
+            convolution(&data, &small, (1, 1), (1, 1)),
+            direct_convolution(&data, &small, (1, 1), (1, 1))
+        );
+        assert_eq!(
+            convolution(&data, &large, (2, 2), (2, 1)),
+            fft_convolution(&data, &large, (2, 2), (2, 1))
+        );
+    }
+
+    const MODES: [PaddingMode; 4] = [
+        PaddingMode::Zero,
+        PaddingMode::Reflect,
+        PaddingMode::Replicate,
+        PaddingMode::Circular,
+    ];
+
+    #[test]
+    fn test_padding_sides() {
+        assert_eq!(
+            Padding::from((1, 2)).sides((5, 5), (3, 3), (1, 1)),
+            ((1, 1), (2, 2))
+        );
+        assert_eq!(
+            Padding::Valid.sides((5, 5), (3, 3), (1, 1)),
+            ((0, 0), (0, 0))
+        );
+        assert_eq!(
+            Padding::Same.sides((5, 6), (3, 3), (2, 2)),
+            ((1, 1), (0, 1))
+        );
+        assert_eq!(
+            Padding::Same.sides((4, 4), (2, 4), (1, 1)),
+            ((0, 1), (1, 2))
+        );
+        assert_eq!(
+            Padding::Same.sides((7, 7), (1, 1), (3, 3)),
+            ((0, 0), (0, 0))
+        );
+    }
+
+    #[test]
+    fn test_pad_modes() {
+        let matrix = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let sides = ((1, 0), (2, 1));
+        assert_eq!(
+            pad(&matrix, sides, PaddingMode::Zero),
+            [
+                [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 2.0, 3.0, 0.0],
+                [0.0, 0.0, 4.0, 5.0, 6.0, 0.0]
+            ]
+        );
+        assert_eq!(
+            pad(&matrix, sides, PaddingMode::Reflect),
+            [
+                [6.0, 5.0, 4.0, 5.0, 6.0, 5.0],
+                [3.0, 2.0, 1.0, 2.0, 3.0, 2.0],
+                [6.0, 5.0, 4.0, 5.0, 6.0, 5.0]
+            ]
+        );
+        assert_eq!(
+            pad(&matrix, sides, PaddingMode::Replicate),
+            [
+                [1.0, 1.0, 1.0, 2.0, 3.0, 3.0],
+                [1.0, 1.0, 1.0, 2.0, 3.0, 3.0],
+                [4.0, 4.0, 4.0, 5.0, 6.0, 6.0]
+            ]
+        );
+        assert_eq!(
+            pad(&matrix, sides, PaddingMode::Circular),
+            [
+                [5.0, 6.0, 4.0, 5.0, 6.0, 4.0],
+                [2.0, 3.0, 1.0, 2.0, 3.0, 1.0],
+                [5.0, 6.0, 4.0, 5.0, 6.0, 4.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unpad_is_adjoint() {
+        let matrix = image((1, 4, 5), 0.0).remove(0);
+        let sides = ((2, 1), (3, 2));
+        for mode in MODES {
+            let padded = pad(&matrix, sides, mode);
+            let other = image((1, padded.len(), padded[0].len()), 1.0).remove(0);
+            let input_side = dot(
+                std::slice::from_ref(&matrix),
+                &[unpad(&other, (4, 5), sides, mode)],
+            );
+            let padded_side = dot(&[padded], &[other]);
+            assert!((padded_side - input_side).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_conv2d_padding_modes() {
+        let conv = Conv2D::new((2, 7, 6), 3, (3, 2), Padding::Same, (2, 2));
+        assert_eq!(conv.dim_out, (3, 4, 3));
+        let conv = Conv2D::new((2, 7, 6), 3, (3, 2), Padding::Valid, (1, 1));
+        assert_eq!(conv.dim_out, (3, 5, 5));
+
+        for mode in MODES {
+            for mut conv in [
+                Conv2D::new((2, 6, 5), 2, (3, 3), Padding::Same, (1, 1)),
+                Conv2D::new((1, 7, 6), 2, (2, 3), Padding::Same, (2, 2)),
+                Conv2D::grouped(
+                    (2, 5, 6),
+                    2,
+                    (2, 2),
+                    Padding::Explicit((2, 0), (1, 3)),
+                    (1, 2),
+                    (2, 1),
+                    1,
+                ),
+            ] {
+                conv.mode = mode;
+                check_conv2d_gradients(&conv);
+                let input = image(conv.dim_in, 0.0);
+                let error = image(conv.dim_out, 1.0);
+                assert_close(&conv.forward(&input), &conv.forward_direct(&input));
+                assert_close(
+                    &conv.back(&input, &error).0,
+                    &conv.back_direct(&input, &error).0,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pool_padding_modes() {
+        let pool = MaxPool2D::new((1, 5, 5), (2, 2), Padding::Same, (2, 2));
+        assert_eq!(pool.dim_out, (1, 3, 3));
+        for mode in MODES {
+            let mut max = MaxPool2D::new((2, 6, 5), (3, 3), Padding::Same, (2, 1));
+            let mut avg = AvgPool2D::new((2, 6, 5), (3, 2), Padding::Same, (1, 2));
+            max.mode = mode;
+            avg.mode = mode;
+            check_input_gradient(&max);
+            check_input_gradient(&avg);
+        }
+
+        // replicated edges make the average of a constant image constant
+        let mut avg = AvgPool2D::new((1, 4, 4), (3, 3), (1, 1), (1, 1));
+        avg.mode = PaddingMode::Replicate;
+        let output = avg.forward(&[vec![vec![2.0; 4]; 4]]);
+        assert!(output[0].iter().flatten().all(|x| (x - 2.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_padding_across_dimensions() {
+        let conv = Conv1D::new((2, 9), 3, 3, Padding1D::Same, 2, 1);
+        assert_eq!(conv.dim_out, (3, 5));
+        let pool = MaxPool1D::new((2, 9), 2, Padding1D::Same, 1);
+        assert_eq!(pool.dim_out, (2, 9));
+        let pool = MaxPool1D::new((2, 9), 2, Padding1D::Valid, 1);
+        assert_eq!(pool.dim_out, (2, 8));
+        check_layer1d_gradients(&mut Conv1D::new(
+            (2, 9),
+            3,
+            3,
+            Padding1D::Explicit(2, 0),
+            1,
+            2,
+        ));
+        check_layer1d_gradients(&mut AvgPool1D::new((2, 8), 3, Padding1D::Same, 2));
+
+        let conv = Conv3D::new(
+            (1, 3, 4, 5),
+            2,
+            (2, 3, 3),
+            Padding3D::Same,
+            (1, 1, 2),
+            (1, 1, 1),
+        );
+        assert_eq!(conv.dim_out, (2, 3, 4, 3));
+        let conv = Conv3D::new(
+            (1, 3, 4, 5),
+            2,
+            (2, 3, 3),
+            Padding3D::Valid,
+            (1, 1, 2),
+            (1, 1, 1),
+        );
+        assert_eq!(conv.dim_out, (2, 2, 2, 2));
+        check_layer3d_gradients(&mut Conv3D::new(
+            (1, 3, 4, 4),
+            2,
+            (2, 2, 3),
+            Padding3D::Explicit((1, 0), (0, 1), (2, 1)),
+            (1, 1, 2),
+            (1, 2, 1),
+        ));
+        check_layer3d_gradients(&mut MaxPool3D::new(
+            (1, 3, 4, 5),
+            (2, 3, 2),
+            Padding3D::Same,
+            (1, 2, 2),
+        ));
+
+        let mut separable =
+            SeparableConv2D::new((2, 6, 5), 3, (3, 3), Padding::Same, (1, 1), (1, 1));
+        assert_eq!(separable.dim_out(), (3, 6, 5));
+        check_parameter_gradients(&mut separable);
+
+        // Same makes the transpose the adjoint of a Same Conv2D over stride
+        // times the input size
+        let mut layer =
+            ConvTranspose2D::new((2, 3, 4), 3, (3, 3), Padding::Same, (0, 0), (2, 2), (1, 1));
+        assert_eq!(layer.dim_out, (3, 6, 8));
+        let mut conv = Conv2D::new(layer.dim_out, 2, (3, 3), Padding::Same, (2, 2));
+        assert_eq!(conv.dim_out, layer.dim_in);
+        conv.weights = layer.weights.clone();
+        let input = image(layer.dim_in, 0.0);
+        let (expected, _, _) = conv.back(&image(conv.dim_in, 1.0), &input);
+        assert_close(&layer.forward(&input), &expected);
+        check_input_gradient(&layer);
+        check_parameter_gradients(&mut layer);
+        let mut layer = ConvTranspose2D::new(
+            (1, 3, 3),
+            2,
+            (2, 3),
+            Padding::Explicit((0, 1), (2, 0)),
+            (1, 0),
+            (2, 1),
+            (1, 1),
+        );
+        assert_eq!(layer.dim_out, (2, 6, 3));
+        check_input_gradient(&layer);
+        check_parameter_gradients(&mut layer);
+    }
+
+    // a bias above anything the filter can reach keeps the relu linear, so the
+    // input error from back is the derivative of forward
+    #[test]
+    fn test_convlayer_padding_modes() {
+        let input: Vec<f32> = (0..20).map(|i| (i as f32).sin()).collect();
+        let error: Vec<f32> = (0..20).map(|i| (i as f32 * 0.37 + 1.0).cos()).collect();
+        let dot = |a: &[f32]| a.iter().zip(error.iter()).map(|(x, y)| x * y).sum::<f32>();
+        let epsilon = 1e-2;
+        for mode in MODES {
+            let mut conv = ConvolutionLayer::new(
+                (4, 5),
+                (4, 5),
+                (3, 3),
+                Padding::Same,
+                (1, 1),
+                Function::ReLU,
+            );
+            conv.mode = mode;
+            conv.a = vec![
+                vec![0.3, -0.5, 0.1],
+                vec![0.7, 0.2, -0.4],
+                vec![-0.6, 0.9, 0.05],
+            ];
+            conv.b = vec![10.0];
+            let (partial, _, input_error) = conv.back(&input, &error);
+            assert_eq!((partial.len(), partial[0].len()), (3, 3));
+            for k in 0..input.len() {
+                let mut up = input.clone();
+                let mut down = input.clone();
+                up[k] += epsilon;
+                down[k] -= epsilon;
+                let expected =
+                    (dot(&conv.forward(&up)) - dot(&conv.forward(&down))) / (2.0 * epsilon);
+                assert!((expected - input_error[k]).abs() < 1e-2);
+            }
+        }
+    }
+
+    // deterministic parameters keep the relus of the blocks away from their kinks
+    fn fix_parameters(layer: &mut impl Layer2D) {
+        for (r, row) in layer.parameters().into_iter().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = 0.5 * (((7 * r + j) as f32) * 0.61).sin();
+            }
+        }
+    }
+
+    #[test]
+    fn test_group_norm() {
+        let norm = GroupNorm::new((4, 3, 3), 2);
+        let output = norm.forward(&image((4, 3, 3), 0.0));
+        for group in output.chunks(2) {
+            let values: Vec<f32> = group.iter().flatten().flatten().copied().collect();
+            let mean = values.iter().sum::<f32>() / 18.0;
+            let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / 18.0;
+            assert!(mean.abs() < 1e-5 && (variance - 1.0).abs() < 1e-3);
+        }
+
+        for groups in [1, 2, 4] {
+            let mut norm = GroupNorm::new((4, 3, 2), groups);
+            fix_parameters(&mut norm);
+            check_input_gradient(&norm);
+            check_parameter_gradients(&mut norm);
+        }
+    }
+
+    #[test]
+    fn test_residual_blocks() {
+        let mut identity = ResidualBlock::basic((4, 5, 5), 4, 1, 2);
+        assert!(identity.shortcut.is_empty());
+        assert_eq!(identity.dim_out(), (4, 5, 5));
+        fix_parameters(&mut identity);
+        check_input_gradient(&identity);
+        check_parameter_gradients_at(&mut identity, 1e-3);
+
+        let mut projection = ResidualBlock::basic((2, 5, 5), 4, 2, 2);
+        assert_eq!(projection.shortcut.len(), 2);
+        assert_eq!(projection.dim_out(), (4, 3, 3));
+        fix_parameters(&mut projection);
+        check_input_gradient(&projection);
+        check_parameter_gradients_at(&mut projection, 1e-3);
+
+        let mut bottleneck = ResidualBlock::bottleneck((8, 4, 4), 2, 2, 2);
+        assert_eq!(
+            (bottleneck.main.len(), bottleneck.dim_out()),
+            (8, (8, 2, 2))
+        );
+        fix_parameters(&mut bottleneck);
+        check_input_gradient(&bottleneck);
+        check_parameter_gradients_at(&mut bottleneck, 1e-3);
+    }
+
+    #[test]
+    fn test_residual_identity_branch() {
+        // with the last norm of the main branch zeroed the block is relu(x), and
+        // the error still reaches the input through the shortcut
+        let mut block = ResidualBlock::basic((2, 4, 4), 2, 1, 1);
+        let parameters = block.parameters();
+        let count = parameters.len();
+        for row in parameters.into_iter().skip(count - 2) {
+            row.fill(0.0);
+        }
+        let input = image((2, 4, 4), 0.0);
+        let relu = |x: &Vec<Vec<Vec<f32>>>| -> Vec<f32> {
+            x.iter().flatten().flatten().map(|v| v.max(0.0)).collect()
+        };
+        let output = block.forward(&input);
+        assert_eq!(
+            output
+                .iter()
+                .flatten()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>(),
+            relu(&input)
+        );
+        let error = image((2, 4, 4), 1.0);
+        let (input_error, rows) = block.gradients(&input, &error);
+        let expected: Vec<f32> = input
+            .iter()
+            .flatten()
+            .flatten()
+            .zip(error.iter().flatten().flatten())
+            .map(|(x, e)| if *x > 0.0 { *e } else { 0.0 })
+            .collect();
+        let actual: Vec<f32> = input_error.iter().flatten().flatten().copied().collect();
+        assert!(actual
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, b)| (a - b).abs() < 1e-6));
+        // the zeroed gamma still gets a gradient so it can grow back
+        assert!(rows[rows.len() - 2].iter().any(|g| *g != 0.0));
+    }
+
+    #[test]
+    fn test_cifar_resnet() {
+        let model = cifar_resnet((3, 8, 8), 1, 10);
+        // a stem of conv, norm and relu, three blocks and the classifier
+        assert_eq!(model.nodes.len(), 10);
+        let output = model.forward(&Tensor::Spatial(image((3, 8, 8), 0.0)));
+        assert_eq!(output.flat().len(), 10);
+        assert!((output.flat().iter().sum::<f32>() - 1.0).abs() < 1e-5);
+    }
 }
@@ -1,8 +1,27 @@
+use super::fft_convolution;
+
+// kernels with at least this many entries are convolved through the fft, the
+// direct loops are faster below about 10x10 at the image sizes we train on
+pub const FFT_KERNEL_AREA: usize = 100;
+
 pub fn convolution(
     data: &[Vec<f32>],
     filter: &[Vec<f32>],
     padding: (usize, usize),
     stride: (usize, usize),
+) -> Vec<Vec<f32>> {
+    if filter.len() * filter[0].len() >= FFT_KERNEL_AREA {
+        fft_convolution(data, filter, padding, stride)
+    } else {
+        direct_convolution(data, filter, padding, stride)
+    }
+}
+
+pub fn direct_convolution(
+    data: &[Vec<f32>],
+    filter: &[Vec<f32>],
+    padding: (usize, usize),
+    stride: (usize, usize),
 ) -> Vec<Vec<f32>> {
     let mut padded = Vec::new();
     for _ in 0..padding.0 {
@@ -49,6 +68,37 @@ pub fn convolution(
     output
 }
 
+// number of positions a kernel fits along one dimension
+pub fn output_size(size: usize, kernel: usize, padding: usize, stride: usize) -> usize {
+    (size + 2 * padding - kernel) / stride + 1
+}
+
+// the size a transposed convolution produces, output_padding picks between the
+// sizes that all convolve back down to the same input size
+pub fn transposed_output_size(
+    size: usize,
+    kernel: usize,
+    sides: (usize, usize),
+    stride: usize,
+    output_padding: usize,
+) -> usize {
+    (size - 1) * stride + kernel + output_padding - sides.0 - sides.1
+}
+
+// the window of the given size starting at offset, zero filled where it runs off the matrix
+pub fn crop(matrix: &[Vec<f32>], offset: (usize, usize), size: (usize, usize)) -> Vec<Vec<f32>> {
+    (offset.0..(offset.0 + size.0))
+        .map(|i| {
+            (offset.1..(offset.1 + size.1))
+                .map(|j| match matrix.get(i) {
+                    Some(row) => *row.get(j).unwrap_or(&0.0),
+                    None => 0.0,
+                })
+                .collect()
+        })
+        .collect()
+}
+
 pub fn pad_right_within(matrix: &[Vec<f32>], padding: (usize, usize)) -> Vec<Vec<f32>> {
     let new_row_len = matrix[0].len() * (1 + padding.1);
     let mut empty = Vec::new();
@@ -128,11 +178,16 @@ pub fn matrix_op(a: &[Vec<f32>], b: &[Vec<f32>], func: fn(&f32, &f32) -> f32) ->
         .collect()
 }
 
-
-
 pub fn matrix_rotate(x: &[Vec<f32>]) -> Vec<Vec<f32>> {
     x.iter()
         .map(|y| y.iter().rev().map(|z| *z).collect())
         .rev()
         .collect()
 }
+
+// a quarter turn clockwise, two of these make matrix_rotate
+pub fn matrix_rotate_90(x: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    (0..x[0].len())
+        .map(|j| x.iter().rev().map(|row| row[j]).collect())
+        .collect()
+}
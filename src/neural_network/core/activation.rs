@@ -1,8 +1,17 @@
-use super::Layer;
+use super::{Layer, Layer2D};
 
 pub struct ReLU {
-    dim_in: usize,
-    dim_out: usize,
+    pub dim_in: usize,
+    pub dim_out: usize,
+}
+
+impl ReLU {
+    pub fn new(dim: usize) -> Self {
+        ReLU {
+            dim_in: dim,
+            dim_out: dim,
+        }
+    }
 }
 
 impl Layer for ReLU {
@@ -34,3 +43,110 @@ impl Layer for ReLU {
         (new_error, None, None)
     }
 }
+
+// ReLU over every entry of a (channels, rows, cols) input
+pub struct ReLU2D {
+    pub dim: (usize, usize, usize),
+}
+
+impl ReLU2D {
+    pub fn new(dim: (usize, usize, usize)) -> Self {
+        ReLU2D { dim }
+    }
+}
+
+impl Layer2D for ReLU2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim
+    }
+
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        input
+            .iter()
+            .map(|channel| {
+                channel
+                    .iter()
+                    .map(|row| row.iter().map(|x| x.max(0.0)).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let new_error = input
+            .iter()
+            .zip(error.iter())
+            .map(|(x, e)| {
+                x.iter()
+                    .zip(e.iter())
+                    .map(|(xs, es)| {
+                        xs.iter()
+                            .zip(es.iter())
+                            .map(|(xi, ei)| if *xi > 0.0 { *ei } else { 0.0 })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        (new_error, None, None)
+    }
+}
+
+pub struct Softmax {
+    pub dim_in: usize,
+    pub dim_out: usize,
+}
+
+impl Softmax {
+    pub fn new(dim: usize) -> Self {
+        Softmax {
+            dim_in: dim,
+            dim_out: dim,
+        }
+    }
+}
+
+impl Layer for Softmax {
+    fn dim_in(&self) -> usize {
+        self.dim_in
+    }
+    fn dim_out(&self) -> usize {
+        self.dim_out
+    }
+
+    // shifted by the maximum so large inputs don't overflow
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        assert_eq!(input.len(), self.dim_in);
+        let max = input.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let exp: Vec<f32> = input.iter().map(|x| (x - max).exp()).collect();
+        let total: f32 = exp.iter().sum();
+        exp.iter().map(|x| x / total).collect()
+    }
+
+    // J^T e where J = diag(p) - p p^T
+    fn backward(
+        &self,
+        input: &[f32],
+        error: &[f32],
+    ) -> (Vec<f32>, Option<Vec<Vec<f32>>>, Option<Vec<f32>>) {
+        let p = self.forward(input);
+        let dot: f32 = p.iter().zip(error.iter()).map(|(x, y)| x * y).sum();
+        let new_error = p
+            .iter()
+            .zip(error.iter())
+            .map(|(pi, ei)| pi * (ei - dot))
+            .collect();
+        (new_error, None, None)
+    }
+}
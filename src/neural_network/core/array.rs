@@ -19,3 +19,13 @@ pub fn unstack(matrix: &[Vec<f32>]) -> Vec<f32> {
     }
     flat
 }
+
+pub fn stack_volume(flat: &[f32], dim: (usize, usize, usize)) -> Vec<Vec<Vec<f32>>> {
+    flat.chunks(dim.1 * dim.2)
+        .map(|slice| stack(slice, (dim.1, dim.2)))
+        .collect()
+}
+
+pub fn unstack_volume(volume: &[Vec<Vec<f32>>]) -> Vec<f32> {
+    volume.iter().flat_map(|matrix| unstack(matrix)).collect()
+}
@@ -3,6 +3,8 @@ use super::traits::{Activation, Derivative, Loss};
 #[derive(Clone, Copy)]
 pub enum Function {
     ReLU,
+    Sigmoid,
+    Tanh,
     CrossEntropy,
 }
 
@@ -18,6 +20,8 @@ impl Activation for Function {
                     .map(|xi| if *xi > 0.0 { *xi } else { 0.0 })
                     .collect()
             },
+            Self::Sigmoid => |x| x.iter().map(|xi| 1.0 / (1.0 + (-xi).exp())).collect(),
+            Self::Tanh => |x| x.iter().map(|xi| xi.tanh()).collect(),
         }
     }
 }
@@ -32,6 +36,21 @@ impl Derivative for Function {
                     .map(|(xi, yi)| if *xi > 0.0 { *yi } else { 0.0 })
                     .collect()
             },
+            Self::Sigmoid => |x, y| {
+                x.iter()
+                    .zip(y.iter())
+                    .map(|(xi, yi)| {
+                        let s = 1.0 / (1.0 + (-xi).exp());
+                        s * (1.0 - s) * yi
+                    })
+                    .collect()
+            },
+            Self::Tanh => |x, y| {
+                x.iter()
+                    .zip(y.iter())
+                    .map(|(xi, yi)| (1.0 - xi.tanh().powi(2)) * yi)
+                    .collect()
+            },
         }
     }
 }
@@ -46,7 +65,7 @@ impl Loss for Function {
                     .fold(0.0, |acc, z| acc + z)
                     / (x.len() as f32)
             }),
-            Self::ReLU => None,
+            Self::ReLU | Self::Sigmoid | Self::Tanh => None,
         }
     }
 }
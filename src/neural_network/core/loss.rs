@@ -0,0 +1,34 @@
+// losses return the value and its gradient with respect to the output
+pub type LossFunction = fn(&[f32], &[f32]) -> (f32, Vec<f32>);
+
+// the output is a probability distribution, e.g. from Softmax
+pub fn cross_entropy(output: &[f32], target: &[f32]) -> (f32, Vec<f32>) {
+    let clipped: Vec<f32> = output.iter().map(|x| x.max(1e-7)).collect();
+    let loss = -clipped
+        .iter()
+        .zip(target.iter())
+        .map(|(x, y)| y * x.ln())
+        .sum::<f32>();
+    let gradient = clipped
+        .iter()
+        .zip(target.iter())
+        .map(|(x, y)| -y / x)
+        .collect();
+    (loss, gradient)
+}
+
+pub fn mean_squared_error(output: &[f32], target: &[f32]) -> (f32, Vec<f32>) {
+    let n = output.len() as f32;
+    let loss = output
+        .iter()
+        .zip(target.iter())
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f32>()
+        / n;
+    let gradient = output
+        .iter()
+        .zip(target.iter())
+        .map(|(x, y)| 2.0 * (x - y) / n)
+        .collect();
+    (loss, gradient)
+}
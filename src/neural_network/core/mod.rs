@@ -1,11 +1,18 @@
 pub mod activation;
 pub mod array;
 pub mod function;
+pub mod loss;
+pub mod record;
+pub mod reshape;
 mod test;
 pub mod traits;
 pub mod utilities;
 
+pub use self::activation::*;
 pub use self::array::*;
 pub use self::function::*;
+pub use self::loss::*;
+pub use self::record::*;
+pub use self::reshape::*;
 pub use self::traits::*;
 pub use self::utilities::*;
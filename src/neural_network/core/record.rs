@@ -0,0 +1,155 @@
+use std::{fs, io, path::Path};
+
+// named rows of numbers, written as text with one row per line, the name and
+// then the values; f32 formatting round trips exactly, so loading what was saved
+// restores the same bits; names nest with dots, see extend and section
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    pub entries: Vec<(String, Vec<f32>)>,
+}
+
+fn missing(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("no entry named {name}"))
+}
+
+// a u64 or u128 as 16 bit pieces, which f32 holds exactly
+pub fn pieces(x: u128, count: usize) -> Vec<f32> {
+    (0..count)
+        .map(|i| ((x >> (16 * i)) & 0xffff) as f32)
+        .collect()
+}
+
+pub fn from_pieces(pieces: &[f32]) -> u128 {
+    pieces
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (i, x)| acc | (*x as u128) << (16 * i))
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Record {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, name: &str, values: &[f32]) {
+        assert!(!name.is_empty() && !name.contains(char::is_whitespace));
+        self.entries.push((name.to_string(), values.to_vec()));
+    }
+
+    pub fn get(&self, name: &str) -> io::Result<&[f32]> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, values)| values.as_slice())
+            .ok_or_else(|| missing(name))
+    }
+
+    pub fn scalar(&self, name: &str) -> io::Result<f32> {
+        match self.get(name)? {
+            [x] => Ok(*x),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name} is not a single value"),
+            )),
+        }
+    }
+
+    // a counter in pieces, as f32 loses whole numbers above 2^24
+    pub fn push_count(&mut self, name: &str, count: u64) {
+        self.push(name, &pieces(count as u128, 4));
+    }
+
+    pub fn count(&self, name: &str) -> io::Result<u64> {
+        let values = self.get(name)?;
+        let piece = |x: &f32| x.fract() == 0.0 && (0.0..65536.0).contains(x);
+        if values.is_empty() || values.len() > 4 || !values.iter().all(piece) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name} is not a count"),
+            ));
+        }
+        Ok(from_pieces(values) as u64)
+    }
+
+    // a list of rows as name holding the count and name.i holding row i
+    pub fn push_rows(&mut self, name: &str, rows: &[Vec<f32>]) {
+        self.push(name, &[rows.len() as f32]);
+        for (i, row) in rows.iter().enumerate() {
+            self.push(&format!("{name}.{i}"), row);
+        }
+    }
+
+    pub fn rows(&self, name: &str) -> io::Result<Vec<Vec<f32>>> {
+        let count = self.scalar(name)? as usize;
+        (0..count)
+            .map(|i| self.get(&format!("{name}.{i}")).map(|row| row.to_vec()))
+            .collect()
+    }
+
+    // adds every entry of other with prefix and a dot in front of its name
+    pub fn extend(&mut self, prefix: &str, other: Record) {
+        for (name, values) in other.entries {
+            self.entries.push((format!("{prefix}.{name}"), values));
+        }
+    }
+
+    // the entries named prefix.something as something, the inverse of extend
+    pub fn section(&self, prefix: &str) -> Record {
+        let start = format!("{prefix}.");
+        Record {
+            entries: self
+                .entries
+                .iter()
+                .filter_map(|(name, values)| {
+                    name.strip_prefix(&start)
+                        .map(|rest| (rest.to_string(), values.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, values) in &self.entries {
+            text.push_str(name);
+            for x in values {
+                text.push(' ');
+                text.push_str(&x.to_string());
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> io::Result<Record> {
+        let mut record = Record::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut tokens = line.split_whitespace();
+            let name = tokens.next().unwrap();
+            let values = tokens
+                .map(|token| token.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("bad value in {name}"))
+                })?;
+            record.entries.push((name.to_string(), values));
+        }
+        Ok(record)
+    }
+
+    // written next to path and renamed over it, so a crash part way through
+    // leaves whatever was at path before whole
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, self.to_text())?;
+        fs::rename(&temporary, path)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Record> {
+        Record::from_text(&fs::read_to_string(path)?)
+    }
+}
@@ -0,0 +1,91 @@
+// adapters between the flat Layer and the (channels, rows, cols) Layer2D layouts,
+// values are kept in channel, row, column order
+
+use super::{stack, Layer2D};
+
+pub struct Flatten {
+    pub dim_in: (usize, usize, usize),
+}
+
+impl Flatten {
+    pub fn new(dim_in: (usize, usize, usize)) -> Self {
+        Flatten { dim_in }
+    }
+
+    pub fn dim_out(&self) -> usize {
+        self.dim_in.0 * self.dim_in.1 * self.dim_in.2
+    }
+
+    pub fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<f32> {
+        input.iter().flatten().flatten().copied().collect()
+    }
+
+    pub fn back(&self, error: &[f32]) -> Vec<Vec<Vec<f32>>> {
+        Unflatten::new(self.dim_in).forward(error)
+    }
+}
+
+pub struct Unflatten {
+    pub dim_out: (usize, usize, usize),
+}
+
+impl Unflatten {
+    pub fn new(dim_out: (usize, usize, usize)) -> Self {
+        Unflatten { dim_out }
+    }
+
+    pub fn dim_in(&self) -> usize {
+        self.dim_out.0 * self.dim_out.1 * self.dim_out.2
+    }
+
+    pub fn forward(&self, input: &[f32]) -> Vec<Vec<Vec<f32>>> {
+        assert_eq!(input.len(), self.dim_in());
+        input
+            .chunks(self.dim_out.1 * self.dim_out.2)
+            .map(|channel| stack(channel, (self.dim_out.1, self.dim_out.2)))
+            .collect()
+    }
+
+    pub fn back(&self, error: &[Vec<Vec<f32>>]) -> Vec<f32> {
+        Flatten::new(self.dim_out).forward(error)
+    }
+}
+
+pub struct Reshape {
+    pub dim_in: (usize, usize, usize),
+    pub dim_out: (usize, usize, usize),
+}
+
+impl Reshape {
+    pub fn new(dim_in: (usize, usize, usize), dim_out: (usize, usize, usize)) -> Self {
+        assert_eq!(
+            dim_in.0 * dim_in.1 * dim_in.2,
+            dim_out.0 * dim_out.1 * dim_out.2
+        );
+        Reshape { dim_in, dim_out }
+    }
+}
+
+impl Layer2D for Reshape {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.dim_out
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        Unflatten::new(self.dim_out).forward(&Flatten::new(self.dim_in).forward(input))
+    }
+    fn back(
+        &self,
+        _input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let flat = Flatten::new(self.dim_out).forward(error);
+        (Unflatten::new(self.dim_in).forward(&flat), None, None)
+    }
+}
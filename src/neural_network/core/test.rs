@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod test_core {
-    use crate::neural_network::core::array::{stack, unstack};
+    use crate::neural_network::core::{
+        array::{stack, unstack},
+        cross_entropy, mean_squared_error, Flatten, Layer, Layer2D, Record, Reshape, Softmax,
+        Unflatten,
+    };
 
     #[test]
     fn test_stack() {
@@ -15,4 +19,103 @@ mod test_core {
         let matrix: Vec<Vec<f32>> = Vec::from([vec![1.0, 1.0], vec![1.0, 1.0]]);
         assert_eq!(unstack(&matrix), [1.0, 1.0, 1.0, 1.0]);
     }
+
+    #[test]
+    fn test_flatten() {
+        let input = vec![
+            vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+            vec![vec![5.0, 6.0], vec![7.0, 8.0]],
+        ];
+        let flatten = Flatten::new((2, 2, 2));
+        let flat = flatten.forward(&input);
+        assert_eq!(flat, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(flatten.back(&flat), input);
+        assert_eq!(Unflatten::new((2, 2, 2)).forward(&flat), input);
+    }
+
+    #[test]
+    fn test_reshape() {
+        let input = vec![vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]];
+        let reshape = Reshape::new((1, 2, 3), (3, 1, 2));
+        let output = reshape.forward(&input);
+        assert_eq!(output, [[[1.0, 2.0]], [[3.0, 4.0]], [[5.0, 6.0]]]);
+        assert_eq!(reshape.back(&input, &output).0, input);
+    }
+
+    #[test]
+    fn test_softmax() {
+        let softmax = Softmax::new(3);
+        let input = [1.0, 2.0, 0.5];
+        let output = softmax.forward(&input);
+        assert!((output.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        let error = [0.3, -1.0, 2.0];
+        let (input_error, _, _) = softmax.backward(&input, &error);
+        for k in 0..3 {
+            let mut up = input;
+            let mut down = input;
+            up[k] += 1e-3;
+            down[k] -= 1e-3;
+            let dot = |x: Vec<f32>| x.iter().zip(error.iter()).map(|(a, b)| a * b).sum::<f32>();
+            let expected = (dot(softmax.forward(&up)) - dot(softmax.forward(&down))) / 2e-3;
+            assert!((expected - input_error[k]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_losses() {
+        let (loss, gradient) = cross_entropy(&[0.25, 0.75], &[0.0, 1.0]);
+        assert!((loss + 0.75f32.ln()).abs() < 1e-6);
+        assert_eq!(gradient[0], 0.0);
+        let (loss, gradient) = mean_squared_error(&[1.0, 3.0], &[0.0, 1.0]);
+        assert_eq!(loss, 2.5);
+        assert_eq!(gradient, [1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut inner = Record::new();
+        inner.push("rate", &[0.1]);
+        inner.push_rows(
+            "moments",
+            &[vec![1.0 / 3.0, -2.5e-9], vec![], vec![f32::INFINITY]],
+        );
+        let mut record = Record::new();
+        record.push("steps", &[12.0]);
+        record.extend("optimiser", inner.clone());
+
+        let text = record.to_text();
+        assert!(text.starts_with("steps 12\noptimiser.rate 0.1\noptimiser.moments 3\n"));
+        let loaded = Record::from_text(&text).unwrap();
+        assert_eq!(loaded, record);
+        assert_eq!(loaded.section("optimiser"), inner);
+        assert_eq!(
+            loaded.section("optimiser").rows("moments").unwrap(),
+            [vec![1.0 / 3.0, -2.5e-9], vec![], vec![f32::INFINITY]]
+        );
+        assert_eq!(loaded.scalar("steps").unwrap(), 12.0);
+        assert!(loaded.scalar("optimiser.moments.0").is_err());
+        assert!(loaded.get("missing").is_err());
+        assert!(Record::from_text("name 1 x").is_err());
+    }
+
+    #[test]
+    fn test_record_save() {
+        let directory = std::env::temp_dir().join(format!("record-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("record.txt");
+        let mut first = Record::new();
+        first.push("steps", &[1.0]);
+        first.save(&path).unwrap();
+        let mut second = Record::new();
+        second.push("steps", &[2.0]);
+        second.save(&path).unwrap();
+        assert_eq!(Record::load(&path).unwrap(), second);
+        assert!(!directory.join("record.txt.tmp").exists());
+
+        // a write that fails part way leaves the last record whole
+        std::fs::create_dir(directory.join("record.txt.tmp")).unwrap();
+        assert!(first.save(&path).is_err());
+        assert_eq!(Record::load(&path).unwrap(), second);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
 }
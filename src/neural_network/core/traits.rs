@@ -1,3 +1,5 @@
+use std::any::Any;
+
 use super::Function;
 
 pub trait Activation {
@@ -12,28 +14,133 @@ pub trait Loss {
     fn loss(&self) -> Option<fn(&[f32], &[f32]) -> f32>;
 }
 
+// lets passes over a model such as quantisation and pruning pick out the layers
+// they know; call it on the layer itself, as on a Box it gives the Box
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub trait DEPRECATEDLayer: Clone {
     fn cap(&self) -> Function;
     fn forward(&self, input: &[f32]) -> Vec<f32>;
     fn back(&self, output: &[f32], error: &[f32]) -> (Vec<Vec<f32>>, Vec<f32>, Vec<f32>);
 }
 
-pub trait Layer2D: Clone {
+// what a parameter row holds, so regularisation can treat them differently
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterKind {
+    Weight,
+    Bias,
+    Norm,
+}
+
+// the kinds of the usual layout of weight rows then a bias
+pub fn weights_then_bias(rows: usize) -> Vec<ParameterKind> {
+    let mut kinds = vec![ParameterKind::Weight; rows.saturating_sub(1)];
+    if rows > 0 {
+        kinds.push(ParameterKind::Bias);
+    }
+    kinds
+}
+
+// (input error, weight gradients, bias gradients) of a Layer2D
+pub type Back2D = (
+    Vec<Vec<Vec<f32>>>,
+    Option<Vec<Vec<Vec<f32>>>>,
+    Option<Vec<f32>>,
+);
+
+// parameters hands out every weight row followed by the bias, and gradients
+// returns the input error and the parameter gradients as rows in that order;
+// layers built from other layers override both along with parameter_kinds
+pub trait Layer2D: AsAny {
     fn dim_in(&self) -> (usize, usize, usize);
     fn dim_out(&self) -> (usize, usize, usize);
     fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>>;
-    fn back(
+    fn back(&self, input: &[Vec<Vec<f32>>], error: &[Vec<Vec<f32>>]) -> Back2D;
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        Vec::new()
+    }
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        weights_then_bias(self.parameters().len())
+    }
+    fn gradients(
         &self,
         input: &[Vec<Vec<f32>>],
         error: &[Vec<Vec<f32>>],
-    ) -> (
-        Vec<Vec<Vec<f32>>>,
-        Option<Vec<Vec<Vec<f32>>>>,
-        Option<Vec<f32>>,
-    );
+    ) -> (Vec<Vec<Vec<f32>>>, Vec<Vec<f32>>) {
+        let (input_error, weights, bias) = self.back(input, error);
+        let mut rows: Vec<Vec<f32>> = weights.unwrap_or_default().into_iter().flatten().collect();
+        rows.extend(bias);
+        (input_error, rows)
+    }
+}
+
+// (input error, weight gradients, bias gradients) of a Layer1D
+pub type Back1D = (Vec<Vec<f32>>, Option<Vec<Vec<f32>>>, Option<Vec<f32>>);
+
+// sequences laid out as (channels, length), filter errors are [out * in + in][kernel]
+pub trait Layer1D {
+    fn dim_in(&self) -> (usize, usize);
+    fn dim_out(&self) -> (usize, usize);
+    fn forward(&self, input: &[Vec<f32>]) -> Vec<Vec<f32>>;
+    fn back(&self, input: &[Vec<f32>], error: &[Vec<f32>]) -> Back1D;
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        Vec::new()
+    }
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        weights_then_bias(self.parameters().len())
+    }
+    fn gradients(&self, input: &[Vec<f32>], error: &[Vec<f32>]) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let (input_error, weights, bias) = self.back(input, error);
+        let mut rows = weights.unwrap_or_default();
+        rows.extend(bias);
+        (input_error, rows)
+    }
 }
 
-pub trait Layer {
+// (input error, weight gradients, bias gradients) of a Layer3D
+pub type Back3D = (
+    Vec<Vec<Vec<Vec<f32>>>>,
+    Option<Vec<Vec<Vec<Vec<f32>>>>>,
+    Option<Vec<f32>>,
+);
+
+// (input error, gradient rows in the order of parameters) of a Layer3D
+pub type Gradients3D = (Vec<Vec<Vec<Vec<f32>>>>, Vec<Vec<f32>>);
+
+// volumes laid out as (channels, depth, rows, cols)
+pub trait Layer3D {
+    fn dim_in(&self) -> (usize, usize, usize, usize);
+    fn dim_out(&self) -> (usize, usize, usize, usize);
+    fn forward(&self, input: &[Vec<Vec<Vec<f32>>>]) -> Vec<Vec<Vec<Vec<f32>>>>;
+    fn back(&self, input: &[Vec<Vec<Vec<f32>>>], error: &[Vec<Vec<Vec<f32>>>]) -> Back3D;
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        Vec::new()
+    }
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        weights_then_bias(self.parameters().len())
+    }
+    fn gradients(&self, input: &[Vec<Vec<Vec<f32>>>], error: &[Vec<Vec<Vec<f32>>>]) -> Gradients3D {
+        let (input_error, weights, bias) = self.back(input, error);
+        let mut rows: Vec<Vec<f32>> = weights
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect();
+        rows.extend(bias);
+        (input_error, rows)
+    }
+}
+
+pub trait Layer: AsAny {
     fn dim_in(&self) -> usize;
     fn dim_out(&self) -> usize;
     fn forward(&self, input: &[f32]) -> Vec<f32>;
@@ -42,4 +149,53 @@ pub trait Layer {
         input: &[f32],
         error: &[f32],
     ) -> (Vec<f32>, Option<Vec<Vec<f32>>>, Option<Vec<f32>>);
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        Vec::new()
+    }
+    fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        weights_then_bias(self.parameters().len())
+    }
+    fn gradients(&self, input: &[f32], error: &[f32]) -> (Vec<f32>, Vec<Vec<f32>>) {
+        let (input_error, weights, bias) = self.backward(input, error);
+        let mut rows = weights.unwrap_or_default();
+        rows.extend(bias);
+        (input_error, rows)
+    }
+}
+
+// (input error, weight gradients, bias gradients, initial state error)
+pub type RecurrentGradients = (
+    Vec<Vec<f32>>,
+    Vec<Vec<Vec<f32>>>,
+    Vec<Vec<f32>>,
+    Vec<Vec<f32>>,
+);
+
+// a recurrent layer runs over a whole sequence, the state is one vector
+// for a vanilla RNN or GRU (h) and two for an LSTM (h, c)
+pub trait Recurrent: Clone {
+    fn dim_in(&self) -> usize;
+    fn dim_hidden(&self) -> usize;
+    fn initial_state(&self) -> Vec<Vec<f32>>;
+    // returns every hidden state and the final state
+    fn forward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>);
+    // error is either one vector per step or a single vector for the output of
+    // forward_last, truncation cuts the gradient through the state every k steps
+    fn backward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+        error: &[Vec<f32>],
+        state_error: Option<&[Vec<f32>]>,
+        truncation: Option<usize>,
+    ) -> RecurrentGradients;
+
+    fn forward_last(&self, input: &[Vec<f32>], state: Option<&[Vec<f32>]>) -> Vec<f32> {
+        let (_, final_state) = self.forward(input, state);
+        final_state[0].clone()
+    }
 }
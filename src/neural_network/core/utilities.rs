@@ -13,6 +13,14 @@ pub fn he_initialise(dim_in: usize, dim_out: usize) -> Vec<f32> {
         .collect()
 }
 
+#[test]
+fn test_matmul() {
+    let a = vec![vec![1.0, 2.0, 0.0], vec![0.0, 1.0, 1.0]];
+    let b = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]];
+    let expected = vec![vec![7.0, 10.0], vec![8.0, 10.0]];
+    assert_eq!(matmul(&a, &b, None, false), expected);
+}
+
 #[test]
 fn test_matmul_t() {
     let a = vec![vec![1.0, 2.0], vec![2.0, 3.0]];
@@ -28,31 +36,25 @@ pub fn matmul(
     scalar: Option<f32>,
     transpose: bool,
 ) -> Vec<Vec<f32>> {
-    let mut result = Vec::new();
-    let c = match scalar {
-        Some(x) => x,
-        None => 1.0,
-    };
-    for i in 0..a.len() {
-        let mut row = Vec::new();
-        for j in 0..b.len() {
-            row.push(
-                (0..a[0].len())
-                    .collect::<Vec<_>>()
-                    .iter()
-                    .map(|k| {
-                        if transpose {
-                            c * a[i][*k] * b[j][*k]
-                        } else {
-                            c * a[i][*k] * b[*k][j]
-                        }
-                    })
-                    .fold(0.0, |acc, x| acc + x),
-            )
-        }
-        result.push(row)
-    }
-    result
+    let c = scalar.unwrap_or(1.0);
+    a.iter()
+        .map(|row| {
+            if transpose {
+                b.iter()
+                    .map(|col| c * row.iter().zip(col.iter()).map(|(x, y)| x * y).sum::<f32>())
+                    .collect()
+            } else {
+                // accumulate whole rows of b so the inner loop runs over contiguous memory
+                let mut result = vec![0.0; b.first().map_or(0, |x| x.len())];
+                for (x, b_row) in row.iter().zip(b.iter()) {
+                    for (r, y) in result.iter_mut().zip(b_row.iter()) {
+                        *r += x * y;
+                    }
+                }
+                result.iter().map(|r| c * r).collect()
+            }
+        })
+        .collect()
 }
 
 #[test]
@@ -64,17 +66,40 @@ fn test_linear_transform() {
     ];
     let x = vec![2.0, 3.0, 5.0];
     assert_eq!(linear_transform(&a, &x, None), x);
+    assert_eq!(
+        linear_transform(&a, &x, Some(&[1.0, 0.0, -1.0])),
+        [3.0, 3.0, 4.0]
+    );
 }
 
 pub fn linear_transform(a: &[Vec<f32>], x: &[f32], b: Option<&[f32]>) -> Vec<f32> {
-    a.iter()
-        .map(|y| {
-            y.iter()
-                .zip(x.iter())
-                .map(|(z, w)| z * w)
-                .fold(0.0, |acc, y| acc + y)
-        })
-        .collect()
+    let product = a.iter().map(|y| {
+        y.iter()
+            .zip(x.iter())
+            .map(|(z, w)| z * w)
+            .fold(0.0, |acc, y| acc + y)
+    });
+    match b {
+        Some(bias) => product.zip(bias.iter()).map(|(y, c)| y + c).collect(),
+        None => product.collect(),
+    }
+}
+
+#[test]
+fn test_transpose_transform() {
+    let a = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]];
+    let x = vec![1.0, 0.0, 2.0];
+    assert_eq!(transpose_transform(&a, &x), vec![11.0, 14.0]);
+}
+
+pub fn transpose_transform(a: &[Vec<f32>], x: &[f32]) -> Vec<f32> {
+    let mut result = vec![0.0; a[0].len()];
+    for (row, xi) in a.iter().zip(x.iter()) {
+        for (r, y) in result.iter_mut().zip(row.iter()) {
+            *r += y * xi;
+        }
+    }
+    result
 }
 
 pub fn add(a: &[f32], b: &[f32]) -> Vec<f32> {
@@ -0,0 +1,325 @@
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal};
+
+use crate::neural_network::convolutional::{matrix_rotate, matrix_rotate_90, pad, PaddingMode};
+
+// every transform draws from the rng it is handed, so a seeded rng such as
+// ChaCha8Rng reproduces the same augmented images; the output keeps the
+// (channels, rows, cols) shape of the input
+pub trait Augmentation {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>>;
+}
+
+// runs the steps in order
+pub struct Pipeline {
+    pub steps: Vec<Box<dyn Augmentation>>,
+}
+
+impl Pipeline {
+    pub fn new(steps: Vec<Box<dyn Augmentation>>) -> Self {
+        Pipeline { steps }
+    }
+
+    pub fn apply_batch(
+        &self,
+        images: &[Vec<Vec<Vec<f32>>>],
+        rng: &mut dyn RngCore,
+    ) -> Vec<Vec<Vec<Vec<f32>>>> {
+        images.iter().map(|image| self.apply(image, rng)).collect()
+    }
+}
+
+impl Augmentation for Pipeline {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        self.steps
+            .iter()
+            .fold(image.to_vec(), |image, step| step.apply(&image, rng))
+    }
+}
+
+// pads every side then crops back to the original size at a random offset
+pub struct RandomCrop {
+    pub padding: (usize, usize),
+    pub mode: PaddingMode,
+}
+
+impl RandomCrop {
+    pub fn new(padding: (usize, usize)) -> Self {
+        RandomCrop {
+            padding,
+            mode: PaddingMode::Zero,
+        }
+    }
+}
+
+impl Augmentation for RandomCrop {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = (image[0].len(), image[0][0].len());
+        let top = rng.gen_range(0..=(2 * self.padding.0));
+        let left = rng.gen_range(0..=(2 * self.padding.1));
+        let sides = (
+            (self.padding.0, self.padding.0),
+            (self.padding.1, self.padding.1),
+        );
+        image
+            .iter()
+            .map(|channel| {
+                pad(channel, sides, self.mode)[top..(top + rows)]
+                    .iter()
+                    .map(|row| row[left..(left + cols)].to_vec())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// mirrors left to right and top to bottom, each with its own probability
+pub struct Flip {
+    pub horizontal: f32,
+    pub vertical: f32,
+}
+
+impl Flip {
+    pub fn new(horizontal: f32, vertical: f32) -> Self {
+        Flip {
+            horizontal,
+            vertical,
+        }
+    }
+}
+
+impl Augmentation for Flip {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let horizontal = rng.gen_range(0.0..1.0) < self.horizontal;
+        let vertical = rng.gen_range(0.0..1.0) < self.vertical;
+        image
+            .iter()
+            .map(|channel| {
+                let mut channel = channel.clone();
+                if horizontal {
+                    channel.iter_mut().for_each(|row| row.reverse());
+                }
+                if vertical {
+                    channel.reverse();
+                }
+                channel
+            })
+            .collect()
+    }
+}
+
+// a random number of quarter turns; a quarter turn would change the shape of a
+// non square image, so those only get turned half way or not at all
+pub struct Rotate90;
+
+impl Augmentation for Rotate90 {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let square = image[0].len() == image[0][0].len();
+        let turns = if square {
+            rng.gen_range(0..4)
+        } else {
+            2 * rng.gen_range(0..2)
+        };
+        image
+            .iter()
+            .map(|channel| match turns {
+                0 => channel.clone(),
+                1 => matrix_rotate_90(channel),
+                2 => matrix_rotate(channel),
+                _ => matrix_rotate(&matrix_rotate_90(channel)),
+            })
+            .collect()
+    }
+}
+
+// rotation in degrees, scale and translation as a fraction of the size are each
+// drawn uniformly from -x..x (scale from 1 - x..1 + x) about the centre; pixels
+// are sampled bilinearly and anything from outside the image is zero
+pub struct Affine {
+    pub rotation: f32,
+    pub translation: (f32, f32),
+    pub scale: f32,
+    pub shear: f32,
+}
+
+impl Affine {
+    pub fn new(rotation: f32, translation: (f32, f32), scale: f32, shear: f32) -> Self {
+        Affine {
+            rotation,
+            translation,
+            scale,
+            shear,
+        }
+    }
+}
+
+fn symmetric(rng: &mut dyn RngCore, extent: f32) -> f32 {
+    if extent > 0.0 {
+        rng.gen_range(-extent..=extent)
+    } else {
+        0.0
+    }
+}
+
+fn bilinear(channel: &[Vec<f32>], y: f32, x: f32) -> f32 {
+    let (y0, x0) = (y.floor(), x.floor());
+    let (dy, dx) = (y - y0, x - x0);
+    let at = |i: f32, j: f32| {
+        if i < 0.0 || j < 0.0 || i as usize >= channel.len() || j as usize >= channel[0].len() {
+            0.0
+        } else {
+            channel[i as usize][j as usize]
+        }
+    };
+    at(y0, x0) * (1.0 - dy) * (1.0 - dx)
+        + at(y0, x0 + 1.0) * (1.0 - dy) * dx
+        + at(y0 + 1.0, x0) * dy * (1.0 - dx)
+        + at(y0 + 1.0, x0 + 1.0) * dy * dx
+}
+
+impl Augmentation for Affine {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = (image[0].len(), image[0][0].len());
+        let angle = symmetric(rng, self.rotation).to_radians();
+        let scale = 1.0 + symmetric(rng, self.scale);
+        let shear = symmetric(rng, self.shear);
+        let shift = (
+            symmetric(rng, self.translation.0) * rows as f32,
+            symmetric(rng, self.translation.1) * cols as f32,
+        );
+        let centre = ((rows as f32 - 1.0) / 2.0, (cols as f32 - 1.0) / 2.0);
+
+        // forward map is shift + scale * rotate * shear, each output pixel reads
+        // the input through its inverse
+        let (sin, cos) = angle.sin_cos();
+        let forward = [
+            [scale * cos, scale * (cos * shear - sin)],
+            [scale * sin, scale * (sin * shear + cos)],
+        ];
+        let det = forward[0][0] * forward[1][1] - forward[0][1] * forward[1][0];
+        let inverse = [
+            [forward[1][1] / det, -forward[0][1] / det],
+            [-forward[1][0] / det, forward[0][0] / det],
+        ];
+        let sources: Vec<Vec<(f32, f32)>> = (0..rows)
+            .map(|i| {
+                (0..cols)
+                    .map(|j| {
+                        let x = j as f32 - centre.1 - shift.1;
+                        let y = i as f32 - centre.0 - shift.0;
+                        (
+                            inverse[1][0] * x + inverse[1][1] * y + centre.0,
+                            inverse[0][0] * x + inverse[0][1] * y + centre.1,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        image
+            .iter()
+            .map(|channel| {
+                sources
+                    .iter()
+                    .map(|row| row.iter().map(|(y, x)| bilinear(channel, *y, *x)).collect())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// adds an offset drawn from -brightness..brightness and scales about each
+// channel mean by a factor drawn from 1 - contrast..1 + contrast, the same for
+// every channel
+pub struct ColourJitter {
+    pub brightness: f32,
+    pub contrast: f32,
+}
+
+impl ColourJitter {
+    pub fn new(brightness: f32, contrast: f32) -> Self {
+        ColourJitter {
+            brightness,
+            contrast,
+        }
+    }
+}
+
+impl Augmentation for ColourJitter {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let offset = symmetric(rng, self.brightness);
+        let factor = 1.0 + symmetric(rng, self.contrast);
+        image
+            .iter()
+            .map(|channel| {
+                let count = (channel.len() * channel[0].len()) as f32;
+                let mean = channel.iter().flatten().sum::<f32>() / count;
+                channel
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|x| (x - mean) * factor + mean + offset)
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+pub struct GaussianNoise {
+    pub std: f32,
+}
+
+impl GaussianNoise {
+    pub fn new(std: f32) -> Self {
+        GaussianNoise { std }
+    }
+}
+
+impl Augmentation for GaussianNoise {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let normal = Normal::new(0.0, self.std).unwrap();
+        image
+            .iter()
+            .map(|channel| {
+                channel
+                    .iter()
+                    .map(|row| row.iter().map(|x| x + normal.sample(rng)).collect())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// zeroes count patches of the given size in every channel, the centres are
+// anywhere in the image so a patch can hang off the edge
+pub struct Cutout {
+    pub size: (usize, usize),
+    pub count: usize,
+}
+
+impl Cutout {
+    pub fn new(size: (usize, usize), count: usize) -> Self {
+        Cutout { size, count }
+    }
+}
+
+impl Augmentation for Cutout {
+    fn apply(&self, image: &[Vec<Vec<f32>>], rng: &mut dyn RngCore) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = (image[0].len(), image[0][0].len());
+        let mut image = image.to_vec();
+        for _ in 0..self.count {
+            let (i, j) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+            let top = i.saturating_sub(self.size.0 / 2);
+            let left = j.saturating_sub(self.size.1 / 2);
+            let bottom = (i + self.size.0 - self.size.0 / 2).min(rows);
+            let right = (j + self.size.1 - self.size.1 / 2).min(cols);
+            for channel in image.iter_mut() {
+                for row in channel[top..bottom].iter_mut() {
+                    row[left..right].fill(0.0);
+                }
+            }
+        }
+        image
+    }
+}
@@ -0,0 +1,51 @@
+use std::{fs, io, path::Path};
+
+use super::{invalid, Dataset, Normalisation};
+
+pub const CIFAR_CLASSES: [&str; 10] = [
+    "airplane",
+    "automobile",
+    "bird",
+    "cat",
+    "deer",
+    "dog",
+    "frog",
+    "horse",
+    "ship",
+    "truck",
+];
+
+const SIDE: usize = 32;
+const RECORD: usize = 1 + 3 * SIDE * SIDE;
+
+// the CIFAR-10 binary batches are records of a label byte followed by the red,
+// green and blue planes of a 32x32 image, each in row major order
+pub fn parse_cifar(bytes: &[u8], normalisation: &Normalisation) -> io::Result<Dataset> {
+    if !bytes.len().is_multiple_of(RECORD) {
+        return Err(invalid("cifar data is not a whole number of records"));
+    }
+    let mut images = Vec::new();
+    let mut labels = Vec::new();
+    for record in bytes.chunks(RECORD) {
+        if record[0] as usize >= CIFAR_CLASSES.len() {
+            return Err(invalid("cifar label out of range"));
+        }
+        labels.push(record[0] as usize);
+        let mut image: Vec<Vec<Vec<f32>>> = record[1..]
+            .chunks(SIDE * SIDE)
+            .map(|plane| {
+                plane
+                    .chunks(SIDE)
+                    .map(|row| row.iter().map(|x| *x as f32 / 255.0).collect())
+                    .collect()
+            })
+            .collect();
+        normalisation.apply(&mut image);
+        images.push(image);
+    }
+    Ok((images, labels))
+}
+
+pub fn read_cifar(path: impl AsRef<Path>, normalisation: &Normalisation) -> io::Result<Dataset> {
+    parse_cifar(&fs::read(path)?, normalisation)
+}
@@ -0,0 +1,118 @@
+use std::{fs, io, path::Path};
+
+use super::{invalid, Dataset, Normalisation};
+
+// an IDX array, the format of MNIST and Fashion-MNIST; values are widened to f32
+// whatever the stored type, which is kept as its type code
+#[derive(Clone, Debug, PartialEq)]
+pub struct Idx {
+    pub kind: u8,
+    pub dims: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+// the magic number is two zero bytes, a type code and the number of dimensions,
+// followed by each dimension as a big endian u32 and then the values
+pub fn parse_idx(bytes: &[u8]) -> io::Result<Idx> {
+    if bytes.len() < 4 || bytes[0] != 0 || bytes[1] != 0 {
+        return Err(invalid("not an idx file"));
+    }
+    let width = match bytes[2] {
+        0x08 | 0x09 => 1usize,
+        0x0B => 2,
+        0x0C | 0x0D => 4,
+        0x0E => 8,
+        _ => return Err(invalid("unknown idx data type")),
+    };
+    let rank = bytes[3] as usize;
+    let header = 4 + 4 * rank;
+    if bytes.len() < header {
+        return Err(invalid("truncated idx header"));
+    }
+    let dims: Vec<usize> = bytes[4..header]
+        .chunks(4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .collect();
+    let end = dims
+        .iter()
+        .try_fold(width, |acc, d| acc.checked_mul(*d))
+        .and_then(|size| size.checked_add(header))
+        .ok_or_else(|| invalid("idx dimensions too large"))?;
+    if bytes.len() < end {
+        return Err(invalid("truncated idx data"));
+    }
+    let data = bytes[header..end]
+        .chunks(width)
+        .map(|b| match bytes[2] {
+            0x08 => b[0] as f32,
+            0x09 => b[0] as i8 as f32,
+            0x0B => i16::from_be_bytes([b[0], b[1]]) as f32,
+            0x0C => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f32,
+            0x0D => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            _ => f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+        })
+        .collect();
+    Ok(Idx {
+        kind: bytes[2],
+        dims,
+        data,
+    })
+}
+
+pub fn read_idx(path: impl AsRef<Path>) -> io::Result<Idx> {
+    parse_idx(&fs::read(path)?)
+}
+
+// a (count, rows, cols) array as single channel images, bytes scaled to [0, 1]
+// and any other type left as stored
+pub fn idx_images(idx: &Idx, normalisation: &Normalisation) -> io::Result<Vec<Vec<Vec<Vec<f32>>>>> {
+    if idx.dims.len() != 3 {
+        return Err(invalid("expected a three dimensional idx array of images"));
+    }
+    let (rows, cols) = (idx.dims[1], idx.dims[2]);
+    if rows == 0 || cols == 0 {
+        return Err(invalid("idx images with no pixels"));
+    }
+    let scale = if idx.kind == 0x08 { 255.0 } else { 1.0 };
+    Ok(idx
+        .data
+        .chunks(rows * cols)
+        .map(|pixels| {
+            let mut image = vec![pixels
+                .chunks(cols)
+                .map(|row| row.iter().map(|x| x / scale).collect())
+                .collect()];
+            normalisation.apply(&mut image);
+            image
+        })
+        .collect())
+}
+
+pub fn idx_labels(idx: &Idx) -> io::Result<Vec<usize>> {
+    if idx.dims.len() != 1 {
+        return Err(invalid("expected a one dimensional idx array of labels"));
+    }
+    Ok(idx.data.iter().map(|x| *x as usize).collect())
+}
+
+// reads the images and labels of MNIST or Fashion-MNIST from the uncompressed
+// files under their usual names, train-images-idx3-ubyte and so on
+pub fn load_mnist(
+    directory: impl AsRef<Path>,
+    train: bool,
+    normalisation: &Normalisation,
+) -> io::Result<Dataset> {
+    let prefix = if train { "train" } else { "t10k" };
+    let directory = directory.as_ref();
+    let images = idx_images(
+        &read_idx(directory.join(format!("{prefix}-images-idx3-ubyte")))?,
+        normalisation,
+    )?;
+    let labels = idx_labels(&read_idx(
+        directory.join(format!("{prefix}-labels-idx1-ubyte")),
+    )?)?;
+    if images.len() != labels.len() {
+        return Err(invalid("image and label counts differ"));
+    }
+    Ok((images, labels))
+}
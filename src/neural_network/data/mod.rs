@@ -0,0 +1,66 @@
+pub mod augment;
+pub mod cifar;
+pub mod idx;
+pub mod netpbm;
+mod test;
+
+pub use augment::*;
+pub use cifar::*;
+pub use idx::*;
+pub use netpbm::*;
+
+use std::io;
+
+// images in the (channels, rows, cols) layout Layer2D takes and their labels
+pub type Dataset = (Vec<Vec<Vec<Vec<f32>>>>, Vec<usize>);
+
+// the readers scale pixels to [0, 1] and then apply one of these; Standard takes
+// a mean and standard deviation per channel
+#[derive(Clone, Debug, PartialEq)]
+pub enum Normalisation {
+    Unit,
+    Centred,
+    Standard(Vec<f32>, Vec<f32>),
+}
+
+impl Normalisation {
+    pub fn apply(&self, image: &mut [Vec<Vec<f32>>]) {
+        for (c, channel) in image.iter_mut().enumerate() {
+            let (shift, scale) = match self {
+                Normalisation::Unit => (0.0, 1.0),
+                Normalisation::Centred => (0.5, 0.5),
+                Normalisation::Standard(mean, std) => (mean[c], std[c]),
+            };
+            for x in channel.iter_mut().flatten() {
+                *x = (*x - shift) / scale;
+            }
+        }
+    }
+}
+
+// the per channel mean and standard deviation over a set of images, to build
+// Normalisation::Standard from the training set
+pub fn channel_statistics(images: &[Vec<Vec<Vec<f32>>>]) -> (Vec<f32>, Vec<f32>) {
+    let channels = images[0].len();
+    let mut mean = vec![0.0; channels];
+    let mut std = vec![0.0; channels];
+    for c in 0..channels {
+        let values = || images.iter().flat_map(|image| image[c].iter().flatten());
+        let count = values().count() as f64;
+        let m = values().map(|x| *x as f64).sum::<f64>() / count;
+        let v = values().map(|x| (*x as f64 - m).powi(2)).sum::<f64>() / count;
+        mean[c] = m as f32;
+        std[c] = v.sqrt().max(1e-12) as f32;
+    }
+    (mean, std)
+}
+
+pub fn one_hot(label: usize, classes: usize) -> Vec<f32> {
+    let mut target = vec![0.0; classes];
+    target[label] = 1.0;
+    target
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
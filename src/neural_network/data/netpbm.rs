@@ -0,0 +1,127 @@
+use std::{fs, io, path::Path};
+
+use super::{invalid, Normalisation};
+
+// reads the next whitespace separated header token, skipping # comments
+fn token<'a>(bytes: &'a [u8], position: &mut usize) -> io::Result<&'a [u8]> {
+    loop {
+        while *position < bytes.len() && bytes[*position].is_ascii_whitespace() {
+            *position += 1;
+        }
+        if *position < bytes.len() && bytes[*position] == b'#' {
+            while *position < bytes.len() && bytes[*position] != b'\n' {
+                *position += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let start = *position;
+    while *position < bytes.len() && !bytes[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+    if start == *position {
+        return Err(invalid("truncated netpbm header"));
+    }
+    Ok(&bytes[start..*position])
+}
+
+fn number(bytes: &[u8], position: &mut usize) -> io::Result<usize> {
+    std::str::from_utf8(token(bytes, position)?)
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(|| invalid("expected a number in netpbm data"))
+}
+
+// greyscale PGM (P2 and P5) gives one channel and PPM (P3 and P6) three, scaled
+// by the maximum value to [0, 1] before normalising; binary files with a maximum
+// above 255 store big endian pairs of bytes
+pub fn parse_netpbm(bytes: &[u8], normalisation: &Normalisation) -> io::Result<Vec<Vec<Vec<f32>>>> {
+    let mut position = 0;
+    let (channels, binary) = match token(bytes, &mut position)? {
+        b"P2" => (1, false),
+        b"P3" => (3, false),
+        b"P5" => (1, true),
+        b"P6" => (3, true),
+        _ => return Err(invalid("not a pgm or ppm image")),
+    };
+    let cols = number(bytes, &mut position)?;
+    let rows = number(bytes, &mut position)?;
+    let max = number(bytes, &mut position)?;
+    if max == 0 || max > 65535 {
+        return Err(invalid("netpbm maximum value out of range"));
+    }
+    let count = rows
+        .checked_mul(cols)
+        .and_then(|n| n.checked_mul(channels))
+        .ok_or_else(|| invalid("netpbm image too large"))?;
+
+    let samples: Vec<usize> = if binary {
+        // a single whitespace byte separates the header from the raster
+        let start = position + 1;
+        let width = if max > 255 { 2 } else { 1 };
+        let end = count
+            .checked_mul(width)
+            .and_then(|n| n.checked_add(start))
+            .ok_or_else(|| invalid("netpbm image too large"))?;
+        if bytes.len() < end {
+            return Err(invalid("truncated netpbm raster"));
+        }
+        bytes[start..end]
+            .chunks(width)
+            .map(|b| b.iter().fold(0, |acc, x| (acc << 8) | *x as usize))
+            .collect()
+    } else {
+        (0..count)
+            .map(|_| number(bytes, &mut position))
+            .collect::<io::Result<_>>()?
+    };
+    if samples.iter().any(|x| *x > max) {
+        return Err(invalid("netpbm sample above the maximum value"));
+    }
+
+    // samples are interleaved per pixel
+    let mut image: Vec<Vec<Vec<f32>>> = (0..channels)
+        .map(|c| {
+            (0..rows)
+                .map(|i| {
+                    (0..cols)
+                        .map(|j| samples[(i * cols + j) * channels + c] as f32 / max as f32)
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    normalisation.apply(&mut image);
+    Ok(image)
+}
+
+pub fn read_netpbm(
+    path: impl AsRef<Path>,
+    normalisation: &Normalisation,
+) -> io::Result<Vec<Vec<Vec<f32>>>> {
+    parse_netpbm(&fs::read(path)?, normalisation)
+}
+
+// a binary P5 or P6 image from one or three channels of values in [0, 1]
+pub fn encode_netpbm(image: &[Vec<Vec<f32>>]) -> Vec<u8> {
+    let magic = match image.len() {
+        1 => "P5",
+        3 => "P6",
+        _ => panic!("netpbm images have one or three channels"),
+    };
+    let (rows, cols) = (image[0].len(), image[0][0].len());
+    let mut bytes = format!("{magic}\n{cols} {rows}\n255\n").into_bytes();
+    for i in 0..rows {
+        for j in 0..cols {
+            for channel in image {
+                bytes.push((channel[i][j].clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+    }
+    bytes
+}
+
+pub fn write_netpbm(path: impl AsRef<Path>, image: &[Vec<Vec<f32>>]) -> io::Result<()> {
+    fs::write(path, encode_netpbm(image))
+}
@@ -0,0 +1,341 @@
+#[cfg(test)]
+mod test_data {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::neural_network::convolutional::{matrix_rotate, matrix_rotate_90};
+    use crate::neural_network::data::{
+        channel_statistics, encode_netpbm, idx_images, idx_labels, load_mnist, one_hot,
+        parse_cifar, parse_idx, parse_netpbm, read_netpbm, write_netpbm, Affine, Augmentation,
+        ColourJitter, Cutout, Flip, GaussianNoise, Normalisation, Pipeline, RandomCrop, Rotate90,
+    };
+
+    fn idx_bytes(code: u8, dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, code, dims.len() as u8];
+        for d in dims {
+            bytes.extend(d.to_be_bytes());
+        }
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_idx() {
+        let pixels: Vec<u8> = (0..12).map(|x| x * 20).collect();
+        let idx = parse_idx(&idx_bytes(0x08, &[2, 2, 3], &pixels)).unwrap();
+        assert_eq!(idx.dims, [2, 2, 3]);
+        let images = idx_images(&idx, &Normalisation::Unit).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(
+            images[1][0][1],
+            [180.0 / 255.0, 200.0 / 255.0, 220.0 / 255.0]
+        );
+
+        let labels = parse_idx(&idx_bytes(0x08, &[3], &[7, 0, 9])).unwrap();
+        assert_eq!(idx_labels(&labels).unwrap(), [7, 0, 9]);
+
+        let mut wide = Vec::new();
+        for x in [-2.5f32, 4.0] {
+            wide.extend(x.to_be_bytes());
+        }
+        assert_eq!(
+            parse_idx(&idx_bytes(0x0D, &[2], &wide)).unwrap().data,
+            [-2.5, 4.0]
+        );
+        let mut short = Vec::new();
+        for x in [-300i16, 2] {
+            short.extend(x.to_be_bytes());
+        }
+        assert_eq!(
+            parse_idx(&idx_bytes(0x0B, &[2], &short)).unwrap().data,
+            [-300.0, 2.0]
+        );
+        // only bytes are scaled as pixels
+        let images = idx_images(
+            &parse_idx(&idx_bytes(0x0B, &[1, 1, 2], &short)).unwrap(),
+            &Normalisation::Unit,
+        )
+        .unwrap();
+        assert_eq!(images[0][0][0], [-300.0, 2.0]);
+    }
+
+    #[test]
+    fn test_parse_idx_errors() {
+        assert!(parse_idx(&[1, 0, 8, 1]).is_err());
+        assert!(parse_idx(&idx_bytes(0x07, &[1], &[0])).is_err());
+        assert!(parse_idx(&idx_bytes(0x08, &[4], &[0, 1])).is_err());
+        let labels = parse_idx(&idx_bytes(0x08, &[3], &[1, 2, 3])).unwrap();
+        assert!(idx_images(&labels, &Normalisation::Unit).is_err());
+        // dimensions whose product overflows, and images with no pixels
+        let huge = [u32::MAX, u32::MAX, u32::MAX];
+        assert!(parse_idx(&idx_bytes(0x0C, &huge, &[])).is_err());
+        let empty = parse_idx(&idx_bytes(0x08, &[2, 0, 3], &[])).unwrap();
+        assert!(idx_images(&empty, &Normalisation::Unit).is_err());
+    }
+
+    #[test]
+    fn test_load_mnist() {
+        let directory = std::env::temp_dir().join(format!("mnist-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let pixels: Vec<u8> = (0..(3 * 28 * 28)).map(|x| (x % 256) as u8).collect();
+        std::fs::write(
+            directory.join("t10k-images-idx3-ubyte"),
+            idx_bytes(0x08, &[3, 28, 28], &pixels),
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join("t10k-labels-idx1-ubyte"),
+            idx_bytes(0x08, &[3], &[4, 1, 8]),
+        )
+        .unwrap();
+        let (images, labels) = load_mnist(&directory, false, &Normalisation::Centred).unwrap();
+        assert_eq!(labels, [4, 1, 8]);
+        assert_eq!(
+            (images[2].len(), images[2].len(), images[2][0][0].len()),
+            (1, 1, 28)
+        );
+        assert_eq!(images[0][0][0][0], -1.0);
+        assert_eq!(images[0][0][9][3], 1.0);
+        assert!(load_mnist(&directory, true, &Normalisation::Unit).is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_parse_cifar() {
+        let mut bytes = Vec::new();
+        for label in [3u8, 9] {
+            bytes.push(label);
+            for c in 0..3 {
+                bytes.extend((0..1024).map(|p| ((p + c * 50 + label as usize) % 256) as u8));
+            }
+        }
+        let (images, labels) = parse_cifar(&bytes, &Normalisation::Unit).unwrap();
+        assert_eq!(labels, [3, 9]);
+        assert_eq!(
+            (images[0].len(), images[0][0].len(), images[0][0][0].len()),
+            (3, 32, 32)
+        );
+        assert_eq!(images[1][2][1][0], ((32 + 100 + 9) as f32) / 255.0);
+        assert!(parse_cifar(&bytes[1..], &Normalisation::Unit).is_err());
+        bytes[0] = 10;
+        assert!(parse_cifar(&bytes, &Normalisation::Unit).is_err());
+    }
+
+    #[test]
+    fn test_parse_netpbm() {
+        let ascii = b"P2\n# a comment\n3 2\n# another\n10\n0 5 10\n10 5 0\n";
+        let image = parse_netpbm(ascii, &Normalisation::Unit).unwrap();
+        assert_eq!(image, [[[0.0, 0.5, 1.0], [1.0, 0.5, 0.0]]]);
+
+        let colour = b"P3 2 1 255 255 0 0 0 0 255";
+        let image = parse_netpbm(colour, &Normalisation::Centred).unwrap();
+        assert_eq!(image, [[[1.0, -1.0]], [[-1.0, -1.0]], [[-1.0, 1.0]]]);
+
+        let mut deep = b"P5 2 1 1000\n".to_vec();
+        deep.extend(500u16.to_be_bytes());
+        deep.extend(1000u16.to_be_bytes());
+        assert_eq!(
+            parse_netpbm(&deep, &Normalisation::Unit).unwrap(),
+            [[[0.5, 1.0]]]
+        );
+
+        assert!(parse_netpbm(b"P7 1 1 255\n", &Normalisation::Unit).is_err());
+        assert!(parse_netpbm(b"P5 2 2 255\n\x01\x02", &Normalisation::Unit).is_err());
+        assert!(parse_netpbm(b"P2 1 1 3 4", &Normalisation::Unit).is_err());
+        let huge = format!("P6 {} {} 255\n", usize::MAX, usize::MAX);
+        assert!(parse_netpbm(huge.as_bytes(), &Normalisation::Unit).is_err());
+    }
+
+    #[test]
+    fn test_netpbm_round_trip() {
+        let image: Vec<Vec<Vec<f32>>> = (0..3)
+            .map(|c| {
+                (0..4)
+                    .map(|i| {
+                        (0..5)
+                            .map(|j| ((c * 20 + i * 5 + j) * 4) as f32 / 255.0)
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let bytes = encode_netpbm(&image);
+        assert!(bytes.starts_with(b"P6\n5 4\n255\n"));
+        let decoded = parse_netpbm(&bytes, &Normalisation::Unit).unwrap();
+        assert!(decoded
+            .iter()
+            .flatten()
+            .flatten()
+            .zip(image.iter().flatten().flatten())
+            .all(|(x, y)| (x - y).abs() < 1e-6));
+
+        let path = std::env::temp_dir().join(format!("grey-{}.pgm", std::process::id()));
+        write_netpbm(&path, &image[..1]).unwrap();
+        assert_eq!(read_netpbm(&path, &Normalisation::Unit).unwrap().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_normalisation() {
+        let images = vec![
+            vec![vec![vec![0.0, 1.0]], vec![vec![2.0, 2.0]]],
+            vec![vec![vec![2.0, 3.0]], vec![vec![4.0, 4.0]]],
+        ];
+        let (mean, std) = channel_statistics(&images);
+        assert_eq!(mean, [1.5, 3.0]);
+        assert!((std[0] - 1.25f32.sqrt()).abs() < 1e-6 && (std[1] - 1.0).abs() < 1e-6);
+        let mut image = images[1].clone();
+        Normalisation::Standard(mean, std).apply(&mut image);
+        assert!((image[1][0][0] - 1.0).abs() < 1e-6);
+        assert_eq!(one_hot(2, 4), [0.0, 0.0, 1.0, 0.0]);
+    }
+
+    fn gradient_image(channels: usize, rows: usize, cols: usize) -> Vec<Vec<Vec<f32>>> {
+        (0..channels)
+            .map(|c| {
+                (0..rows)
+                    .map(|i| (0..cols).map(|j| (c * 100 + i * cols + j) as f32).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pipeline_is_seeded() {
+        let pipeline = Pipeline::new(vec![
+            Box::new(RandomCrop::new((2, 2))),
+            Box::new(Flip::new(0.5, 0.5)),
+            Box::new(Rotate90),
+            Box::new(Affine::new(15.0, (0.1, 0.1), 0.1, 0.1)),
+            Box::new(ColourJitter::new(0.2, 0.2)),
+            Box::new(GaussianNoise::new(0.1)),
+            Box::new(Cutout::new((2, 2), 1)),
+        ]);
+        let images = vec![gradient_image(3, 6, 6), gradient_image(3, 6, 6)];
+        let first = pipeline.apply_batch(&images, &mut ChaCha8Rng::seed_from_u64(3));
+        let again = pipeline.apply_batch(&images, &mut ChaCha8Rng::seed_from_u64(3));
+        let other = pipeline.apply_batch(&images, &mut ChaCha8Rng::seed_from_u64(4));
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(
+            (first[0].len(), first[0][0].len(), first[0][0][0].len()),
+            (3, 6, 6)
+        );
+    }
+
+    #[test]
+    fn test_random_crop() {
+        let image = gradient_image(2, 4, 5);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(RandomCrop::new((0, 0)).apply(&image, &mut rng), image);
+        for _ in 0..10 {
+            let cropped = RandomCrop::new((1, 2)).apply(&image, &mut rng);
+            // the crop is the image moved by at most the padding with zeros
+            // shifted in, so some offset lines every nonzero pixel up
+            let fits = |di: isize, dj: isize| {
+                (0..2).all(|c| {
+                    (0..4).all(|i| {
+                        (0..5).all(|j| {
+                            let (y, x) = (i as isize + di, j as isize + dj);
+                            let expected = if (0..4).contains(&y) && (0..5).contains(&x) {
+                                image[c][y as usize][x as usize]
+                            } else {
+                                0.0
+                            };
+                            cropped[c][i][j] == expected
+                        })
+                    })
+                })
+            };
+            assert!((-1..=1).any(|di| (-2..=2).any(|dj| fits(di, dj))));
+        }
+    }
+
+    #[test]
+    fn test_flip_and_rotate() {
+        let image = gradient_image(1, 3, 3);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(Flip::new(0.0, 0.0).apply(&image, &mut rng), image);
+        assert_eq!(
+            Flip::new(1.0, 1.0).apply(&image, &mut rng)[0],
+            matrix_rotate(&image[0])
+        );
+        let quarter = matrix_rotate_90(&image[0]);
+        assert_eq!(quarter[0], [6.0, 3.0, 0.0]);
+        assert_eq!(matrix_rotate_90(&quarter), matrix_rotate(&image[0]));
+
+        let turns: Vec<Vec<Vec<f32>>> = (0..4)
+            .scan(image[0].clone(), |x, _| {
+                let current = x.clone();
+                *x = matrix_rotate_90(x);
+                Some(current)
+            })
+            .collect();
+        for _ in 0..10 {
+            assert!(turns.contains(&Rotate90.apply(&image, &mut rng)[0]));
+        }
+        let wide = gradient_image(1, 2, 3);
+        for _ in 0..10 {
+            let turned = Rotate90.apply(&wide, &mut rng);
+            assert!(turned == wide || turned[0] == matrix_rotate(&wide[0]));
+        }
+    }
+
+    #[test]
+    fn test_affine() {
+        let image = gradient_image(2, 5, 4);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(
+            Affine::new(0.0, (0.0, 0.0), 0.0, 0.0).apply(&image, &mut rng),
+            image
+        );
+        // a small rotation about the centre keeps the centre pixel of an odd
+        // image and leaves the mean roughly in place
+        let square = gradient_image(1, 5, 5);
+        let rotated = Affine::new(10.0, (0.0, 0.0), 0.0, 0.0).apply(&square, &mut rng);
+        assert!((rotated[0][2][2] - square[0][2][2]).abs() < 1e-4);
+        assert_ne!(rotated, square);
+    }
+
+    #[test]
+    fn test_colour_and_noise() {
+        let image = gradient_image(2, 4, 4);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let brighter = ColourJitter::new(0.5, 0.0).apply(&image, &mut rng);
+        let offset = brighter[0][0][0] - image[0][0][0];
+        assert!(offset.abs() <= 0.5);
+        assert!(brighter
+            .iter()
+            .flatten()
+            .flatten()
+            .zip(image.iter().flatten().flatten())
+            .all(|(x, y)| (x - y - offset).abs() < 1e-4));
+
+        let contrasted = ColourJitter::new(0.0, 0.5).apply(&image, &mut rng);
+        for (x, y) in contrasted.iter().zip(image.iter()) {
+            let mean = |c: &Vec<Vec<f32>>| c.iter().flatten().sum::<f32>() / 16.0;
+            assert!((mean(x) - mean(y)).abs() < 1e-3);
+        }
+
+        let flat = vec![vec![vec![0.0; 50]; 40]];
+        let noisy = GaussianNoise::new(0.2).apply(&flat, &mut rng);
+        let values: Vec<f32> = noisy.iter().flatten().flatten().copied().collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let std =
+            (values.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / values.len() as f32).sqrt();
+        assert!(mean.abs() < 0.03 && (std - 0.2).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_cutout() {
+        let image = vec![vec![vec![1.0; 8]; 8]; 2];
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for _ in 0..10 {
+            let cut = Cutout::new((3, 3), 1).apply(&image, &mut rng);
+            let zeros = cut[0].iter().flatten().filter(|x| **x == 0.0).count();
+            assert!((1..=9).contains(&zeros));
+            assert_eq!(cut[0], cut[1]);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// centre and size as fractions of the image, so boxes don't depend on resolution
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl BoundingBox {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        BoundingBox {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_corners(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        BoundingBox::new(
+            (left + right) / 2.0,
+            (top + bottom) / 2.0,
+            right - left,
+            bottom - top,
+        )
+    }
+
+    // (left, top, right, bottom)
+    pub fn corners(&self) -> (f32, f32, f32, f32) {
+        (
+            self.x - self.width / 2.0,
+            self.y - self.height / 2.0,
+            self.x + self.width / 2.0,
+            self.y + self.height / 2.0,
+        )
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width.max(0.0) * self.height.max(0.0)
+    }
+}
+
+// a ground truth object
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Annotation {
+    pub bbox: BoundingBox,
+    pub class: usize,
+}
+
+impl Annotation {
+    pub fn new(bbox: BoundingBox, class: usize) -> Self {
+        Annotation { bbox, class }
+    }
+}
+
+// a predicted object, score is objectness times the class probability
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detection {
+    pub bbox: BoundingBox,
+    pub class: usize,
+    pub score: f32,
+}
+
+impl Detection {
+    pub fn new(bbox: BoundingBox, class: usize, score: f32) -> Self {
+        Detection { bbox, class, score }
+    }
+}
+
+fn intersection(a: &BoundingBox, b: &BoundingBox) -> f32 {
+    let (al, at, ar, ab) = a.corners();
+    let (bl, bt, br, bb) = b.corners();
+    (ar.min(br) - al.max(bl)).max(0.0) * (ab.min(bb) - at.max(bt)).max(0.0)
+}
+
+pub fn iou(a: &BoundingBox, b: &BoundingBox) -> f32 {
+    let overlap = intersection(a, b);
+    let union = a.area() + b.area() - overlap;
+    if union > 0.0 {
+        overlap / union
+    } else {
+        0.0
+    }
+}
+
+// IoU less the fraction of the smallest enclosing box that neither covers, which
+// still says how far apart boxes are when they don't overlap; in -1..1
+pub fn giou(a: &BoundingBox, b: &BoundingBox) -> f32 {
+    let overlap = intersection(a, b);
+    let union = a.area() + b.area() - overlap;
+    let (al, at, ar, ab) = a.corners();
+    let (bl, bt, br, bb) = b.corners();
+    let enclosing = (ar.max(br) - al.min(bl)) * (ab.max(bb) - at.min(bt));
+    if union <= 0.0 || enclosing <= 0.0 {
+        return 0.0;
+    }
+    overlap / union - (enclosing - union) / enclosing
+}
+
+// keeps the highest scoring detections, dropping any that overlap a kept
+// detection of the same class by more than the threshold
+pub fn non_max_suppression(detections: &[Detection], threshold: f32) -> Vec<Detection> {
+    let mut sorted = detections.to_vec();
+    sorted.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut kept: Vec<Detection> = Vec::new();
+    for detection in sorted {
+        if kept
+            .iter()
+            .all(|k| k.class != detection.class || iou(&k.bbox, &detection.bbox) <= threshold)
+        {
+            kept.push(detection);
+        }
+    }
+    kept
+}
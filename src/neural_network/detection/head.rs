@@ -0,0 +1,153 @@
+use crate::neural_network::convolutional::Conv2D;
+use crate::neural_network::core::{Activation, Function, Layer, Layer2D, Softmax};
+
+use super::{iou, Annotation, BoundingBox, Detection};
+
+// the layout of the head output: every cell of a rows x cols grid predicts one
+// box per anchor, anchor a owning channels a * (5 + classes) onwards as
+// [x offset, y offset, log width, log height, objectness, class logits..];
+// anchors are (width, height) as fractions of the image
+#[derive(Clone, Debug, PartialEq)]
+pub struct YoloGrid {
+    pub grid: (usize, usize),
+    pub anchors: Vec<(f32, f32)>,
+    pub classes: usize,
+}
+
+impl YoloGrid {
+    pub fn new(grid: (usize, usize), anchors: Vec<(f32, f32)>, classes: usize) -> Self {
+        assert!(classes > 0, "a detection head needs at least one class");
+        YoloGrid {
+            grid,
+            anchors,
+            classes,
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.anchors.len() * (5 + self.classes)
+    }
+
+    // the anchor whose shape best matches the box when both share a centre
+    pub fn best_anchor(&self, bbox: &BoundingBox) -> usize {
+        let shape = |w: f32, h: f32| BoundingBox::new(0.0, 0.0, w, h);
+        let target = shape(bbox.width, bbox.height);
+        (0..self.anchors.len())
+            .max_by(|a, b| {
+                let (wa, ha) = self.anchors[*a];
+                let (wb, hb) = self.anchors[*b];
+                iou(&shape(wa, ha), &target).total_cmp(&iou(&shape(wb, hb), &target))
+            })
+            .unwrap()
+    }
+
+    // the training target in the output layout: each object goes to the cell
+    // holding its centre and its best anchor, offsets are where the centre falls
+    // in the cell and sizes are logs relative to the anchor; the objectness
+    // channel marks the assigned anchors, a later object in the same slot wins
+    pub fn encode(&self, objects: &[Annotation]) -> Vec<Vec<Vec<f32>>> {
+        let (rows, cols) = self.grid;
+        let mut target = vec![vec![vec![0.0; cols]; rows]; self.channels()];
+        for object in objects {
+            let bbox = object.bbox;
+            let gx = (bbox.x * cols as f32).clamp(0.0, cols as f32 - 1e-4);
+            let gy = (bbox.y * rows as f32).clamp(0.0, rows as f32 - 1e-4);
+            let (i, j) = (gy as usize, gx as usize);
+            let anchor = self.best_anchor(&bbox);
+            let (aw, ah) = self.anchors[anchor];
+            let base = anchor * (5 + self.classes);
+            let values = [
+                gx - j as f32,
+                gy - i as f32,
+                (bbox.width / aw).ln(),
+                (bbox.height / ah).ln(),
+                1.0,
+            ];
+            for (k, v) in values.iter().enumerate() {
+                target[base + k][i][j] = *v;
+            }
+            for c in 0..self.classes {
+                target[base + 5 + c][i][j] = if c == object.class { 1.0 } else { 0.0 };
+            }
+        }
+        target
+    }
+
+    // every box scoring at least the threshold, before non maximum suppression
+    pub fn decode(&self, output: &[Vec<Vec<f32>>], threshold: f32) -> Vec<Detection> {
+        let (rows, cols) = self.grid;
+        let sigmoid = Function::Sigmoid.activation();
+        let softmax = Softmax::new(self.classes);
+        let mut detections = Vec::new();
+        for (a, (aw, ah)) in self.anchors.iter().enumerate() {
+            let base = a * (5 + self.classes);
+            let cells = (0..rows).flat_map(|i| (0..cols).map(move |j| (i, j)));
+            for (i, j) in cells {
+                let at = |k: usize| output[base + k][i][j];
+                let logits: Vec<f32> = (0..self.classes).map(|c| at(5 + c)).collect();
+                let probabilities = softmax.forward(&logits);
+                let (class, p) = probabilities
+                    .iter()
+                    .enumerate()
+                    .max_by(|x, y| x.1.total_cmp(y.1))
+                    .unwrap();
+                let s = sigmoid(&[at(0), at(1), at(4)]);
+                let score = s[2] * p;
+                if score >= threshold {
+                    let bbox = BoundingBox::new(
+                        (j as f32 + s[0]) / cols as f32,
+                        (i as f32 + s[1]) / rows as f32,
+                        aw * at(2).exp(),
+                        ah * at(3).exp(),
+                    );
+                    detections.push(Detection::new(bbox, class, score));
+                }
+            }
+        }
+        detections
+    }
+}
+
+// a 1x1 convolution from the backbone features to the raw predictions of every
+// cell, the grid is the spatial size of the features; decode and the loss only
+// need the grid so it can be cloned out before the head goes into a Sequential
+pub struct YoloHead {
+    pub conv: Conv2D,
+    pub grid: YoloGrid,
+}
+
+impl YoloHead {
+    pub fn new(dim_in: (usize, usize, usize), anchors: Vec<(f32, f32)>, classes: usize) -> Self {
+        let grid = YoloGrid::new((dim_in.1, dim_in.2), anchors, classes);
+        YoloHead {
+            conv: Conv2D::new(dim_in, grid.channels(), (1, 1), (0, 0), (1, 1)),
+            grid,
+        }
+    }
+}
+
+impl Layer2D for YoloHead {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.conv.dim_in()
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.conv.dim_out()
+    }
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        self.conv.forward(input)
+    }
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        self.conv.back(input, error)
+    }
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        self.conv.parameters()
+    }
+}
@@ -0,0 +1,85 @@
+use crate::neural_network::core::{
+    cross_entropy, Activation, Derivative, Function, Layer, Softmax,
+};
+
+use super::YoloGrid;
+
+// the weights of the box and the empty anchor terms against the objectness and
+// class terms, 5 and 0.5 in the original YOLO
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct YoloLoss {
+    pub coordinate: f32,
+    pub no_object: f32,
+}
+
+impl YoloLoss {
+    pub fn new(coordinate: f32, no_object: f32) -> Self {
+        YoloLoss {
+            coordinate,
+            no_object,
+        }
+    }
+
+    // the loss of one image and its error with respect to the raw head output;
+    // objectness is binary cross entropy on every anchor, while the squared error
+    // of the sigmoid offsets and log sizes and the softmax cross entropy of the
+    // class only count for anchors with an object assigned by encode
+    pub fn loss(
+        &self,
+        grid: &YoloGrid,
+        output: &[Vec<Vec<f32>>],
+        target: &[Vec<Vec<f32>>],
+    ) -> (f32, Vec<Vec<Vec<f32>>>) {
+        let (rows, cols) = grid.grid;
+        let stride = 5 + grid.classes;
+        let (sigmoid, gradient) = (
+            Function::Sigmoid.activation(),
+            Function::Sigmoid.derivative(),
+        );
+        let softmax = Softmax::new(grid.classes);
+        let mut total = 0.0;
+        let mut error = vec![vec![vec![0.0; cols]; rows]; output.len()];
+        for a in 0..grid.anchors.len() {
+            let base = a * stride;
+            for i in 0..rows {
+                for j in 0..cols {
+                    let object = target[base + 4][i][j];
+                    let p = sigmoid(&[output[base + 4][i][j]])[0];
+                    let weight = if object > 0.0 { 1.0 } else { self.no_object };
+                    total -= weight
+                        * (object * p.max(1e-7).ln() + (1.0 - object) * (1.0 - p).max(1e-7).ln());
+                    error[base + 4][i][j] = weight * (p - object);
+                    if object == 0.0 {
+                        continue;
+                    }
+
+                    for k in 0..2 {
+                        let x = [output[base + k][i][j]];
+                        let d = sigmoid(&x)[0] - target[base + k][i][j];
+                        total += self.coordinate * d * d;
+                        error[base + k][i][j] = gradient(&x, &[2.0 * self.coordinate * d])[0];
+                    }
+                    for k in 2..4 {
+                        let d = output[base + k][i][j] - target[base + k][i][j];
+                        total += self.coordinate * d * d;
+                        error[base + k][i][j] = 2.0 * self.coordinate * d;
+                    }
+
+                    let logits: Vec<f32> = (0..grid.classes)
+                        .map(|c| output[base + 5 + c][i][j])
+                        .collect();
+                    let classes: Vec<f32> = (0..grid.classes)
+                        .map(|c| target[base + 5 + c][i][j])
+                        .collect();
+                    let (loss, e) = cross_entropy(&softmax.forward(&logits), &classes);
+                    total += loss;
+                    let (e, _, _) = softmax.backward(&logits, &e);
+                    for (c, ec) in e.iter().enumerate() {
+                        error[base + 5 + c][i][j] = *ec;
+                    }
+                }
+            }
+        }
+        (total, error)
+    }
+}
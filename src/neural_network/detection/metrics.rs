@@ -0,0 +1,77 @@
+use super::{iou, Annotation, Detection};
+
+// the area under the precision recall curve of one class, with precision made
+// monotone from the right as in PASCAL VOC; detections are matched greedily in
+// score order to the best overlapping unmatched object of the same image, and
+// None when the class has no objects
+pub fn average_precision(
+    detections: &[Vec<Detection>],
+    objects: &[Vec<Annotation>],
+    class: usize,
+    threshold: f32,
+) -> Option<f32> {
+    let total = objects
+        .iter()
+        .flatten()
+        .filter(|object| object.class == class)
+        .count();
+    if total == 0 {
+        return None;
+    }
+    let mut ranked: Vec<(usize, &Detection)> = detections
+        .iter()
+        .enumerate()
+        .flat_map(|(image, found)| found.iter().map(move |d| (image, d)))
+        .filter(|(_, d)| d.class == class)
+        .collect();
+    ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+
+    let mut matched: Vec<Vec<bool>> = objects.iter().map(|o| vec![false; o.len()]).collect();
+    let mut true_positives = 0.0;
+    let mut curve = Vec::new();
+    for (rank, (image, detection)) in ranked.iter().enumerate() {
+        let best = objects[*image]
+            .iter()
+            .enumerate()
+            .filter(|(k, object)| object.class == class && !matched[*image][*k])
+            .map(|(k, object)| (k, iou(&object.bbox, &detection.bbox)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((k, overlap)) = best {
+            if overlap >= threshold {
+                matched[*image][k] = true;
+                true_positives += 1.0;
+            }
+        }
+        curve.push((
+            true_positives / total as f32,
+            true_positives / (rank + 1) as f32,
+        ));
+    }
+
+    let mut precision = 0.0f32;
+    let mut area = 0.0;
+    let mut recall = curve.last().map_or(0.0, |point| point.0);
+    for (r, p) in curve.iter().rev() {
+        area += (recall - r) * precision;
+        precision = precision.max(*p);
+        recall = *r;
+    }
+    Some(area + recall * precision)
+}
+
+// the mean average precision over the classes that have objects
+pub fn mean_average_precision(
+    detections: &[Vec<Detection>],
+    objects: &[Vec<Annotation>],
+    classes: usize,
+    threshold: f32,
+) -> f32 {
+    let precisions: Vec<f32> = (0..classes)
+        .filter_map(|class| average_precision(detections, objects, class, threshold))
+        .collect();
+    if precisions.is_empty() {
+        0.0
+    } else {
+        precisions.iter().sum::<f32>() / precisions.len() as f32
+    }
+}
@@ -0,0 +1,12 @@
+pub mod boxes;
+pub mod head;
+pub mod loss;
+pub mod metrics;
+pub mod shapes;
+mod test;
+
+pub use boxes::*;
+pub use head::*;
+pub use loss::*;
+pub use metrics::*;
+pub use shapes::*;
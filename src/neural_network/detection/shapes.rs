@@ -0,0 +1,78 @@
+use rand::{Rng, RngCore};
+
+use super::{Annotation, BoundingBox};
+
+pub const SHAPE_CLASSES: [&str; 3] = ["square", "circle", "triangle"];
+
+// an image and the objects in it
+pub type Sample = (Vec<Vec<Vec<f32>>>, Vec<Annotation>);
+
+// an image of the given side with up to max_objects non overlapping shapes in
+// random colours on a black background, and their boxes; sides run from a fifth
+// to half the image
+pub fn synthetic_shape(size: usize, max_objects: usize, rng: &mut dyn RngCore) -> Sample {
+    assert!(size >= 2, "the smallest shape is two pixels a side");
+    let mut image = vec![vec![vec![0.0; size]; size]; 3];
+    let mut objects: Vec<Annotation> = Vec::new();
+    let mut placed: Vec<(usize, usize, usize)> = Vec::new();
+    let count = rng.gen_range(1..=max_objects);
+    let (smallest, largest) = ((size / 5).max(2), (size / 2).max(2));
+    for _ in 0..count {
+        // a few tries at a spot clear of the shapes so far, otherwise leave it out
+        for _ in 0..20 {
+            let side = rng.gen_range(smallest..=largest);
+            let top = rng.gen_range(0..=(size - side));
+            let left = rng.gen_range(0..=(size - side));
+            let clear = placed.iter().all(|(t, l, s)| {
+                top >= t + s || t >= &(top + side) || left >= l + s || l >= &(left + side)
+            });
+            if !clear {
+                continue;
+            }
+            let class = rng.gen_range(0..SHAPE_CLASSES.len());
+            let colour: Vec<f32> = (0..3).map(|_| rng.gen_range(0.3..1.0)).collect();
+            for i in 0..side {
+                for j in 0..side {
+                    if covers(class, side, i, j) {
+                        for (channel, c) in image.iter_mut().zip(colour.iter()) {
+                            channel[top + i][left + j] = *c;
+                        }
+                    }
+                }
+            }
+            placed.push((top, left, side));
+            let bbox = BoundingBox::from_corners(
+                left as f32 / size as f32,
+                top as f32 / size as f32,
+                (left + side) as f32 / size as f32,
+                (top + side) as f32 / size as f32,
+            );
+            objects.push(Annotation::new(bbox, class));
+            break;
+        }
+    }
+    (image, objects)
+}
+
+// whether pixel (i, j) of a side x side box is inside the shape, the circle and
+// the triangle touch every edge of the box so it stays tight
+fn covers(class: usize, side: usize, i: usize, j: usize) -> bool {
+    let (y, x) = (i as f32 + 0.5, j as f32 + 0.5);
+    let half = side as f32 / 2.0;
+    match class {
+        0 => true,
+        1 => (y - half).powi(2) + (x - half).powi(2) <= half * half + 0.5,
+        _ => (x - half).abs() <= y / 2.0 + 0.5,
+    }
+}
+
+pub fn synthetic_shapes(
+    count: usize,
+    size: usize,
+    max_objects: usize,
+    rng: &mut dyn RngCore,
+) -> Vec<Sample> {
+    (0..count)
+        .map(|_| synthetic_shape(size, max_objects, rng))
+        .collect()
+}
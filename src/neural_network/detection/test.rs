@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod test_detection {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::neural_network::convolutional::{Conv2D, MaxPool2D};
+    use crate::neural_network::core::{Layer2D, ReLU2D};
+    use crate::neural_network::detection::{
+        average_precision, giou, iou, mean_average_precision, non_max_suppression,
+        synthetic_shapes, Annotation, BoundingBox, Detection, YoloGrid, YoloHead, YoloLoss,
+    };
+    use crate::neural_network::sequential::{Node, Sequential, Tensor};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn test_iou() {
+        let a = BoundingBox::from_corners(0.0, 0.0, 0.4, 0.4);
+        let b = BoundingBox::from_corners(0.2, 0.2, 0.6, 0.6);
+        assert_eq!(a.corners(), (0.0, 0.0, 0.4, 0.4));
+        assert!(close(iou(&a, &a), 1.0));
+        assert!(close(iou(&a, &b), 0.04 / 0.28));
+        // the enclosing box of 0.36 leaves 0.08 uncovered
+        assert!(close(giou(&a, &b), 0.04 / 0.28 - 0.08 / 0.36));
+
+        let far = BoundingBox::from_corners(0.6, 0.0, 1.0, 0.4);
+        assert_eq!(iou(&a, &far), 0.0);
+        assert!(close(giou(&a, &far), -0.08 / 0.4));
+        assert!(giou(&a, &far) < giou(&a, &BoundingBox::from_corners(0.5, 0.0, 0.9, 0.4)));
+    }
+
+    #[test]
+    fn test_non_max_suppression() {
+        let bbox = BoundingBox::new(0.5, 0.5, 0.2, 0.2);
+        let shifted = BoundingBox::new(0.52, 0.5, 0.2, 0.2);
+        let elsewhere = BoundingBox::new(0.1, 0.1, 0.1, 0.1);
+        let kept = non_max_suppression(
+            &[
+                Detection::new(shifted, 0, 0.6),
+                Detection::new(bbox, 0, 0.9),
+                Detection::new(shifted, 1, 0.5),
+                Detection::new(elsewhere, 0, 0.3),
+            ],
+            0.5,
+        );
+        assert_eq!(
+            kept,
+            [
+                Detection::new(bbox, 0, 0.9),
+                Detection::new(shifted, 1, 0.5),
+                Detection::new(elsewhere, 0, 0.3),
+            ]
+        );
+    }
+
+    fn grid() -> YoloGrid {
+        YoloGrid::new((4, 4), vec![(0.2, 0.2), (0.5, 0.3)], 3)
+    }
+
+    fn objects() -> Vec<Annotation> {
+        vec![
+            Annotation::new(BoundingBox::new(0.3, 0.6, 0.18, 0.22), 2),
+            Annotation::new(BoundingBox::new(0.8, 0.1, 0.45, 0.25), 0),
+        ]
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let grid = grid();
+        assert_eq!(grid.best_anchor(&objects()[0].bbox), 0);
+        assert_eq!(grid.best_anchor(&objects()[1].bbox), 1);
+        let target = grid.encode(&objects());
+        assert_eq!(target.len(), grid.channels());
+        // the first object is in row 2 and column 1 of the grid
+        assert!(close(target[0][2][1], 0.2) && close(target[1][2][1], 0.4));
+        assert!(close(target[2][2][1], 0.9f32.ln()));
+        assert_eq!((target[4][2][1], target[7][2][1]), (1.0, 1.0));
+        assert_eq!(target.iter().map(|c| c[2][1]).sum::<f32>() as usize, 2);
+
+        // raw outputs that invert the encoding decode to the objects
+        let logit = |p: f32| (p / (1.0 - p)).ln();
+        let output: Vec<Vec<Vec<f32>>> = target
+            .iter()
+            .enumerate()
+            .map(|(k, channel)| {
+                channel
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|x| match k % 8 {
+                                0 | 1 => logit(*x),
+                                2 | 3 => *x,
+                                _ => 20.0 * x - 10.0,
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut detections = grid.decode(&output, 0.5);
+        detections.sort_by(|a, b| a.bbox.x.total_cmp(&b.bbox.x));
+        assert_eq!(detections.len(), 2);
+        for (detection, object) in detections.iter().zip(objects().iter()) {
+            assert_eq!(detection.class, object.class);
+            assert!(iou(&detection.bbox, &object.bbox) > 0.999);
+            assert!(detection.score > 0.99);
+        }
+    }
+
+    #[test]
+    fn test_yolo_loss_gradients() {
+        let grid = grid();
+        let target = grid.encode(&objects());
+        let output: Vec<Vec<Vec<f32>>> = (0..grid.channels())
+            .map(|c| {
+                (0..4)
+                    .map(|i| {
+                        (0..4)
+                            .map(|j| ((c * 16 + i * 4 + j) as f32 * 0.77).sin())
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let loss = YoloLoss::new(5.0, 0.5);
+        let (_, error) = loss.loss(&grid, &output, &target);
+        let h = 1e-2;
+        for c in 0..grid.channels() {
+            for (i, j) in [(2, 1), (0, 3), (3, 3)] {
+                let mut plus = output.clone();
+                let mut minus = output.clone();
+                plus[c][i][j] += h;
+                minus[c][i][j] -= h;
+                let numeric = (loss.loss(&grid, &plus, &target).0
+                    - loss.loss(&grid, &minus, &target).0)
+                    / (2.0 * h);
+                assert!((numeric - error[c][i][j]).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_average_precision() {
+        let bbox = |x: f32| BoundingBox::new(x, 0.5, 0.2, 0.2);
+        let objects = vec![
+            vec![Annotation::new(bbox(0.2), 0), Annotation::new(bbox(0.7), 0)],
+            vec![Annotation::new(bbox(0.5), 1)],
+        ];
+        let perfect = vec![
+            vec![
+                Detection::new(bbox(0.2), 0, 0.9),
+                Detection::new(bbox(0.7), 0, 0.8),
+            ],
+            vec![Detection::new(bbox(0.5), 1, 0.7)],
+        ];
+        assert!(close(
+            mean_average_precision(&perfect, &objects, 3, 0.5),
+            1.0
+        ));
+
+        // a false positive ranked between the hits: precision 1 up to recall
+        // 0.5 then 2 / 3 up to recall 1, and a duplicate only counts once
+        let mixed = vec![
+            vec![
+                Detection::new(bbox(0.2), 0, 0.9),
+                Detection::new(bbox(0.21), 0, 0.85),
+                Detection::new(bbox(0.7), 0, 0.6),
+            ],
+            vec![Detection::new(bbox(0.5), 0, 0.3)],
+        ];
+        let ap = average_precision(&mixed, &objects, 0, 0.5).unwrap();
+        assert!(close(ap, 0.5 + 0.5 * 2.0 / 3.0));
+        assert_eq!(average_precision(&mixed, &objects, 2, 0.5), None);
+        assert_eq!(average_precision(&mixed, &objects, 1, 0.5), Some(0.0));
+    }
+
+    #[test]
+    fn test_synthetic_shapes() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        for (image, objects) in synthetic_shapes(20, 24, 3, &mut rng) {
+            assert!((1..=3).contains(&objects.len()));
+            for object in &objects {
+                let (l, t, r, b) = object.bbox.corners();
+                let (l, t, r, b) = [l, t, r, b].map(|x| (x * 24.0).round() as usize).into();
+                // the shape reaches every edge of its box
+                let lit = |i: usize, j: usize| image.iter().any(|c| c[i][j] > 0.0);
+                assert!((l..r).any(|j| lit(t, j)) && (l..r).any(|j| lit(b - 1, j)));
+                assert!((t..b).any(|i| lit(i, l)) && (t..b).any(|i| lit(i, r - 1)));
+            }
+            for (k, a) in objects.iter().enumerate() {
+                assert!(objects[..k].iter().all(|b| iou(&a.bbox, &b.bbox) == 0.0));
+            }
+        }
+        // the smallest images still fit a shape
+        for (image, objects) in synthetic_shapes(5, 2, 1, &mut rng) {
+            assert_eq!(image[0].len(), 2);
+            assert_eq!(objects.len(), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_synthetic_shape_too_small() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        synthetic_shapes(1, 1, 1, &mut rng);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_grid_without_classes() {
+        YoloGrid::new((2, 2), vec![(0.5, 0.5)], 0);
+    }
+
+    #[test]
+    fn test_detector_trains() {
+        let conv = Conv2D::new((3, 16, 16), 6, (3, 3), (1, 1), (1, 1));
+        let relu = ReLU2D::new(conv.dim_out());
+        let pool = MaxPool2D::new(relu.dim_out(), (4, 4), (0, 0), (4, 4));
+        let head = YoloHead::new(pool.dim_out(), vec![(0.3, 0.3)], 3);
+        let grid = head.grid.clone();
+        let mut model = Sequential::new(vec![
+            Node::Spatial(Box::new(conv)),
+            Node::Spatial(Box::new(relu)),
+            Node::Spatial(Box::new(pool)),
+            Node::Spatial(Box::new(head)),
+        ]);
+        let data = synthetic_shapes(4, 16, 2, &mut ChaCha8Rng::seed_from_u64(1));
+        let loss = YoloLoss::new(5.0, 0.5);
+        let epoch = |model: &mut Sequential, learning_rate: f32| {
+            let mut total = 0.0;
+            for (image, objects) in &data {
+                let input = Tensor::Spatial(image.clone());
+                let output = model.forward(&input);
+                let (value, error) = loss.loss(&grid, output.spatial(), &grid.encode(objects));
+                let (_, gradients) = model.backward(&input, &Tensor::Spatial(error));
+                model.update(&gradients, learning_rate);
+                total += value;
+            }
+            total
+        };
+        let first = epoch(&mut model, 0.01);
+        let mut last = first;
+        for _ in 0..30 {
+            last = epoch(&mut model, 0.01);
+        }
+        assert!(last < 0.5 * first);
+    }
+}
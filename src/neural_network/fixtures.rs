@@ -0,0 +1,83 @@
+use crate::neural_network::{
+    convolutional::{Conv2D, MaxPool2D},
+    core::{Flatten, Layer, Layer2D, ReLU, ReLU2D, Softmax},
+    linear::linear::Linear,
+    sequential::{Node, Sequential, Tensor},
+};
+
+// deterministic values in [-1, 1] for filling inputs and weights in tests
+pub fn wave(k: usize) -> f32 {
+    (k as f32 * 0.73).sin()
+}
+
+pub fn volume(dim: (usize, usize, usize), offset: usize) -> Vec<Vec<Vec<f32>>> {
+    (0..dim.0)
+        .map(|c| {
+            (0..dim.1)
+                .map(|i| {
+                    (0..dim.2)
+                        .map(|j| wave(offset + (c * dim.1 + i) * dim.2 + j))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn largest_difference(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .fold(0.0, |acc, (x, y)| acc.max((x - y).abs()))
+}
+
+// conv, pool and two linear layers over a 6x6 image into two classes, with
+// fixed weights in place of the random initialisation
+pub fn classifier(hidden: usize) -> Sequential {
+    let conv = Conv2D::new((1, 6, 6), 4, (3, 3), (1, 1), (1, 1));
+    let relu = ReLU2D::new(conv.dim_out());
+    let pool = MaxPool2D::new(relu.dim_out(), (2, 2), (0, 0), (2, 2));
+    let flatten = Flatten::new(pool.dim_out());
+    let hidden = Linear::new(flatten.dim_out(), hidden);
+    let relu_hidden = ReLU::new(hidden.dim_out());
+    let output = Linear::new(relu_hidden.dim_out(), 2);
+    let softmax = Softmax::new(output.dim_out());
+    let mut model = Sequential::new(vec![
+        Node::Spatial(Box::new(conv)),
+        Node::Spatial(Box::new(relu)),
+        Node::Spatial(Box::new(pool)),
+        Node::Flatten(flatten),
+        Node::Dense(Box::new(hidden)),
+        Node::Dense(Box::new(relu_hidden)),
+        Node::Dense(Box::new(output)),
+        Node::Dense(Box::new(softmax)),
+    ]);
+    for (k, p) in model.parameters().into_iter().flatten().enumerate() {
+        *p = 0.3 * wave(7 * k + 1);
+    }
+    model
+}
+
+// noisy vertical bars are class 0 and horizontal bars class 1
+pub fn bars(count: usize, offset: usize) -> (Vec<Tensor>, Vec<Vec<f32>>) {
+    (0..count)
+        .map(|k| {
+            let bar = k % 6;
+            let image: Vec<Vec<f32>> = (0..6)
+                .map(|i| {
+                    (0..6)
+                        .map(|j| {
+                            let on = if k % 2 == 0 { j == bar } else { i == bar };
+                            0.2 * wave(offset + k * 36 + i * 6 + j) + on as u8 as f32
+                        })
+                        .collect()
+                })
+                .collect();
+            let target = if k % 2 == 0 {
+                vec![1.0, 0.0]
+            } else {
+                vec![0.0, 1.0]
+            };
+            (Tensor::Spatial(vec![image]), target)
+        })
+        .unzip()
+}
@@ -1,9 +1,25 @@
-use crate::neural_network::core::{linear_transform, outer, Layer};
+use crate::neural_network::core::{
+    he_initialise, linear_transform, outer, transpose_transform, Layer,
+};
 
 pub struct Linear {
-    dim_in: usize,
-    dim_out: usize,
-    weights: Vec<Vec<f32>>,
+    pub dim_in: usize,
+    pub dim_out: usize,
+    pub weights: Vec<Vec<f32>>,
+    pub bias: Vec<f32>,
+}
+
+impl Linear {
+    pub fn new(dim_in: usize, dim_out: usize) -> Self {
+        Linear {
+            dim_in,
+            dim_out,
+            weights: (0..dim_out)
+                .map(|_| he_initialise(dim_in, dim_in))
+                .collect(),
+            bias: vec![0.0; dim_out],
+        }
+    }
 }
 
 impl Layer for Linear {
@@ -15,7 +31,7 @@ impl Layer for Linear {
     }
 
     fn forward(&self, input: &[f32]) -> Vec<f32> {
-        linear_transform(&self.weights, input, None)
+        linear_transform(&self.weights, input, Some(&self.bias))
     }
 
     fn backward(
@@ -23,15 +39,20 @@ impl Layer for Linear {
         input: &[f32],
         error: &[f32],
     ) -> (Vec<f32>, Option<Vec<Vec<f32>>>, Option<Vec<f32>>) {
-        let weight_error = outer(error, input);
         (
-            weight_error
-                .iter()
-                .map(|x| x.clone())
-                .reduce(|acc, x| acc.iter().zip(x.iter()).map(|(i, j)| i + j).collect())
-                .unwrap(),
-            Some(weight_error),
-            None,
+            transpose_transform(&self.weights, error),
+            Some(outer(error, input)),
+            Some(error.to_vec()),
         )
     }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters: Vec<&mut [f32]> = self
+            .weights
+            .iter_mut()
+            .map(|row| row.as_mut_slice())
+            .collect();
+        parameters.push(&mut self.bias);
+        parameters
+    }
 }
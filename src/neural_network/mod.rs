@@ -1,5 +1,15 @@
 pub mod convolutional;
 pub mod core;
+pub mod data;
+pub mod detection;
+#[cfg(test)]
+mod fixtures;
 pub mod linear;
 pub mod optimiser;
+pub mod pruning;
+pub mod quantisation;
+pub mod recurrent;
+pub mod sequential;
+pub mod training;
 pub mod transformer;
+pub mod tuning;
@@ -0,0 +1,140 @@
+use std::io;
+
+use crate::neural_network::core::Record;
+
+use super::{initialise, Optimizer};
+
+// divides the step by the root of the sum of every squared gradient so far
+pub struct Adagrad {
+    pub learning_rate: f32,
+    pub epsilon: f32,
+    pub square_sum: Vec<Vec<f32>>,
+}
+
+impl Adagrad {
+    pub fn new(learning_rate: f32, epsilon: f32) -> Self {
+        Adagrad {
+            learning_rate,
+            epsilon,
+            square_sum: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Adagrad {
+    fn step(&mut self, parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        initialise(&mut self.square_sum, gradients);
+        for ((parameter, gradient), sum) in parameters
+            .into_iter()
+            .zip(gradients.iter())
+            .zip(self.square_sum.iter_mut())
+        {
+            for ((p, g), s) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(sum.iter_mut())
+            {
+                *s += g * g;
+                *p -= self.learning_rate * g / (s.sqrt() + self.epsilon);
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("learning_rate", &[self.learning_rate]);
+        record.push("epsilon", &[self.epsilon]);
+        record.push_rows("square_sum", &self.square_sum);
+        record
+    }
+
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        self.learning_rate = record.scalar("learning_rate")?;
+        self.epsilon = record.scalar("epsilon")?;
+        self.square_sum = record.rows("square_sum")?;
+        Ok(())
+    }
+}
+
+// scales the step by the ratio of running root mean squares of past steps and
+// gradients so it has the units of the parameters; the learning rate is a plain
+// multiplier on that step, 1 in the original
+pub struct Adadelta {
+    pub learning_rate: f32,
+    pub rho: f32,
+    pub epsilon: f32,
+    pub square_average: Vec<Vec<f32>>,
+    pub delta_average: Vec<Vec<f32>>,
+}
+
+impl Adadelta {
+    pub fn new(learning_rate: f32, rho: f32, epsilon: f32) -> Self {
+        Adadelta {
+            learning_rate,
+            rho,
+            epsilon,
+            square_average: Vec::new(),
+            delta_average: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Adadelta {
+    fn step(&mut self, parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        initialise(&mut self.square_average, gradients);
+        initialise(&mut self.delta_average, gradients);
+        for (((parameter, gradient), squares), deltas) in parameters
+            .into_iter()
+            .zip(gradients.iter())
+            .zip(self.square_average.iter_mut())
+            .zip(self.delta_average.iter_mut())
+        {
+            for (((p, g), s), d) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(squares.iter_mut())
+                .zip(deltas.iter_mut())
+            {
+                *s = self.rho * *s + (1.0 - self.rho) * g * g;
+                let delta = (*d + self.epsilon).sqrt() / (*s + self.epsilon).sqrt() * g;
+                *d = self.rho * *d + (1.0 - self.rho) * delta * delta;
+                *p -= self.learning_rate * delta;
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("learning_rate", &[self.learning_rate]);
+        record.push("rho", &[self.rho]);
+        record.push("epsilon", &[self.epsilon]);
+        record.push_rows("square_average", &self.square_average);
+        record.push_rows("delta_average", &self.delta_average);
+        record
+    }
+
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        self.learning_rate = record.scalar("learning_rate")?;
+        self.rho = record.scalar("rho")?;
+        self.epsilon = record.scalar("epsilon")?;
+        self.square_average = record.rows("square_average")?;
+        self.delta_average = record.rows("delta_average")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use std::io;
+
+use crate::neural_network::core::Record;
+
+use super::{initialise, Optimizer};
+
+// running averages of the gradient and its square, corrected for starting at zero
+pub struct Adam {
+    pub learning_rate: f32,
+    pub betas: (f32, f32),
+    pub epsilon: f32,
+    pub steps: i32,
+    pub first_moment: Vec<Vec<f32>>,
+    pub second_moment: Vec<Vec<f32>>,
+}
+
+impl Adam {
+    pub fn new(learning_rate: f32, betas: (f32, f32), epsilon: f32) -> Self {
+        Adam {
+            learning_rate,
+            betas,
+            epsilon,
+            steps: 0,
+            first_moment: Vec::new(),
+            second_moment: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        initialise(&mut self.first_moment, gradients);
+        initialise(&mut self.second_moment, gradients);
+        self.steps += 1;
+        let (beta1, beta2) = self.betas;
+        let correction = (1.0 - beta1.powi(self.steps), 1.0 - beta2.powi(self.steps));
+        for (((parameter, gradient), first), second) in parameters
+            .into_iter()
+            .zip(gradients.iter())
+            .zip(self.first_moment.iter_mut())
+            .zip(self.second_moment.iter_mut())
+        {
+            for (((p, g), m), v) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(first.iter_mut())
+                .zip(second.iter_mut())
+            {
+                *m = beta1 * *m + (1.0 - beta1) * g;
+                *v = beta2 * *v + (1.0 - beta2) * g * g;
+                let m_hat = *m / correction.0;
+                let v_hat = *v / correction.1;
+                *p -= self.learning_rate * m_hat / (v_hat.sqrt() + self.epsilon);
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("learning_rate", &[self.learning_rate]);
+        record.push("betas", &[self.betas.0, self.betas.1]);
+        record.push("epsilon", &[self.epsilon]);
+        record.push_count("steps", self.steps as u64);
+        record.push_rows("first_moment", &self.first_moment);
+        record.push_rows("second_moment", &self.second_moment);
+        record
+    }
+
+    // the moments must have matching rows, one pair per parameter row
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let betas = match record.get("betas")? {
+            [beta1, beta2] => (*beta1, *beta2),
+            _ => return Err(invalid("adam needs two betas")),
+        };
+        let first_moment = record.rows("first_moment")?;
+        let second_moment = record.rows("second_moment")?;
+        let matching = first_moment.len() == second_moment.len()
+            && first_moment
+                .iter()
+                .zip(second_moment.iter())
+                .all(|(m, v)| m.len() == v.len());
+        if !matching {
+            return Err(invalid("adam moments of different shapes"));
+        }
+        self.learning_rate = record.scalar("learning_rate")?;
+        self.betas = betas;
+        self.epsilon = record.scalar("epsilon")?;
+        self.steps = i32::try_from(record.count("steps")?)
+            .map_err(|_| invalid("adam steps out of range"))?;
+        self.first_moment = first_moment;
+        self.second_moment = second_moment;
+        Ok(())
+    }
+}
+
+// Adam with weight decay applied to the parameters directly rather than added to
+// the gradient, so it isn't scaled down by the second moment
+pub struct AdamW {
+    pub adam: Adam,
+    pub weight_decay: f32,
+}
+
+impl AdamW {
+    pub fn new(learning_rate: f32, betas: (f32, f32), epsilon: f32, weight_decay: f32) -> Self {
+        AdamW {
+            adam: Adam::new(learning_rate, betas, epsilon),
+            weight_decay,
+        }
+    }
+}
+
+impl Optimizer for AdamW {
+    fn step(&mut self, mut parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        let shrink = 1.0 - self.adam.learning_rate * self.weight_decay;
+        for parameter in parameters.iter_mut() {
+            parameter.iter_mut().for_each(|p| *p *= shrink);
+        }
+        self.adam.step(parameters, gradients);
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.adam.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.adam.learning_rate = learning_rate;
+    }
+
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.extend("adam", self.adam.save());
+        record.push("weight_decay", &[self.weight_decay]);
+        record
+    }
+
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        self.adam.load(&record.section("adam"))?;
+        self.weight_decay = record.scalar("weight_decay")?;
+        Ok(())
+    }
+}
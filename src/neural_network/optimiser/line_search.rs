@@ -0,0 +1,145 @@
+// the objective along a search direction, phi(a) = f(x + a d) and its slope
+// phi'(a) = g(x + a d) . d, with the point and gradient kept for the caller
+#[derive(Clone, Debug)]
+pub struct Trial {
+    pub step: f64,
+    pub value: f64,
+    pub slope: f64,
+    pub x: Vec<f64>,
+    pub gradient: Vec<f64>,
+}
+
+// the value and gradient at a point
+pub type Objective<'a> = dyn FnMut(&[f64]) -> (f64, Vec<f64>) + 'a;
+
+pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn trial(
+    objective: &mut Objective,
+    x: &[f64],
+    direction: &[f64],
+    step: f64,
+) -> Trial {
+    let x: Vec<f64> = x
+        .iter()
+        .zip(direction.iter())
+        .map(|(x, d)| x + step * d)
+        .collect();
+    let (value, gradient) = objective(&x);
+    Trial {
+        step,
+        value,
+        slope: dot(&gradient, direction),
+        x,
+        gradient,
+    }
+}
+
+// the minimiser of the cubic through both ends' values and slopes, falling back
+// to bisection when it is undefined or too close to either end
+fn interpolate(lo: &Trial, hi: &Trial) -> f64 {
+    let d1 = lo.slope + hi.slope - 3.0 * (lo.value - hi.value) / (lo.step - hi.step);
+    let d2 = (hi.step - lo.step).signum() * (d1 * d1 - lo.slope * hi.slope).sqrt();
+    let step =
+        hi.step - (hi.step - lo.step) * (hi.slope + d2 - d1) / (hi.slope - lo.slope + 2.0 * d2);
+    let (low, high) = (lo.step.min(hi.step), lo.step.max(hi.step));
+    let margin = 0.1 * (high - low);
+    if step.is_finite() && step > low + margin && step < high - margin {
+        step
+    } else {
+        0.5 * (lo.step + hi.step)
+    }
+}
+
+// a step along a descent direction meeting the strong Wolfe conditions,
+// sufficient decrease phi(a) <= phi(0) + c1 a phi'(0) and curvature
+// |phi'(a)| <= c2 |phi'(0)|; bracketing then zooming as in Nocedal and Wright,
+// None if no such step turns up within the evaluation budget
+pub fn strong_wolfe(
+    objective: &mut Objective,
+    start: &Trial,
+    direction: &[f64],
+    initial: f64,
+    c1: f64,
+    c2: f64,
+) -> Option<Trial> {
+    assert!(start.slope < 0.0 && 0.0 < c1 && c1 < c2 && c2 < 1.0);
+    let sufficient = |t: &Trial| t.value <= start.value + c1 * t.step * start.slope;
+    let curved = |t: &Trial| t.slope.abs() <= -c2 * start.slope;
+
+    // start.step is whatever step reached it, here it is the origin
+    let mut previous = Trial {
+        step: 0.0,
+        ..start.clone()
+    };
+    let mut step = initial;
+    for i in 0..20 {
+        let current = trial(objective, &start.x, direction, step);
+        if !current.value.is_finite() {
+            step = 0.5 * (previous.step + step);
+            continue;
+        }
+        if !sufficient(&current) || (i > 0 && current.value >= previous.value) {
+            return zoom(
+                objective,
+                start,
+                direction,
+                previous,
+                current,
+                &sufficient,
+                &curved,
+            );
+        }
+        if curved(&current) {
+            return Some(current);
+        }
+        if current.slope >= 0.0 {
+            return zoom(
+                objective,
+                start,
+                direction,
+                current,
+                previous,
+                &sufficient,
+                &curved,
+            );
+        }
+        step *= 2.0;
+        previous = current;
+    }
+    None
+}
+
+// lo always satisfies sufficient decrease with the lowest value so far, and
+// the minimiser lies between lo and hi
+fn zoom(
+    objective: &mut Objective,
+    start: &Trial,
+    direction: &[f64],
+    mut lo: Trial,
+    mut hi: Trial,
+    sufficient: &dyn Fn(&Trial) -> bool,
+    curved: &dyn Fn(&Trial) -> bool,
+) -> Option<Trial> {
+    for _ in 0..30 {
+        let current = trial(objective, &start.x, direction, interpolate(&lo, &hi));
+        if !sufficient(&current) || current.value >= lo.value {
+            hi = current;
+        } else {
+            if curved(&current) {
+                return Some(current);
+            }
+            if current.slope * (hi.step - lo.step) >= 0.0 {
+                hi = lo;
+            }
+            lo = current;
+        }
+        if (hi.step - lo.step).abs() < 1e-16 * lo.step.abs().max(1.0) {
+            break;
+        }
+    }
+    // settle for the decrease found so far rather than nothing
+    (lo.step > 0.0).then_some(lo)
+}
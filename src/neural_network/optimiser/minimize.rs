@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+
+use super::line_search::{dot, strong_wolfe, Trial};
+
+// how conjugate gradient weighs the previous direction against the new
+// gradient; Polak-Ribiere and Hestenes-Stiefel are clipped at zero, which
+// restarts along the gradient when they go negative
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Beta {
+    FletcherReeves,
+    PolakRibiere,
+    HestenesStiefel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    // quasi-Newton from the last memory steps and changes in the gradient
+    Lbfgs { memory: usize },
+    // nonlinear conjugate gradient, restarted every n iterations in n dimensions
+    ConjugateGradient { beta: Beta },
+}
+
+// stops once the largest gradient entry is within gradient_tolerance, or an
+// iteration improves the value by no more than value_tolerance relative to
+// its size; c1 and c2 are the strong Wolfe constants of the line search
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    pub method: Method,
+    pub max_iterations: usize,
+    pub gradient_tolerance: f64,
+    pub value_tolerance: f64,
+    pub c1: f64,
+    pub c2: f64,
+}
+
+impl Options {
+    // conjugate gradient needs a more exact line search than L-BFGS to keep
+    // its directions conjugate
+    pub fn new(method: Method) -> Self {
+        let c2 = match method {
+            Method::Lbfgs { .. } => 0.9,
+            Method::ConjugateGradient { .. } => 0.1,
+        };
+        Options {
+            method,
+            max_iterations: 1000,
+            gradient_tolerance: 1e-6,
+            value_tolerance: 1e-12,
+            c1: 1e-4,
+            c2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Minimum {
+    pub x: Vec<f64>,
+    pub value: f64,
+    pub gradient: Vec<f64>,
+    pub iterations: usize,
+    pub evaluations: usize,
+    pub converged: bool,
+}
+
+// L-BFGS with ten pairs and the default tolerances
+pub fn minimize(
+    f: impl Fn(&[f64]) -> f64,
+    grad: impl Fn(&[f64]) -> Vec<f64>,
+    x0: &[f64],
+) -> Minimum {
+    minimize_with(
+        |x| (f(x), grad(x)),
+        x0,
+        &Options::new(Method::Lbfgs { memory: 10 }),
+    )
+}
+
+fn max_norm(x: &[f64]) -> f64 {
+    x.iter().fold(0.0, |acc, y| acc.max(y.abs()))
+}
+
+fn negative(x: &[f64]) -> Vec<f64> {
+    x.iter().map(|y| -y).collect()
+}
+
+fn difference(a: &[f64], b: &[f64]) -> Vec<f64> {
+    a.iter().zip(b.iter()).map(|(x, y)| x - y).collect()
+}
+
+// the two loop recursion, -H g for the inverse Hessian estimate H built from
+// the (step, gradient change) pairs, oldest first, on top of a scaled identity
+fn two_loop(gradient: &[f64], pairs: &VecDeque<(Vec<f64>, Vec<f64>)>) -> Vec<f64> {
+    let mut q = gradient.to_vec();
+    let mut alphas = Vec::with_capacity(pairs.len());
+    for (s, y) in pairs.iter().rev() {
+        let alpha = dot(s, &q) / dot(y, s);
+        q.iter_mut()
+            .zip(y.iter())
+            .for_each(|(q, y)| *q -= alpha * y);
+        alphas.push(alpha);
+    }
+    if let Some((s, y)) = pairs.back() {
+        let gamma = dot(s, y) / dot(y, y);
+        q.iter_mut().for_each(|q| *q *= gamma);
+    }
+    for ((s, y), alpha) in pairs.iter().zip(alphas.iter().rev()) {
+        let beta = dot(y, &q) / dot(y, s);
+        q.iter_mut()
+            .zip(s.iter())
+            .for_each(|(q, s)| *q += (alpha - beta) * s);
+    }
+    negative(&q)
+}
+
+fn beta(kind: Beta, gradient: &[f64], old_gradient: &[f64], old_direction: &[f64]) -> f64 {
+    let change = difference(gradient, old_gradient);
+    match kind {
+        Beta::FletcherReeves => dot(gradient, gradient) / dot(old_gradient, old_gradient),
+        Beta::PolakRibiere => (dot(gradient, &change) / dot(old_gradient, old_gradient)).max(0.0),
+        Beta::HestenesStiefel => (dot(gradient, &change) / dot(old_direction, &change)).max(0.0),
+    }
+}
+
+// objective gives the value and gradient at a point; the search stops early,
+// unconverged, if the line search can't find a step
+pub fn minimize_with(
+    mut objective: impl FnMut(&[f64]) -> (f64, Vec<f64>),
+    x0: &[f64],
+    options: &Options,
+) -> Minimum {
+    let mut evaluations = 0;
+    let mut counted = |x: &[f64]| {
+        evaluations += 1;
+        objective(x)
+    };
+    let (value, gradient) = counted(x0);
+    let mut current = Trial {
+        step: 0.0,
+        value,
+        slope: 0.0,
+        x: x0.to_vec(),
+        gradient,
+    };
+    let mut pairs: VecDeque<(Vec<f64>, Vec<f64>)> = VecDeque::new();
+    // the gradient, direction and slope of the last iteration, for conjugate gradient
+    let mut previous: Option<(Vec<f64>, Vec<f64>, f64)> = None;
+    let mut converged = max_norm(&current.gradient) <= options.gradient_tolerance;
+    let mut iterations = 0;
+
+    while !converged && iterations < options.max_iterations {
+        // steps along the bare gradient have no scale to go on, so start at one
+        // over its length
+        let mut initial = None;
+        let mut direction = match options.method {
+            Method::Lbfgs { .. } if !pairs.is_empty() => {
+                initial = Some(1.0);
+                two_loop(&current.gradient, &pairs)
+            }
+            Method::ConjugateGradient { beta: kind } => match &previous {
+                Some((old_gradient, old_direction, old_slope)) if iterations % x0.len() != 0 => {
+                    let b = beta(kind, &current.gradient, old_gradient, old_direction);
+                    let direction: Vec<f64> = current
+                        .gradient
+                        .iter()
+                        .zip(old_direction.iter())
+                        .map(|(g, d)| -g + b * d)
+                        .collect();
+                    // the last step scaled to give the same first order change
+                    initial = Some(current.step * old_slope / dot(&current.gradient, &direction));
+                    direction
+                }
+                _ => negative(&current.gradient),
+            },
+            Method::Lbfgs { .. } => negative(&current.gradient),
+        };
+        current.slope = dot(&current.gradient, &direction);
+        // lost descent, so start again along the gradient
+        if current.slope >= 0.0 || !current.slope.is_finite() {
+            direction = negative(&current.gradient);
+            current.slope = -dot(&current.gradient, &current.gradient);
+            pairs.clear();
+            initial = None;
+        }
+        let initial = match initial {
+            Some(step) if step > 0.0 && step.is_finite() => step,
+            _ => (1.0 / current.slope.abs().sqrt()).min(1.0),
+        };
+
+        let Some(next) = strong_wolfe(
+            &mut counted,
+            &current,
+            &direction,
+            initial,
+            options.c1,
+            options.c2,
+        ) else {
+            break;
+        };
+        iterations += 1;
+        if let Method::Lbfgs { memory } = options.method {
+            let s = difference(&next.x, &current.x);
+            let y = difference(&next.gradient, &current.gradient);
+            // keeps the estimate positive definite
+            if dot(&s, &y) > 1e-10 * dot(&y, &y) {
+                pairs.push_back((s, y));
+                if pairs.len() > memory {
+                    pairs.pop_front();
+                }
+            }
+        }
+        let scale = current.value.abs().max(next.value.abs()).max(1.0);
+        let stalled = current.value - next.value <= options.value_tolerance * scale;
+        previous = Some((current.gradient, direction, current.slope));
+        current = next;
+        converged = max_norm(&current.gradient) <= options.gradient_tolerance || stalled;
+    }
+
+    Minimum {
+        x: current.x,
+        value: current.value,
+        gradient: current.gradient,
+        iterations,
+        evaluations,
+        converged,
+    }
+}
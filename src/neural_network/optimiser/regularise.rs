@@ -0,0 +1,206 @@
+use std::io;
+
+use crate::neural_network::core::{ParameterKind, Record};
+
+use super::Optimizer;
+
+// rescale every gradient when their combined norm is above the limit, or clamp
+// each entry to within the limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clip {
+    Norm(f32),
+    Value(f32),
+}
+
+// the penalty on one parameter row, l1 |p| + l2 p^2 / 2 added to the loss
+// through its gradient, and weight decay shrinking the parameter by
+// rate * decay * p outside the optimiser's own update
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Penalty {
+    pub l1: f32,
+    pub l2: f32,
+    pub weight_decay: f32,
+}
+
+impl Penalty {
+    pub fn new(l1: f32, l2: f32, weight_decay: f32) -> Self {
+        Penalty {
+            l1,
+            l2,
+            weight_decay,
+        }
+    }
+}
+
+// a penalty per row from the kind of each row, for example none on biases and
+// norm parameters
+pub fn penalties_by_kind(
+    kinds: &[ParameterKind],
+    penalty: impl Fn(ParameterKind) -> Penalty,
+) -> Vec<Penalty> {
+    kinds.iter().map(|kind| penalty(*kind)).collect()
+}
+
+pub fn global_norm(gradients: &[Vec<f32>]) -> f32 {
+    gradients
+        .iter()
+        .flatten()
+        .map(|g| g * g)
+        .sum::<f32>()
+        .sqrt()
+}
+
+// clips in place and returns the global norm from before clipping
+pub fn clip_gradients(gradients: &mut [Vec<f32>], clip: Clip) -> f32 {
+    let norm = global_norm(gradients);
+    match clip {
+        Clip::Norm(limit) => {
+            if norm > limit {
+                let scale = limit / norm;
+                gradients.iter_mut().flatten().for_each(|g| *g *= scale);
+            }
+        }
+        Clip::Value(limit) => {
+            gradients
+                .iter_mut()
+                .flatten()
+                .for_each(|g| *g = g.clamp(-limit, limit));
+        }
+    }
+    norm
+}
+
+// wraps an optimiser with the things done to gradients before it sees them;
+// the gradients of accumulation calls are averaged and only every last one
+// updates, then the penalties are added, the result is clipped and weight decay
+// applied; penalties holds one per parameter row or is empty for none
+pub struct Regularised<O: Optimizer> {
+    pub optimizer: O,
+    pub penalties: Vec<Penalty>,
+    pub clip: Option<Clip>,
+    pub accumulation: usize,
+    pub accumulated: Vec<Vec<f32>>,
+    pub pending: usize,
+}
+
+impl<O: Optimizer> Regularised<O> {
+    pub fn new(
+        optimizer: O,
+        penalties: Vec<Penalty>,
+        clip: Option<Clip>,
+        accumulation: usize,
+    ) -> Self {
+        assert!(accumulation > 0);
+        Regularised {
+            optimizer,
+            penalties,
+            clip,
+            accumulation,
+            accumulated: Vec::new(),
+            pending: 0,
+        }
+    }
+
+    fn penalty(&self, row: usize) -> Penalty {
+        self.penalties.get(row).copied().unwrap_or_default()
+    }
+}
+
+impl<O: Optimizer> Optimizer for Regularised<O> {
+    fn step(&mut self, mut parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        let share = 1.0 / self.accumulation as f32;
+        if self.pending == 0 {
+            self.accumulated = gradients.iter().map(|row| vec![0.0; row.len()]).collect();
+        }
+        for (sum, row) in self.accumulated.iter_mut().zip(gradients.iter()) {
+            for (s, g) in sum.iter_mut().zip(row.iter()) {
+                *s += share * g;
+            }
+        }
+        self.pending += 1;
+        if self.pending < self.accumulation {
+            return;
+        }
+        self.pending = 0;
+        let mut gradients = std::mem::take(&mut self.accumulated);
+
+        for (r, (row, parameter)) in gradients.iter_mut().zip(parameters.iter()).enumerate() {
+            let penalty = self.penalty(r);
+            for (g, p) in row.iter_mut().zip(parameter.iter()) {
+                let sign = if *p == 0.0 { 0.0 } else { p.signum() };
+                *g += penalty.l1 * sign + penalty.l2 * p;
+            }
+        }
+        if let Some(clip) = self.clip {
+            clip_gradients(&mut gradients, clip);
+        }
+        let rate = self.optimizer.learning_rate();
+        for (r, parameter) in parameters.iter_mut().enumerate() {
+            let shrink = 1.0 - rate * self.penalty(r).weight_decay;
+            parameter.iter_mut().for_each(|p| *p *= shrink);
+        }
+        self.optimizer.step(parameters, &gradients);
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.optimizer.learning_rate()
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.optimizer.set_learning_rate(learning_rate);
+    }
+
+    // clip is saved as [kind, limit] with kind 0 for none, 1 for norm and 2 for value
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.extend("optimizer", self.optimizer.save());
+        let penalties: Vec<Vec<f32>> = self
+            .penalties
+            .iter()
+            .map(|p| vec![p.l1, p.l2, p.weight_decay])
+            .collect();
+        record.push_rows("penalties", &penalties);
+        record.push(
+            "clip",
+            &match self.clip {
+                None => [0.0, 0.0],
+                Some(Clip::Norm(limit)) => [1.0, limit],
+                Some(Clip::Value(limit)) => [2.0, limit],
+            },
+        );
+        record.push("accumulation", &[self.accumulation as f32]);
+        record.push_rows("accumulated", &self.accumulated);
+        record.push_count("pending", self.pending as u64);
+        record
+    }
+
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let penalties = record
+            .rows("penalties")?
+            .iter()
+            .map(|p| match p[..] {
+                [l1, l2, weight_decay] => Ok(Penalty::new(l1, l2, weight_decay)),
+                _ => Err(invalid("a penalty needs l1, l2 and weight decay")),
+            })
+            .collect::<io::Result<Vec<Penalty>>>()?;
+        let accumulation = record.scalar("accumulation")? as usize;
+        let pending = record.count("pending")? as usize;
+        if accumulation == 0 || pending >= accumulation {
+            return Err(invalid("accumulation must be above zero and above pending"));
+        }
+        let clip = match record.get("clip")? {
+            [] | [0.0] | [0.0, _] => None,
+            [kind, limit] if *kind == 1.0 => Some(Clip::Norm(*limit)),
+            [kind, limit] if *kind == 2.0 => Some(Clip::Value(*limit)),
+            _ => return Err(invalid("clip is none, norm or value with a limit")),
+        };
+        self.optimizer.load(&record.section("optimizer"))?;
+        self.penalties = penalties;
+        self.clip = clip;
+        self.accumulation = accumulation;
+        self.accumulated = record.rows("accumulated")?;
+        self.pending = pending;
+        Ok(())
+    }
+}
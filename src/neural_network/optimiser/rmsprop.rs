@@ -0,0 +1,70 @@
+use std::io;
+
+use crate::neural_network::core::Record;
+
+use super::{initialise, Optimizer};
+
+// divides the step by a running root mean square of the gradient,
+// s = decay * s + (1 - decay) * g^2 and p -= rate * g / (sqrt(s) + epsilon)
+pub struct RmsProp {
+    pub learning_rate: f32,
+    pub decay: f32,
+    pub epsilon: f32,
+    pub square_average: Vec<Vec<f32>>,
+}
+
+impl RmsProp {
+    pub fn new(learning_rate: f32, decay: f32, epsilon: f32) -> Self {
+        RmsProp {
+            learning_rate,
+            decay,
+            epsilon,
+            square_average: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn step(&mut self, parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        initialise(&mut self.square_average, gradients);
+        for ((parameter, gradient), average) in parameters
+            .into_iter()
+            .zip(gradients.iter())
+            .zip(self.square_average.iter_mut())
+        {
+            for ((p, g), s) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(average.iter_mut())
+            {
+                *s = self.decay * *s + (1.0 - self.decay) * g * g;
+                *p -= self.learning_rate * g / (s.sqrt() + self.epsilon);
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("learning_rate", &[self.learning_rate]);
+        record.push("decay", &[self.decay]);
+        record.push("epsilon", &[self.epsilon]);
+        record.push_rows("square_average", &self.square_average);
+        record
+    }
+
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        self.learning_rate = record.scalar("learning_rate")?;
+        self.decay = record.scalar("decay")?;
+        self.epsilon = record.scalar("epsilon")?;
+        self.square_average = record.rows("square_average")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+use std::f32::consts::PI;
+use std::io;
+
+use crate::neural_network::core::Record;
+
+use super::Optimizer;
+
+// how the learning rate moves away from the base rate as steps go by; a step is
+// whatever the caller counts, usually an epoch or a batch
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schedule {
+    Constant,
+    // multiplied by gamma every size steps
+    Step {
+        size: usize,
+        gamma: f32,
+    },
+    Exponential {
+        gamma: f32,
+    },
+    // cosine from the base rate down to minimum over period steps, then back to
+    // the base with each period multiplier times longer than the last
+    CosineRestarts {
+        period: usize,
+        multiplier: usize,
+        minimum: f32,
+    },
+    // rises linearly to the base rate over steps, then follows after
+    Warmup {
+        steps: usize,
+        after: Box<Schedule>,
+    },
+    // cosine up from base / start to the base over the warmup fraction of total
+    // steps and then down to base / end, the base being the peak rate
+    OneCycle {
+        total: usize,
+        warmup: f32,
+        start: f32,
+        end: f32,
+    },
+    // (base - minimum) * (1 - step / total)^power + minimum, minimum after total
+    Polynomial {
+        total: usize,
+        power: f32,
+        minimum: f32,
+    },
+    // multiplied by factor whenever the metric, lower being better, has not
+    // improved on the best by a fraction of threshold for more than patience
+    // steps, but never below minimum; best, wait and scale are its running state
+    Plateau {
+        factor: f32,
+        patience: usize,
+        threshold: f32,
+        minimum: f32,
+        best: f32,
+        wait: usize,
+        scale: f32,
+    },
+}
+
+impl Schedule {
+    pub fn plateau(factor: f32, patience: usize, threshold: f32, minimum: f32) -> Self {
+        Schedule::Plateau {
+            factor,
+            patience,
+            threshold,
+            minimum,
+            best: f32::INFINITY,
+            wait: 0,
+            scale: 1.0,
+        }
+    }
+
+    // whether rate is defined at every step: step sizes, periods and totals above
+    // zero, a warmup fraction within [0, 1] and one cycle divisors above zero
+    pub fn check(&self) -> io::Result<()> {
+        let valid = match self {
+            Schedule::Step { size, .. } => *size > 0,
+            Schedule::CosineRestarts {
+                period, multiplier, ..
+            } => *period > 0 && *multiplier > 0,
+            Schedule::Warmup { after, .. } => return after.check(),
+            Schedule::OneCycle {
+                warmup, start, end, ..
+            } => (0.0..=1.0).contains(warmup) && *start > 0.0 && *end > 0.0,
+            Schedule::Polynomial { total, .. } => *total > 0,
+            _ => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid schedule {:?}", self),
+            ))
+        }
+    }
+
+    pub fn rate(&self, base: f32, step: usize) -> f32 {
+        match self {
+            Schedule::Constant => base,
+            Schedule::Step { size, gamma } => base * gamma.powi((step / size) as i32),
+            Schedule::Exponential { gamma } => base * gamma.powi(step as i32),
+            Schedule::CosineRestarts {
+                period,
+                multiplier,
+                minimum,
+            } => {
+                let (mut start, mut length) = (0, *period);
+                while step >= start + length {
+                    start += length;
+                    length *= multiplier;
+                }
+                let progress = (step - start) as f32 / length as f32;
+                minimum + (base - minimum) * (1.0 + (PI * progress).cos()) / 2.0
+            }
+            Schedule::Warmup { steps, after } => {
+                if step < *steps {
+                    base * (step + 1) as f32 / *steps as f32
+                } else {
+                    after.rate(base, step - steps)
+                }
+            }
+            Schedule::OneCycle {
+                total,
+                warmup,
+                start,
+                end,
+            } => {
+                let peak = ((warmup * *total as f32) as usize).max(1);
+                let anneal = |from: f32, to: f32, progress: f32| {
+                    to + (from - to) * (1.0 + (PI * progress.min(1.0)).cos()) / 2.0
+                };
+                if step < peak {
+                    anneal(base / start, base, step as f32 / peak as f32)
+                } else {
+                    let rest = total.saturating_sub(peak).max(1);
+                    anneal(base, base / end, (step - peak) as f32 / rest as f32)
+                }
+            }
+            Schedule::Polynomial {
+                total,
+                power,
+                minimum,
+            } => {
+                let remaining = 1.0 - step.min(*total) as f32 / *total as f32;
+                (base - minimum) * remaining.powf(*power) + minimum
+            }
+            Schedule::Plateau { minimum, scale, .. } => (base * scale).max(*minimum),
+        }
+    }
+
+    fn observe(&mut self, metric: f32) {
+        if let Schedule::Plateau {
+            factor,
+            patience,
+            threshold,
+            best,
+            wait,
+            scale,
+            ..
+        } = self
+        {
+            if metric < *best * (1.0 - *threshold) {
+                *best = metric;
+                *wait = 0;
+            } else {
+                *wait += 1;
+                if *wait > *patience {
+                    *scale *= *factor;
+                    *wait = 0;
+                }
+            }
+        }
+        if let Schedule::Warmup { after, .. } = self {
+            after.observe(metric);
+        }
+    }
+
+    // one entry named after the variant holding its numbers, and a warmup's
+    // inner schedule nested under warmup
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        match self {
+            Schedule::Constant => record.push("constant", &[]),
+            Schedule::Step { size, gamma } => record.push("step", &[*size as f32, *gamma]),
+            Schedule::Exponential { gamma } => record.push("exponential", &[*gamma]),
+            Schedule::CosineRestarts {
+                period,
+                multiplier,
+                minimum,
+            } => record.push(
+                "cosine_restarts",
+                &[*period as f32, *multiplier as f32, *minimum],
+            ),
+            Schedule::Warmup { steps, after } => {
+                record.push("warmup", &[*steps as f32]);
+                record.extend("warmup", after.save());
+            }
+            Schedule::OneCycle {
+                total,
+                warmup,
+                start,
+                end,
+            } => record.push("one_cycle", &[*total as f32, *warmup, *start, *end]),
+            Schedule::Polynomial {
+                total,
+                power,
+                minimum,
+            } => record.push("polynomial", &[*total as f32, *power, *minimum]),
+            Schedule::Plateau {
+                factor,
+                patience,
+                threshold,
+                minimum,
+                best,
+                wait,
+                scale,
+            } => record.push(
+                "plateau",
+                &[
+                    *factor,
+                    *patience as f32,
+                    *threshold,
+                    *minimum,
+                    *best,
+                    *wait as f32,
+                    *scale,
+                ],
+            ),
+        }
+        record
+    }
+
+    fn load(record: &Record) -> io::Result<Schedule> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "unknown schedule");
+        let (name, values) = record.entries.first().ok_or_else(invalid)?;
+        let n = |i: usize| values[i] as usize;
+        let schedule = match (name.as_str(), values.len()) {
+            ("constant", 0) => Schedule::Constant,
+            ("step", 2) => Schedule::Step {
+                size: n(0),
+                gamma: values[1],
+            },
+            ("exponential", 1) => Schedule::Exponential { gamma: values[0] },
+            ("cosine_restarts", 3) => Schedule::CosineRestarts {
+                period: n(0),
+                multiplier: n(1),
+                minimum: values[2],
+            },
+            ("warmup", 1) => Schedule::Warmup {
+                steps: n(0),
+                after: Box::new(Schedule::load(&record.section("warmup"))?),
+            },
+            ("one_cycle", 4) => Schedule::OneCycle {
+                total: n(0),
+                warmup: values[1],
+                start: values[2],
+                end: values[3],
+            },
+            ("polynomial", 3) => Schedule::Polynomial {
+                total: n(0),
+                power: values[1],
+                minimum: values[2],
+            },
+            ("plateau", 7) => Schedule::Plateau {
+                factor: values[0],
+                patience: n(1),
+                threshold: values[2],
+                minimum: values[3],
+                best: values[4],
+                wait: n(5),
+                scale: values[6],
+            },
+            _ => return Err(invalid()),
+        };
+        schedule.check()?;
+        Ok(schedule)
+    }
+}
+
+// drives the learning rate of an optimiser from a base rate and a schedule
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scheduler {
+    pub base: f32,
+    pub schedule: Schedule,
+    pub steps: usize,
+}
+
+impl Scheduler {
+    pub fn new(base: f32, schedule: Schedule) -> io::Result<Self> {
+        schedule.check()?;
+        Ok(Scheduler {
+            base,
+            schedule,
+            steps: 0,
+        })
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.schedule.rate(self.base, self.steps)
+    }
+
+    // sets the optimiser to the current rate, call once before training starts
+    pub fn apply(&self, optimizer: &mut dyn Optimizer) {
+        optimizer.set_learning_rate(self.rate());
+    }
+
+    // moves on a step and applies the new rate; the metric only matters to a
+    // plateau schedule, which doesn't move without one
+    pub fn step(&mut self, metric: Option<f32>, optimizer: &mut dyn Optimizer) {
+        if let Some(metric) = metric {
+            self.schedule.observe(metric);
+        }
+        self.steps += 1;
+        self.apply(optimizer);
+    }
+
+    pub fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("base", &[self.base]);
+        record.push_count("steps", self.steps as u64);
+        record.extend("schedule", self.schedule.save());
+        record
+    }
+
+    pub fn load(record: &Record) -> io::Result<Scheduler> {
+        Ok(Scheduler {
+            base: record.scalar("base")?,
+            schedule: Schedule::load(&record.section("schedule"))?,
+            steps: record.count("steps")? as usize,
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use std::io;
+
+use crate::neural_network::core::Record;
+
+use super::{initialise, Optimizer};
+
+// gradient descent with an optional velocity, v = momentum * v + g; the step is
+// v, or g + momentum * v looking ahead with Nesterov
+pub struct Sgd {
+    pub learning_rate: f32,
+    pub momentum: f32,
+    pub nesterov: bool,
+    pub velocity: Vec<Vec<f32>>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f32, momentum: f32, nesterov: bool) -> Self {
+        Sgd {
+            learning_rate,
+            momentum,
+            nesterov,
+            velocity: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, parameters: Vec<&mut [f32]>, gradients: &[Vec<f32>]) {
+        initialise(&mut self.velocity, gradients);
+        for ((parameter, gradient), velocity) in parameters
+            .into_iter()
+            .zip(gradients.iter())
+            .zip(self.velocity.iter_mut())
+        {
+            for ((p, g), v) in parameter
+                .iter_mut()
+                .zip(gradient.iter())
+                .zip(velocity.iter_mut())
+            {
+                *v = self.momentum * *v + g;
+                let step = if self.nesterov {
+                    g + self.momentum * *v
+                } else {
+                    *v
+                };
+                *p -= self.learning_rate * step;
+            }
+        }
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("learning_rate", &[self.learning_rate]);
+        record.push("momentum", &[self.momentum]);
+        record.push("nesterov", &[self.nesterov as u8 as f32]);
+        record.push_rows("velocity", &self.velocity);
+        record
+    }
+
+    fn load(&mut self, record: &Record) -> io::Result<()> {
+        self.learning_rate = record.scalar("learning_rate")?;
+        self.momentum = record.scalar("momentum")?;
+        self.nesterov = record.scalar("nesterov")? != 0.0;
+        self.velocity = record.rows("velocity")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,745 @@
+#[cfg(test)]
+mod test_optimiser {
+    use std::io;
+
+    use crate::neural_network::{
+        convolutional::{Conv2D, GroupNorm, ResidualBlock},
+        core::{mean_squared_error, Flatten, Layer2D, ParameterKind, Record},
+        linear::linear::Linear,
+        optimiser::{
+            clip_gradients, dot, minimize, minimize_with, penalties_by_kind, strong_wolfe,
+            Adadelta, Adagrad, Adam, AdamW, Beta, Clip, Method, Optimizer, Options, Penalty,
+            Regularised, RmsProp, Schedule, Scheduler, Sgd, Trial,
+        },
+        sequential::{Node, Sequential, Tensor},
+    };
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    // the gradient of sum((p - target)^2) / 2
+    fn gradients(parameters: &[Vec<f32>], target: &[f32]) -> Vec<Vec<f32>> {
+        parameters
+            .iter()
+            .map(|row| row.iter().zip(target.iter()).map(|(p, t)| p - t).collect())
+            .collect()
+    }
+
+    fn minimise(optimizer: &mut dyn Optimizer, steps: usize) -> Vec<Vec<f32>> {
+        let target = [1.0, -2.0, 3.0];
+        let mut parameters = vec![vec![0.0, 0.0, 0.0], vec![5.0, 5.0, -5.0]];
+        for _ in 0..steps {
+            let g = gradients(&parameters, &target);
+            optimizer.step(
+                parameters.iter_mut().map(|x| x.as_mut_slice()).collect(),
+                &g,
+            );
+        }
+        parameters
+            .iter()
+            .map(|row| row.iter().zip(target.iter()).map(|(p, t)| p - t).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_optimisers_converge() {
+        let mut optimizers: Vec<Box<dyn Optimizer>> = vec![
+            Box::new(Sgd::new(0.1, 0.0, false)),
+            Box::new(Sgd::new(0.1, 0.9, false)),
+            Box::new(Sgd::new(0.1, 0.9, true)),
+            Box::new(RmsProp::new(0.01, 0.9, 1e-8)),
+            Box::new(Adam::new(0.1, (0.9, 0.999), 1e-8)),
+            Box::new(AdamW::new(0.1, (0.9, 0.999), 1e-8, 0.0)),
+            Box::new(Adagrad::new(1.0, 1e-8)),
+            Box::new(Adadelta::new(1.0, 0.9, 1e-2)),
+        ];
+        for optimizer in optimizers.iter_mut() {
+            let residual = minimise(optimizer.as_mut(), 2000);
+            assert!(residual.iter().flatten().all(|r| r.abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn test_sgd_momentum() {
+        let mut parameter = vec![1.0];
+        let mut plain = Sgd::new(0.1, 0.5, false);
+        let mut nesterov = Sgd::new(0.1, 0.5, true);
+        let mut other = vec![1.0];
+        for _ in 0..2 {
+            plain.step(vec![&mut parameter], &[vec![2.0]]);
+            nesterov.step(vec![&mut other], &[vec![2.0]]);
+        }
+        // velocities 2 then 3, Nesterov steps 2 + 1 then 2 + 1.5
+        assert!(close(parameter[0], 1.0 - 0.1 * (2.0 + 3.0)));
+        assert!(close(other[0], 1.0 - 0.1 * (3.0 + 3.5)));
+        assert_eq!(plain.velocity, [[3.0]]);
+    }
+
+    #[test]
+    fn test_adaptive_steps() {
+        // the first corrected Adam step is the learning rate in the gradient's direction
+        let mut adam = Adam::new(0.1, (0.9, 0.999), 1e-8);
+        let mut parameter = vec![1.0, 1.0];
+        adam.step(vec![&mut parameter], &[vec![4.0, -0.01]]);
+        assert!(close(parameter[0], 0.9) && close(parameter[1], 1.1));
+        assert_eq!(adam.steps, 1);
+
+        // AdamW shrinks by rate * decay before the Adam step
+        let mut adamw = AdamW::new(0.1, (0.9, 0.999), 1e-8, 0.5);
+        let mut parameter = vec![2.0];
+        adamw.step(vec![&mut parameter], &[vec![1.0]]);
+        assert!(close(parameter[0], 2.0 * 0.95 - 0.1));
+
+        let mut rmsprop = RmsProp::new(0.01, 0.9, 0.0);
+        let mut parameter = vec![0.0];
+        rmsprop.step(vec![&mut parameter], &[vec![3.0]]);
+        assert!(close(parameter[0], -0.01 * 3.0 / 0.9f32.sqrt()));
+
+        let mut adagrad = Adagrad::new(0.5, 0.0);
+        let mut parameter = vec![0.0];
+        adagrad.step(vec![&mut parameter], &[vec![3.0]]);
+        adagrad.step(vec![&mut parameter], &[vec![4.0]]);
+        assert!(close(parameter[0], -0.5 - 0.5 * 4.0 / 5.0));
+
+        let mut adadelta = Adadelta::new(1.0, 0.9, 1e-6);
+        let mut parameter = vec![0.0];
+        adadelta.step(vec![&mut parameter], &[vec![1.0]]);
+        assert!(close(parameter[0], -(1e-6f32 / (0.1 + 1e-6)).sqrt()));
+    }
+
+    #[test]
+    fn test_learning_rate() {
+        let mut optimizer = AdamW::new(0.1, (0.9, 0.999), 1e-8, 0.01);
+        optimizer.set_learning_rate(0.02);
+        assert_eq!(optimizer.learning_rate(), 0.02);
+        assert_eq!(optimizer.adam.learning_rate, 0.02);
+    }
+
+    #[test]
+    fn test_sequential_optimise() {
+        let linear = Linear::new(2, 1);
+        let mut model = Sequential::new(vec![Node::Dense(Box::new(linear))]);
+        let inputs: Vec<Tensor> = (0..8)
+            .map(|i| Tensor::Flat(vec![(i as f32 * 0.7).sin(), (i as f32 * 1.3).cos()]))
+            .collect();
+        let targets: Vec<Vec<f32>> = inputs
+            .iter()
+            .map(|x| vec![2.0 * x.flat()[0] - x.flat()[1] + 0.5])
+            .collect();
+        let mut optimizer = Adam::new(0.05, (0.9, 0.999), 1e-8);
+        let first = model.optimise(&inputs, &targets, mean_squared_error, &mut optimizer);
+        let mut last = first;
+        for _ in 0..300 {
+            last = model.optimise(&inputs, &targets, mean_squared_error, &mut optimizer);
+        }
+        assert!(last < 1e-3 * first.max(1.0));
+        assert_eq!(optimizer.first_moment.len(), 2);
+    }
+
+    fn rates(schedule: Schedule, steps: usize) -> Vec<f32> {
+        (0..steps).map(|t| schedule.rate(1.0, t)).collect()
+    }
+
+    fn all_close(a: &[f32], b: &[f32]) -> bool {
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| close(*x, *y))
+    }
+
+    #[test]
+    fn test_schedules() {
+        let step = rates(
+            Schedule::Step {
+                size: 2,
+                gamma: 0.5,
+            },
+            5,
+        );
+        assert!(all_close(&step, &[1.0, 1.0, 0.5, 0.5, 0.25]));
+        let exponential = rates(Schedule::Exponential { gamma: 0.9 }, 3);
+        assert!(all_close(&exponential, &[1.0, 0.9, 0.81]));
+
+        // periods of 2 then 4 steps
+        let cosine = rates(
+            Schedule::CosineRestarts {
+                period: 2,
+                multiplier: 2,
+                minimum: 0.0,
+            },
+            7,
+        );
+        assert!(all_close(
+            &cosine,
+            &[1.0, 0.5, 1.0, 0.853553, 0.5, 0.146447, 1.0]
+        ));
+
+        let warmup = rates(
+            Schedule::Warmup {
+                steps: 4,
+                after: Box::new(Schedule::Exponential { gamma: 0.5 }),
+            },
+            6,
+        );
+        assert!(all_close(&warmup, &[0.25, 0.5, 0.75, 1.0, 1.0, 0.5]));
+
+        let one_cycle = rates(
+            Schedule::OneCycle {
+                total: 10,
+                warmup: 0.2,
+                start: 10.0,
+                end: 100.0,
+            },
+            11,
+        );
+        assert!(close(one_cycle[0], 0.1) && close(one_cycle[1], 0.55) && close(one_cycle[2], 1.0));
+        assert!(close(one_cycle[6], 0.505) && close(one_cycle[10], 0.01));
+        assert!(one_cycle[2..].windows(2).all(|pair| pair[1] < pair[0]));
+
+        let polynomial = rates(
+            Schedule::Polynomial {
+                total: 4,
+                power: 2.0,
+                minimum: 0.2,
+            },
+            6,
+        );
+        assert!(all_close(&polynomial, &[1.0, 0.65, 0.4, 0.25, 0.2, 0.2]));
+    }
+
+    #[test]
+    fn test_plateau() {
+        let mut optimizer = Sgd::new(0.0, 0.0, false);
+        let mut scheduler = Scheduler::new(0.1, Schedule::plateau(0.5, 1, 0.01, 0.02)).unwrap();
+        scheduler.apply(&mut optimizer);
+        assert_eq!(optimizer.learning_rate(), 0.1);
+        let mut seen = Vec::new();
+        for metric in [1.0, 0.9, 0.895, 0.9, 0.7, 0.8, 0.8, 0.8, 0.8, 0.8, 0.8] {
+            scheduler.step(Some(metric), &mut optimizer);
+            seen.push(optimizer.learning_rate());
+        }
+        // halved after two steps without a 1% improvement, never below 0.02
+        let expected = [
+            0.1, 0.1, 0.1, 0.05, 0.05, 0.05, 0.025, 0.025, 0.02, 0.02, 0.02,
+        ];
+        assert!(all_close(&seen, &expected));
+
+        // without a metric only the step count moves
+        scheduler.step(None, &mut optimizer);
+        assert_eq!(scheduler.steps, 12);
+        assert_eq!(optimizer.learning_rate(), 0.02);
+    }
+
+    #[test]
+    fn test_scheduler_round_trip() {
+        let schedules = [
+            Schedule::Constant,
+            Schedule::Step {
+                size: 3,
+                gamma: 0.1,
+            },
+            Schedule::CosineRestarts {
+                period: 5,
+                multiplier: 2,
+                minimum: 1e-4,
+            },
+            Schedule::Warmup {
+                steps: 3,
+                after: Box::new(Schedule::Warmup {
+                    steps: 2,
+                    after: Box::new(Schedule::plateau(0.3, 2, 0.0, 0.0)),
+                }),
+            },
+            Schedule::OneCycle {
+                total: 100,
+                warmup: 0.3,
+                start: 25.0,
+                end: 1e4,
+            },
+            Schedule::Polynomial {
+                total: 50,
+                power: 0.9,
+                minimum: 0.0,
+            },
+        ];
+        let mut optimizer = Sgd::new(0.0, 0.0, false);
+        for schedule in schedules {
+            let mut scheduler = Scheduler::new(0.3, schedule).unwrap();
+            for t in 0..7 {
+                scheduler.step(Some(1.0 / (1 + t % 3) as f32), &mut optimizer);
+            }
+            let text = scheduler.save().to_text();
+            let loaded = Scheduler::load(&Record::from_text(&text).unwrap()).unwrap();
+            assert_eq!(loaded, scheduler);
+        }
+        assert!(
+            Scheduler::load(&Record::from_text("base 1\nsteps 0\nschedule.step 1").unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        let invalid = [
+            Schedule::Step {
+                size: 0,
+                gamma: 0.5,
+            },
+            Schedule::CosineRestarts {
+                period: 0,
+                multiplier: 2,
+                minimum: 0.0,
+            },
+            Schedule::CosineRestarts {
+                period: 4,
+                multiplier: 0,
+                minimum: 0.0,
+            },
+            Schedule::Warmup {
+                steps: 2,
+                after: Box::new(Schedule::Step {
+                    size: 0,
+                    gamma: 0.5,
+                }),
+            },
+            Schedule::OneCycle {
+                total: 10,
+                warmup: 1.5,
+                start: 10.0,
+                end: 100.0,
+            },
+            Schedule::OneCycle {
+                total: 10,
+                warmup: 0.2,
+                start: 0.0,
+                end: 100.0,
+            },
+            Schedule::Polynomial {
+                total: 0,
+                power: 1.0,
+                minimum: 0.0,
+            },
+        ];
+        for schedule in invalid {
+            let error = Scheduler::new(0.1, schedule.clone()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            let scheduler = Scheduler {
+                base: 0.1,
+                schedule,
+                steps: 0,
+            };
+            let text = scheduler.save().to_text();
+            let error = Scheduler::load(&Record::from_text(&text).unwrap()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    // the state saved mid run carries on exactly as the run that wasn't stopped
+    #[test]
+    fn test_optimiser_round_trip() {
+        let target = [1.0, -2.0, 3.0];
+        let run = |optimizer: &mut dyn Optimizer, parameters: &mut Vec<Vec<f32>>, steps| {
+            for _ in 0..steps {
+                let g = gradients(parameters, &target);
+                optimizer.step(
+                    parameters.iter_mut().map(|x| x.as_mut_slice()).collect(),
+                    &g,
+                );
+            }
+        };
+        let fresh: Vec<Box<dyn Fn() -> Box<dyn Optimizer>>> = vec![
+            Box::new(|| Box::new(Sgd::new(0.1, 0.9, true))),
+            Box::new(|| Box::new(RmsProp::new(0.01, 0.9, 1e-8))),
+            Box::new(|| Box::new(Adam::new(0.1, (0.9, 0.999), 1e-8))),
+            Box::new(|| Box::new(AdamW::new(0.1, (0.8, 0.99), 1e-6, 0.01))),
+            Box::new(|| Box::new(Adagrad::new(1.0, 1e-8))),
+            Box::new(|| Box::new(Adadelta::new(1.0, 0.9, 1e-2))),
+        ];
+        for make in fresh {
+            let mut straight = make();
+            let mut expected = vec![vec![0.0, 0.0, 0.0], vec![5.0, 5.0, -5.0]];
+            run(straight.as_mut(), &mut expected, 10);
+
+            let mut first = make();
+            let mut parameters = vec![vec![0.0, 0.0, 0.0], vec![5.0, 5.0, -5.0]];
+            run(first.as_mut(), &mut parameters, 4);
+            let text = first.save().to_text();
+            let mut resumed = make();
+            resumed.set_learning_rate(123.0);
+            resumed.load(&Record::from_text(&text).unwrap()).unwrap();
+            run(resumed.as_mut(), &mut parameters, 6);
+            assert_eq!(parameters, expected);
+        }
+
+        // adam rejects a short betas row and moments that don't match
+        let mut adam = Adam::new(0.1, (0.9, 0.999), 1e-8);
+        let mut parameters = vec![vec![0.0, 0.0, 0.0], vec![5.0, 5.0, -5.0]];
+        run(&mut adam, &mut parameters, 2);
+        let text = adam.save().to_text();
+        let second = text.find("second_moment.1").unwrap();
+        for broken in [
+            text.replace("betas 0.9 0.999", "betas 0.9"),
+            text[..second].replace("second_moment 2", "second_moment 1"),
+        ] {
+            assert_ne!(broken, text);
+            let error = adam.load(&Record::from_text(&broken).unwrap()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        assert_eq!(adam.betas, (0.9, 0.999));
+    }
+
+    #[test]
+    fn test_clip_gradients() {
+        let mut gradients = vec![vec![3.0], vec![-4.0]];
+        assert_eq!(clip_gradients(&mut gradients, Clip::Norm(10.0)), 5.0);
+        assert_eq!(gradients, [[3.0], [-4.0]]);
+        assert_eq!(clip_gradients(&mut gradients, Clip::Norm(1.0)), 5.0);
+        assert!(close(gradients[0][0], 0.6) && close(gradients[1][0], -0.8));
+
+        let mut gradients = vec![vec![3.0, -0.5], vec![-4.0]];
+        clip_gradients(&mut gradients, Clip::Value(1.0));
+        assert_eq!(gradients, [vec![1.0, -0.5], vec![-1.0]]);
+    }
+
+    #[test]
+    fn test_penalties() {
+        let kinds = [
+            ParameterKind::Weight,
+            ParameterKind::Bias,
+            ParameterKind::Norm,
+        ];
+        let penalties = penalties_by_kind(&kinds, |kind| match kind {
+            ParameterKind::Weight => Penalty::new(0.0, 0.5, 0.0),
+            ParameterKind::Bias => Penalty::new(1.0, 0.0, 0.0),
+            ParameterKind::Norm => Penalty::new(0.0, 0.0, 2.0),
+        });
+        let mut optimizer = Regularised::new(Sgd::new(0.1, 0.0, false), penalties, None, 1);
+        let mut rows = [vec![2.0, -4.0], vec![-2.0, 0.0], vec![3.0]];
+        let gradients = vec![vec![0.0, 1.0], vec![0.0, 0.0], vec![1.0]];
+        optimizer.step(
+            rows.iter_mut().map(|x| x.as_mut_slice()).collect(),
+            &gradients,
+        );
+        // l2 adds 0.5 p, l1 adds sign(p) except at zero, decay shrinks by 1 - 0.1 * 2
+        assert!(close(rows[0][0], 2.0 - 0.1 * 1.0) && close(rows[0][1], -4.0 - 0.1 * (1.0 - 2.0)));
+        assert!(close(rows[1][0], -2.0 + 0.1) && close(rows[1][1], 0.0));
+        assert!(close(rows[2][0], 3.0 * 0.8 - 0.1));
+    }
+
+    #[test]
+    fn test_clipped_step() {
+        let mut optimizer = Regularised::new(
+            Sgd::new(1.0, 0.0, false),
+            Vec::new(),
+            Some(Clip::Norm(1.0)),
+            1,
+        );
+        let mut rows = [vec![0.0, 0.0]];
+        optimizer.step(vec![&mut rows[0]], &[vec![30.0, 40.0]]);
+        assert!(close(rows[0][0], -0.6) && close(rows[0][1], -0.8));
+    }
+
+    // two micro batches of four accumulate to the update of one batch of eight
+    #[test]
+    fn test_gradient_accumulation() {
+        let model = || {
+            let conv = Conv2D::new((1, 4, 4), 2, (3, 3), (0, 0), (1, 1));
+            let flatten = Flatten::new(conv.dim_out());
+            let linear = Linear::new(flatten.dim_out(), 1);
+            Sequential::new(vec![
+                Node::Spatial(Box::new(conv)),
+                Node::Flatten(flatten),
+                Node::Dense(Box::new(linear)),
+            ])
+        };
+        let mut whole = model();
+        let mut split = model();
+        let mut copy: Vec<Vec<f32>> = whole.parameters().iter().map(|p| p.to_vec()).collect();
+        for (p, q) in split.parameters().into_iter().zip(copy.iter_mut()) {
+            p.copy_from_slice(q);
+        }
+        let inputs: Vec<Tensor> = (0..8)
+            .map(|k| {
+                Tensor::Spatial(vec![(0..4)
+                    .map(|i| {
+                        (0..4)
+                            .map(|j| ((k * 16 + i * 4 + j) as f32 * 0.3).sin())
+                            .collect()
+                    })
+                    .collect()])
+            })
+            .collect();
+        let targets: Vec<Vec<f32>> = (0..8).map(|k| vec![(k as f32).cos()]).collect();
+
+        let mut plain = Sgd::new(0.1, 0.9, false);
+        whole.optimise(&inputs, &targets, mean_squared_error, &mut plain);
+        let mut accumulating = Regularised::new(Sgd::new(0.1, 0.9, false), Vec::new(), None, 2);
+        split.optimise(
+            &inputs[..4],
+            &targets[..4],
+            mean_squared_error,
+            &mut accumulating,
+        );
+        assert_eq!(accumulating.pending, 1);
+        for (p, q) in split.parameters().iter().zip(copy.iter()) {
+            assert_eq!(p.to_vec(), *q);
+        }
+        split.optimise(
+            &inputs[4..],
+            &targets[4..],
+            mean_squared_error,
+            &mut accumulating,
+        );
+        assert_eq!(accumulating.pending, 0);
+        for (p, q) in split.parameters().iter().zip(whole.parameters().iter()) {
+            assert!(p.iter().zip(q.iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+        }
+    }
+
+    #[test]
+    fn test_parameter_kinds() {
+        let conv = Conv2D::new((1, 4, 4), 2, (3, 3), (0, 0), (1, 1));
+        let norm = GroupNorm::new(conv.dim_out(), 1);
+        let flatten = Flatten::new(norm.dim_out());
+        let linear = Linear::new(flatten.dim_out(), 3);
+        let mut model = Sequential::new(vec![
+            Node::Spatial(Box::new(conv)),
+            Node::Spatial(Box::new(norm)),
+            Node::Flatten(flatten),
+            Node::Dense(Box::new(linear)),
+        ]);
+        use ParameterKind::{Bias, Norm, Weight};
+        let mut expected = vec![Weight; 6];
+        expected.extend([Bias, Norm, Norm, Weight, Weight, Weight, Bias]);
+        assert_eq!(model.parameter_kinds(), expected);
+
+        let mut block = ResidualBlock::basic((2, 4, 4), 4, 2, 2);
+        let kinds = block.parameter_kinds();
+        assert_eq!(kinds.len(), block.parameters().len());
+        assert_eq!(kinds.iter().filter(|k| **k == Norm).count(), 6);
+        assert_eq!(kinds.iter().filter(|k| **k == Bias).count(), 3);
+    }
+
+    #[test]
+    fn test_regularised_round_trip() {
+        let make = || {
+            Regularised::new(
+                Adam::new(0.1, (0.9, 0.999), 1e-8),
+                vec![Penalty::new(0.01, 0.1, 0.0), Penalty::new(0.0, 0.0, 0.05)],
+                Some(Clip::Value(2.0)),
+                3,
+            )
+        };
+        let target = [1.0, -2.0, 3.0];
+        let run = |optimizer: &mut dyn Optimizer, parameters: &mut Vec<Vec<f32>>, steps| {
+            for _ in 0..steps {
+                let g = gradients(parameters, &target);
+                optimizer.step(
+                    parameters.iter_mut().map(|x| x.as_mut_slice()).collect(),
+                    &g,
+                );
+            }
+        };
+        let mut straight = make();
+        let mut expected = vec![vec![0.0, 0.0, 0.0], vec![5.0, 5.0, -5.0]];
+        run(&mut straight, &mut expected, 11);
+
+        let mut first = make();
+        let mut parameters = vec![vec![0.0, 0.0, 0.0], vec![5.0, 5.0, -5.0]];
+        run(&mut first, &mut parameters, 5);
+        let text = first.save().to_text();
+        let mut resumed = Regularised::new(Adam::new(1.0, (0.5, 0.5), 1.0), Vec::new(), None, 1);
+        resumed.load(&Record::from_text(&text).unwrap()).unwrap();
+        assert_eq!((resumed.pending, resumed.clip), (2, Some(Clip::Value(2.0))));
+        run(&mut resumed, &mut parameters, 6);
+        assert_eq!(parameters, expected);
+
+        // a short penalty row, no accumulation or an unknown clip is rejected
+        for (from, to) in [
+            ("penalties.0 0.01 0.1 0\n", "penalties.0 0.01 0.1\n"),
+            ("accumulation 3", "accumulation 0"),
+            ("clip 2 2", "clip 3 2"),
+            ("clip 2 2", "clip 2"),
+        ] {
+            let broken = text.replace(from, to);
+            assert_ne!(broken, text);
+            let error = resumed
+                .load(&Record::from_text(&broken).unwrap())
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        let unclipped = text.replace("clip 2 2", "clip 0");
+        resumed
+            .load(&Record::from_text(&unclipped).unwrap())
+            .unwrap();
+        assert_eq!(resumed.clip, None);
+    }
+
+    fn rosenbrock(x: &[f64]) -> (f64, Vec<f64>) {
+        let (a, b) = (1.0 - x[0], x[1] - x[0] * x[0]);
+        (
+            a * a + 100.0 * b * b,
+            vec![-2.0 * a - 400.0 * x[0] * b, 200.0 * b],
+        )
+    }
+
+    const METHODS: [Method; 4] = [
+        Method::Lbfgs { memory: 5 },
+        Method::ConjugateGradient {
+            beta: Beta::FletcherReeves,
+        },
+        Method::ConjugateGradient {
+            beta: Beta::PolakRibiere,
+        },
+        Method::ConjugateGradient {
+            beta: Beta::HestenesStiefel,
+        },
+    ];
+
+    #[test]
+    fn test_strong_wolfe() {
+        let mut objective = |x: &[f64]| rosenbrock(x);
+        let (value, gradient) = rosenbrock(&[-1.2, 1.0]);
+        let direction: Vec<f64> = gradient.iter().map(|g| -g).collect();
+        let start = Trial {
+            step: 0.0,
+            value,
+            slope: dot(&gradient, &direction),
+            x: vec![-1.2, 1.0],
+            gradient,
+        };
+        for (initial, c2) in [(1.0, 0.9), (1e-4, 0.9), (1.0, 0.1)] {
+            let found =
+                strong_wolfe(&mut objective, &start, &direction, initial, 1e-4, c2).unwrap();
+            assert!(found.value <= start.value + 1e-4 * found.step * start.slope);
+            assert!(found.slope.abs() <= -c2 * start.slope);
+            assert_eq!(rosenbrock(&found.x).0, found.value);
+        }
+    }
+
+    #[test]
+    fn test_minimize_rosenbrock() {
+        for method in METHODS {
+            let mut options = Options::new(method);
+            options.max_iterations = 5000;
+            options.value_tolerance = 0.0;
+            let minimum = minimize_with(rosenbrock, &[-1.2, 1.0], &options);
+            assert!(minimum.converged, "{method:?}");
+            assert!(minimum.gradient.iter().all(|g| g.abs() <= 1e-6));
+            assert!((minimum.x[0] - 1.0).abs() < 1e-5 && (minimum.x[1] - 1.0).abs() < 1e-5);
+        }
+        let minimum = minimize(|x| rosenbrock(x).0, |x| rosenbrock(x).1, &[-1.2, 1.0]);
+        assert!(minimum.converged && minimum.iterations < 100);
+    }
+
+    // sum of i (x_i - i)^2 / 2, which conjugate gradient solves in n iterations
+    #[test]
+    fn test_minimize_quadratic() {
+        let quadratic = |x: &[f64]| {
+            let value = (0..x.len())
+                .map(|i| (i + 1) as f64 * (x[i] - i as f64).powi(2) / 2.0)
+                .sum();
+            let gradient = (0..x.len())
+                .map(|i| (i + 1) as f64 * (x[i] - i as f64))
+                .collect();
+            (value, gradient)
+        };
+        for method in METHODS {
+            let mut options = Options::new(method);
+            options.value_tolerance = 0.0;
+            let minimum = minimize_with(quadratic, &[0.0; 10], &options);
+            assert!(minimum.converged && minimum.iterations <= 40, "{method:?}");
+            for (i, x) in minimum.x.iter().enumerate() {
+                assert!((x - i as f64).abs() < 1e-5);
+            }
+        }
+    }
+
+    // (label, features) with labels -1 and 1 either side of a + 2b = 1, with
+    // a few on the wrong side
+    fn labelled() -> Vec<(f64, [f64; 2])> {
+        (0..60)
+            .map(|k| {
+                let (a, b) = (
+                    (k * 7 % 17) as f64 / 8.0 - 1.0,
+                    (k * 5 % 13) as f64 / 6.0 - 1.0,
+                );
+                let side = if a + 2.0 * b > 1.0 { 1.0 } else { -1.0 };
+                (if k % 15 == 0 { -side } else { side }, [a, b])
+            })
+            .collect()
+    }
+
+    // the mean logistic loss and a primal svm with a squared hinge, both with
+    // an l2 penalty on the weights, over (w1, w2, bias)
+    #[test]
+    fn test_minimize_classifiers() {
+        let data = labelled();
+        let n = data.len() as f64;
+        let margin = |x: &[f64], (y, p): &(f64, [f64; 2])| y * (x[0] * p[0] + x[1] * p[1] + x[2]);
+        let logistic = |x: &[f64]| {
+            let mut value = 0.005 * (x[0] * x[0] + x[1] * x[1]);
+            let mut gradient = vec![0.01 * x[0], 0.01 * x[1], 0.0];
+            for sample in &data {
+                let m = margin(x, sample);
+                value += (1.0 + (-m).exp()).ln() / n;
+                let scale = -sample.0 / (1.0 + m.exp()) / n;
+                for (g, p) in gradient.iter_mut().zip([sample.1[0], sample.1[1], 1.0]) {
+                    *g += scale * p;
+                }
+            }
+            (value, gradient)
+        };
+        let svm = |x: &[f64]| {
+            let mut value = 0.005 * (x[0] * x[0] + x[1] * x[1]);
+            let mut gradient = vec![0.01 * x[0], 0.01 * x[1], 0.0];
+            for sample in &data {
+                let slack = (1.0 - margin(x, sample)).max(0.0);
+                value += slack * slack / n;
+                let scale = -2.0 * slack * sample.0 / n;
+                for (g, p) in gradient.iter_mut().zip([sample.1[0], sample.1[1], 1.0]) {
+                    *g += scale * p;
+                }
+            }
+            (value, gradient)
+        };
+        for objective in [&logistic as &dyn Fn(&[f64]) -> (f64, Vec<f64>), &svm] {
+            let reference = minimize_with(objective, &[0.0; 3], &Options::new(METHODS[0]));
+            let wrong = data
+                .iter()
+                .filter(|s| margin(&reference.x, s) <= 0.0)
+                .count();
+            assert!(reference.converged && wrong <= 6);
+            // every method finds the same optimum, the problems being convex
+            for method in &METHODS[1..] {
+                let minimum = minimize_with(objective, &[0.0; 3], &Options::new(*method));
+                assert!(minimum.converged, "{method:?}");
+                for (x, y) in minimum.x.iter().zip(reference.x.iter()) {
+                    assert!((x - y).abs() < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sequential_minimise() {
+        let mut linear = Linear::new(2, 1);
+        linear.weights = vec![vec![0.3, -0.2]];
+        let mut model = Sequential::new(vec![Node::Dense(Box::new(linear))]);
+        let (inputs, targets): (Vec<Tensor>, Vec<Vec<f32>>) = (0..12)
+            .map(|k| {
+                let (a, b) = ((k % 4) as f32 - 1.5, (k / 4) as f32 - 1.0);
+                (Tensor::Flat(vec![a, b]), vec![2.0 * a - b + 0.5])
+            })
+            .unzip();
+        for method in METHODS {
+            let mut options = Options::new(method);
+            options.gradient_tolerance = 1e-5;
+            let minimum = model.minimise(&inputs, &targets, mean_squared_error, &options);
+            assert!(minimum.value < 1e-9, "{method:?}");
+            let parameters: Vec<f32> = model.parameters().concat();
+            for (p, expected) in parameters.iter().zip([2.0, -1.0, 0.5]) {
+                assert!((p - expected).abs() < 1e-4);
+            }
+            model.parameters()[0].copy_from_slice(&[0.3, -0.2]);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::neural_network::convolutional::{col2im, im2col, Conv2D};
+use crate::neural_network::core::{stack, unstack, Layer, Layer2D};
+use crate::neural_network::linear::linear::Linear;
+
+use super::SparseMatrix;
+
+// Linear with the weights in compressed sparse rows, so a pruned layer only
+// multiplies what is left; the stored values and the bias are its parameters,
+// so it can be fine tuned without the pruned weights coming back
+pub struct SparseLinear {
+    pub dim_in: usize,
+    pub dim_out: usize,
+    pub weights: SparseMatrix,
+    pub bias: Vec<f32>,
+}
+
+impl SparseLinear {
+    pub fn new(linear: &Linear) -> Self {
+        SparseLinear {
+            dim_in: linear.dim_in,
+            dim_out: linear.dim_out,
+            weights: SparseMatrix::from_dense(&linear.weights),
+            bias: linear.bias.clone(),
+        }
+    }
+}
+
+impl Layer for SparseLinear {
+    fn dim_in(&self) -> usize {
+        self.dim_in
+    }
+    fn dim_out(&self) -> usize {
+        self.dim_out
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights
+            .matvec(input)
+            .iter()
+            .zip(self.bias.iter())
+            .map(|(x, b)| x + b)
+            .collect()
+    }
+
+    // the weight gradients are a single row, one entry per stored value
+    fn backward(
+        &self,
+        input: &[f32],
+        error: &[f32],
+    ) -> (Vec<f32>, Option<Vec<Vec<f32>>>, Option<Vec<f32>>) {
+        (
+            self.weights.transpose_matvec(error),
+            Some(vec![self.weights.outer_values(error, input)]),
+            Some(error.to_vec()),
+        )
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        vec![&mut self.weights.values, &mut self.bias]
+    }
+}
+
+// Conv2D through im2col with one sparse matrix of filters per group, each
+// [out_channel / groups][in_channel / groups * rows * cols]; conv keeps the
+// layout and padding, its own weights are dropped
+pub struct SparseConv2D {
+    pub conv: Conv2D,
+    pub weights: Vec<SparseMatrix>,
+    pub bias: Vec<f32>,
+}
+
+impl SparseConv2D {
+    pub fn new(conv: &Conv2D) -> Self {
+        let (_, out_channels) = conv.group_channels();
+        let rows: Vec<Vec<f32>> = conv
+            .weights
+            .iter()
+            .map(|filters| filters.iter().flatten().flatten().copied().collect())
+            .collect();
+        let weights = rows
+            .chunks(out_channels)
+            .map(SparseMatrix::from_dense)
+            .collect();
+        let mut conv = conv.clone();
+        conv.weights = Vec::new();
+        SparseConv2D {
+            bias: conv.bias.clone(),
+            conv,
+            weights,
+        }
+    }
+
+    pub fn density(&self) -> f32 {
+        let stored: usize = self.weights.iter().map(|w| w.nonzeros()).sum();
+        let size: usize = self.weights.iter().map(|w| w.rows * w.cols).sum();
+        stored as f32 / size.max(1) as f32
+    }
+
+    fn columns(&self, input: &[Vec<Vec<f32>>], group: usize) -> Vec<Vec<f32>> {
+        let (in_channels, _) = self.conv.group_channels();
+        im2col(
+            &input[(group * in_channels)..((group + 1) * in_channels)],
+            self.conv.kernel,
+            (0, 0),
+            self.conv.stride,
+            self.conv.dilation,
+        )
+    }
+}
+
+impl Layer2D for SparseConv2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.conv.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.conv.dim_out
+    }
+
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let input = self.conv.pad_input(input);
+        let size = (self.conv.dim_out.1, self.conv.dim_out.2);
+        self.weights
+            .iter()
+            .enumerate()
+            .flat_map(|(g, weights)| weights.matmul(&self.columns(&input, g)))
+            .zip(self.bias.iter())
+            .map(|(row, b)| {
+                let shifted: Vec<f32> = row.iter().map(|x| x + b).collect();
+                stack(&shifted, size)
+            })
+            .collect()
+    }
+
+    // the filter error is a single filter with a row of value gradients per
+    // group, which lines up with parameters
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        let (in_channels, out_channels) = self.conv.group_channels();
+        let error_matrix: Vec<Vec<f32>> = error.iter().map(|e| unstack(e)).collect();
+        let input = self.conv.pad_input(input);
+        let padded_size = self.conv.padded_size();
+        let mut padded_error = Vec::new();
+        let mut value_error = Vec::new();
+        for (g, weights) in self.weights.iter().enumerate() {
+            let columns = self.columns(&input, g);
+            let group_error = &error_matrix[(g * out_channels)..((g + 1) * out_channels)];
+            padded_error.extend(col2im(
+                &weights.transpose_matmul(group_error),
+                (in_channels, padded_size.0, padded_size.1),
+                self.conv.kernel,
+                (0, 0),
+                self.conv.stride,
+                self.conv.dilation,
+            ));
+            value_error.push(weights.value_gradients(&columns, group_error));
+        }
+        let bias_error = error_matrix.iter().map(|row| row.iter().sum()).collect();
+
+        (
+            self.conv.unpad_error(&padded_error),
+            Some(vec![value_error]),
+            Some(bias_error),
+        )
+    }
+
+    fn parameters(&mut self) -> Vec<&mut [f32]> {
+        let mut parameters: Vec<&mut [f32]> = self
+            .weights
+            .iter_mut()
+            .map(|w| w.values.as_mut_slice())
+            .collect();
+        parameters.push(&mut self.bias);
+        parameters
+    }
+}
@@ -0,0 +1,166 @@
+use crate::neural_network::core::{weights_then_bias, ParameterKind};
+use crate::neural_network::sequential::Sequential;
+use crate::neural_network::training::{Callback, Trainer};
+
+// what gets removed: single weights of the smallest magnitude, or whole output
+// units, the neurons of a Linear or the channels of a convolution, with the
+// smallest root mean square weight, each taking its bias entry with it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pruning {
+    Magnitude,
+    Structured,
+}
+
+// whether every layer loses the same fraction or one ranking runs across them
+// all, letting the layers with the most to spare lose the most
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+    Layer,
+    Global,
+}
+
+// a flag per entry of every parameter row of a model, false where the entry
+// has been pruned
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mask {
+    pub rows: Vec<Vec<bool>>,
+}
+
+impl Mask {
+    // keeps everything
+    pub fn full(model: &mut Sequential) -> Self {
+        Mask {
+            rows: model
+                .parameters()
+                .iter()
+                .map(|p| vec![true; p.len()])
+                .collect(),
+        }
+    }
+
+    pub fn apply(&self, model: &mut Sequential) {
+        for (parameter, keep) in model.parameters().into_iter().zip(self.rows.iter()) {
+            for (p, k) in parameter.iter_mut().zip(keep.iter()) {
+                if !k {
+                    *p = 0.0;
+                }
+            }
+        }
+    }
+
+    pub fn pruned(&self) -> usize {
+        self.rows.iter().flatten().filter(|k| !**k).count()
+    }
+}
+
+// puts the pruned entries back to zero after every optimiser step, so neither
+// the gradient nor the optimiser's momentum brings them back while fine tuning
+impl Callback for Mask {
+    fn on_step_end(&mut self, _trainer: &Trainer, model: &mut Sequential) {
+        self.apply(model);
+    }
+}
+
+// the fraction of the weights of a model that are zero, biases and
+// normalisation aside
+pub fn sparsity(model: &mut Sequential) -> f32 {
+    let kinds = model.parameter_kinds();
+    let (zeros, total) = model
+        .parameters()
+        .iter()
+        .zip(kinds.iter())
+        .filter(|(_, kind)| **kind == ParameterKind::Weight)
+        .fold((0, 0), |(zeros, total), (p, _)| {
+            (
+                zeros + p.iter().filter(|x| **x == 0.0).count(),
+                total + p.len(),
+            )
+        });
+    zeros as f32 / total.max(1) as f32
+}
+
+// (row, column) in the parameters of the whole model
+type Entry = (usize, usize);
+
+// what could be pruned, ranked by the score that goes with it
+type Candidate = (f32, Vec<Entry>);
+
+fn weight_candidates(rows: &[Vec<f32>], kinds: &[ParameterKind], offset: usize) -> Vec<Candidate> {
+    rows.iter()
+        .zip(kinds.iter())
+        .enumerate()
+        .filter(|(_, (_, kind))| **kind == ParameterKind::Weight)
+        .flat_map(|(r, (row, _))| {
+            row.iter()
+                .enumerate()
+                .map(move |(c, w)| (w.abs(), vec![(offset + r, c)]))
+        })
+        .collect()
+}
+
+// only layers laid out as weight rows then a bias with an entry per unit, the
+// weight rows splitting evenly between the units, as Linear and the
+// convolutions are; anything else is left whole
+fn unit_candidates(rows: &[Vec<f32>], kinds: &[ParameterKind], offset: usize) -> Vec<Candidate> {
+    if rows.is_empty() || kinds != weights_then_bias(rows.len()) {
+        return Vec::new();
+    }
+    let (weight_rows, units) = (rows.len() - 1, rows[rows.len() - 1].len());
+    if units == 0 || !weight_rows.is_multiple_of(units) {
+        return Vec::new();
+    }
+    let per_unit = weight_rows / units;
+    (0..units)
+        .map(|u| {
+            let unit_rows = (u * per_unit)..((u + 1) * per_unit);
+            let weights: Vec<f32> = rows[unit_rows.clone()].iter().flatten().copied().collect();
+            let mean_square =
+                weights.iter().map(|w| w * w).sum::<f32>() / weights.len().max(1) as f32;
+            let mut entries: Vec<Entry> = unit_rows
+                .flat_map(|r| (0..rows[r].len()).map(move |c| (offset + r, c)))
+                .collect();
+            entries.push((offset + weight_rows, u));
+            (mean_square.sqrt(), entries)
+        })
+        .collect()
+}
+
+// the candidates of each node in turn
+fn candidates(model: &mut Sequential, pruning: Pruning) -> Vec<Vec<Candidate>> {
+    let mut offset = 0;
+    let mut nodes = Vec::new();
+    for node in model.nodes.iter_mut() {
+        let kinds = node.parameter_kinds();
+        let rows: Vec<Vec<f32>> = node.parameters().iter().map(|p| p.to_vec()).collect();
+        nodes.push(match pruning {
+            Pruning::Magnitude => weight_candidates(&rows, &kinds, offset),
+            Pruning::Structured => unit_candidates(&rows, &kinds, offset),
+        });
+        offset += rows.len();
+    }
+    nodes
+}
+
+// zeroes the given fraction of the weights or units of the model and returns
+// the mask that keeps them at zero; what is already zero ranks lowest, so
+// pruning again to a higher fraction keeps everything pruned before
+pub fn prune(model: &mut Sequential, pruning: Pruning, scope: Scope, fraction: f32) -> Mask {
+    assert!((0.0..=1.0).contains(&fraction));
+    let nodes = candidates(model, pruning);
+    let groups = match scope {
+        Scope::Layer => nodes,
+        Scope::Global => vec![nodes.concat()],
+    };
+    let mut mask = Mask::full(model);
+    for mut group in groups {
+        group.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let count = (group.len() as f32 * fraction).round() as usize;
+        for (_, entries) in &group[..count] {
+            for (r, c) in entries {
+                mask.rows[*r][*c] = false;
+            }
+        }
+    }
+    mask.apply(model);
+    mask
+}
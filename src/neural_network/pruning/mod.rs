@@ -0,0 +1,12 @@
+pub mod layers;
+pub mod mask;
+pub mod model;
+pub mod schedule;
+pub mod sparse;
+mod test;
+
+pub use layers::*;
+pub use mask::*;
+pub use model::*;
+pub use schedule::*;
+pub use sparse::*;
@@ -0,0 +1,45 @@
+use crate::neural_network::convolutional::Conv2D;
+use crate::neural_network::linear::linear::Linear;
+use crate::neural_network::sequential::{Node, Sequential};
+
+use super::{SparseConv2D, SparseLinear};
+
+// swaps every Linear and Conv2D for its sparse version when no more than
+// max_density of its weights are left; the sparse kernels skip the zeros but
+// pay for the indirection, so they only win well below half
+pub fn sparsify_model(model: Sequential, max_density: f32) -> Sequential {
+    let nodes = model
+        .nodes
+        .into_iter()
+        .map(|node| match node {
+            Node::Dense(layer) => {
+                let sparse = layer
+                    .as_ref()
+                    .as_any()
+                    .downcast_ref::<Linear>()
+                    .map(SparseLinear::new);
+                match sparse {
+                    Some(sparse) if sparse.weights.density() <= max_density => {
+                        Node::Dense(Box::new(sparse))
+                    }
+                    _ => Node::Dense(layer),
+                }
+            }
+            Node::Spatial(layer) => {
+                let sparse = layer
+                    .as_ref()
+                    .as_any()
+                    .downcast_ref::<Conv2D>()
+                    .map(SparseConv2D::new);
+                match sparse {
+                    Some(sparse) if sparse.density() <= max_density => {
+                        Node::Spatial(Box::new(sparse))
+                    }
+                    _ => Node::Spatial(layer),
+                }
+            }
+            other => other,
+        })
+        .collect();
+    Sequential::new(nodes)
+}
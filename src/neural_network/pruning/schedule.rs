@@ -0,0 +1,57 @@
+use crate::neural_network::optimiser::Optimizer;
+use crate::neural_network::sequential::{Sequential, Tensor};
+use crate::neural_network::training::Trainer;
+
+use super::{prune, Mask, Pruning, Scope};
+
+// prunes a little at a time with some epochs of fine tuning after each stage,
+// so the rest of the network can make up for what was removed; stage s of n
+// prunes to target * (1 - (1 - s / n)^3), which takes the most early on while
+// there is the most to spare
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PruningSchedule {
+    pub pruning: Pruning,
+    pub scope: Scope,
+    pub target: f32,
+    pub stages: usize,
+    pub epochs: usize,
+}
+
+impl PruningSchedule {
+    pub fn new(pruning: Pruning, scope: Scope, target: f32, stages: usize, epochs: usize) -> Self {
+        assert!((0.0..=1.0).contains(&target) && stages > 0);
+        PruningSchedule {
+            pruning,
+            scope,
+            target,
+            stages,
+            epochs,
+        }
+    }
+
+    // the fraction pruned by the end of stage, counting from 1
+    pub fn fraction(&self, stage: usize) -> f32 {
+        let left = 1.0 - stage.min(self.stages) as f32 / self.stages as f32;
+        self.target * (1.0 - left.powi(3))
+    }
+
+    // runs every stage, fine tuning through the trainer, which carries on
+    // counting epochs from wherever it was; early stopping ends the fine tuning
+    // of every later stage too, so it is best left off; returns the final mask
+    pub fn run(
+        &self,
+        model: &mut Sequential,
+        trainer: &mut Trainer,
+        optimizer: &mut dyn Optimizer,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+    ) -> Mask {
+        let mut mask = Mask::full(model);
+        for stage in 1..=self.stages {
+            mask = prune(model, self.pruning, self.scope, self.fraction(stage));
+            trainer.epochs = trainer.epoch + self.epochs;
+            trainer.fit(model, optimizer, inputs, targets, &mut [&mut mask]);
+        }
+        mask
+    }
+}
@@ -0,0 +1,129 @@
+// compressed sparse rows; the entries of row r are values[offsets[r]..offsets[r + 1]]
+// in the columns at the same places in indices, in increasing column order
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub offsets: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub values: Vec<f32>,
+}
+
+impl SparseMatrix {
+    // keeps everything that isn't exactly zero
+    pub fn from_dense(dense: &[Vec<f32>]) -> Self {
+        let mut offsets = vec![0];
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+        for row in dense {
+            for (j, x) in row.iter().enumerate() {
+                if *x != 0.0 {
+                    indices.push(j);
+                    values.push(*x);
+                }
+            }
+            offsets.push(values.len());
+        }
+        SparseMatrix {
+            rows: dense.len(),
+            cols: dense.first().map_or(0, |row| row.len()),
+            offsets,
+            indices,
+            values,
+        }
+    }
+
+    pub fn to_dense(&self) -> Vec<Vec<f32>> {
+        let mut dense = vec![vec![0.0; self.cols]; self.rows];
+        for (r, row) in dense.iter_mut().enumerate() {
+            for k in self.offsets[r]..self.offsets[r + 1] {
+                row[self.indices[k]] = self.values[k];
+            }
+        }
+        dense
+    }
+
+    pub fn nonzeros(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn density(&self) -> f32 {
+        self.nonzeros() as f32 / (self.rows * self.cols).max(1) as f32
+    }
+
+    pub fn matvec(&self, x: &[f32]) -> Vec<f32> {
+        (0..self.rows)
+            .map(|r| {
+                (self.offsets[r]..self.offsets[r + 1])
+                    .map(|k| self.values[k] * x[self.indices[k]])
+                    .sum()
+            })
+            .collect()
+    }
+
+    // the gradient of every stored value from the error of matvec with x
+    pub fn outer_values(&self, error: &[f32], x: &[f32]) -> Vec<f32> {
+        let mut gradients = vec![0.0; self.nonzeros()];
+        for (r, e) in error.iter().enumerate() {
+            for k in self.offsets[r]..self.offsets[r + 1] {
+                gradients[k] = e * x[self.indices[k]];
+            }
+        }
+        gradients
+    }
+
+    pub fn transpose_matvec(&self, x: &[f32]) -> Vec<f32> {
+        let mut result = vec![0.0; self.cols];
+        for (r, xr) in x.iter().enumerate() {
+            for k in self.offsets[r]..self.offsets[r + 1] {
+                result[self.indices[k]] += self.values[k] * xr;
+            }
+        }
+        result
+    }
+
+    // times a dense matrix given as rows, such as the columns out of im2col
+    pub fn matmul(&self, dense: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        (0..self.rows)
+            .map(|r| {
+                let mut row = vec![0.0; dense[0].len()];
+                for k in self.offsets[r]..self.offsets[r + 1] {
+                    let w = self.values[k];
+                    for (y, x) in row.iter_mut().zip(dense[self.indices[k]].iter()) {
+                        *y += w * x;
+                    }
+                }
+                row
+            })
+            .collect()
+    }
+
+    pub fn transpose_matmul(&self, dense: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        let mut result = vec![vec![0.0; dense[0].len()]; self.cols];
+        for (r, row) in dense.iter().enumerate() {
+            for k in self.offsets[r]..self.offsets[r + 1] {
+                let w = self.values[k];
+                for (y, x) in result[self.indices[k]].iter_mut().zip(row.iter()) {
+                    *y += w * x;
+                }
+            }
+        }
+        result
+    }
+
+    // the gradient of every stored value from the error of the product with
+    // dense, dot products of error rows with the rows of dense they met
+    pub fn value_gradients(&self, dense: &[Vec<f32>], error: &[Vec<f32>]) -> Vec<f32> {
+        let mut gradients = vec![0.0; self.nonzeros()];
+        for (r, e) in error.iter().enumerate() {
+            for k in self.offsets[r]..self.offsets[r + 1] {
+                gradients[k] = e
+                    .iter()
+                    .zip(dense[self.indices[k]].iter())
+                    .map(|(a, b)| a * b)
+                    .sum();
+            }
+        }
+        gradients
+    }
+}
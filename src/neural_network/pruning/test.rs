@@ -0,0 +1,286 @@
+#[cfg(test)]
+mod test_pruning {
+    use crate::neural_network::{
+        convolutional::{Conv2D, Padding},
+        core::{cross_entropy, Layer, Layer2D},
+        fixtures::{bars, classifier, largest_difference, volume, wave},
+        linear::linear::Linear,
+        optimiser::Adam,
+        pruning::{
+            prune, sparsify_model, sparsity, Mask, Pruning, PruningSchedule, Scope, SparseConv2D,
+            SparseLinear, SparseMatrix,
+        },
+        sequential::Sequential,
+        training::{accuracy, Trainer},
+    };
+
+    // every third entry zeroed
+    fn thinned(rows: usize, cols: usize, offset: usize) -> Vec<Vec<f32>> {
+        (0..rows)
+            .map(|r| {
+                (0..cols)
+                    .map(|c| {
+                        let k = r * cols + c;
+                        if k.is_multiple_of(3) {
+                            0.0
+                        } else {
+                            wave(offset + k)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn dense_matmul(a: &[Vec<f32>], b: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        a.iter()
+            .map(|row| {
+                (0..b[0].len())
+                    .map(|j| row.iter().zip(b.iter()).map(|(x, r)| x * r[j]).sum())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sparse_matrix() {
+        let dense = thinned(4, 5, 0);
+        let sparse = SparseMatrix::from_dense(&dense);
+        assert_eq!(sparse.to_dense(), dense);
+        assert_eq!(sparse.nonzeros(), 13);
+        assert_eq!(sparse.density(), 13.0 / 20.0);
+        assert_eq!(sparse.offsets, vec![0, 3, 6, 10, 13]);
+
+        let x: Vec<f32> = (0..5).map(|k| wave(k + 40)).collect();
+        let y: Vec<f32> = (0..4).map(|k| wave(k + 60)).collect();
+        let columns = |v: &[f32]| v.iter().map(|a| vec![*a]).collect::<Vec<_>>();
+        let expected: Vec<f32> = dense_matmul(&dense, &columns(&x)).concat();
+        assert!(largest_difference(&sparse.matvec(&x), &expected) < 1e-6);
+        let transposed: Vec<Vec<f32>> = (0..5)
+            .map(|j| dense.iter().map(|row| row[j]).collect())
+            .collect();
+        let expected: Vec<f32> = dense_matmul(&transposed, &columns(&y)).concat();
+        assert!(largest_difference(&sparse.transpose_matvec(&y), &expected) < 1e-6);
+
+        let other = volume((1, 5, 3), 80).remove(0);
+        let error = volume((1, 4, 3), 120).remove(0);
+        let product = sparse.matmul(&other);
+        let expected = dense_matmul(&dense, &other);
+        assert!(largest_difference(&product.concat(), &expected.concat()) < 1e-6);
+        let product = sparse.transpose_matmul(&error);
+        let expected = dense_matmul(&transposed, &error);
+        assert!(largest_difference(&product.concat(), &expected.concat()) < 1e-6);
+
+        // only the stored values get a gradient, in the order they are stored
+        let gradients = dense_matmul(
+            &error,
+            &(0..3)
+                .map(|j| other.iter().map(|row| row[j]).collect())
+                .collect::<Vec<_>>(),
+        );
+        let kept: Vec<f32> = gradients
+            .iter()
+            .flatten()
+            .zip(dense.iter().flatten())
+            .filter(|(_, w)| **w != 0.0)
+            .map(|(g, _)| *g)
+            .collect();
+        assert!(largest_difference(&sparse.value_gradients(&other, &error), &kept) < 1e-5);
+    }
+
+    #[test]
+    fn test_sparse_linear() {
+        let mut linear = Linear::new(7, 4);
+        linear.weights = thinned(4, 7, 0);
+        linear.bias = (0..4).map(|k| wave(k + 30)).collect();
+        let mut sparse = SparseLinear::new(&linear);
+        let input: Vec<f32> = (0..7).map(|k| wave(k + 50)).collect();
+        let error: Vec<f32> = (0..4).map(|k| wave(k + 70)).collect();
+        assert!(largest_difference(&sparse.forward(&input), &linear.forward(&input)) < 1e-6);
+
+        let (input_error, gradients) = sparse.gradients(&input, &error);
+        let (expected_error, expected) = linear.gradients(&input, &error);
+        assert!(largest_difference(&input_error, &expected_error) < 1e-6);
+        assert_eq!(sparse.parameters().len(), gradients.len());
+        let kept: Vec<f32> = expected[..4]
+            .iter()
+            .flatten()
+            .zip(linear.weights.iter().flatten())
+            .filter(|(_, w)| **w != 0.0)
+            .map(|(g, _)| *g)
+            .collect();
+        assert_eq!(gradients[0], kept);
+        assert_eq!(gradients[1], expected[4]);
+    }
+
+    #[test]
+    fn test_sparse_conv2d() {
+        let dim = (4, 7, 6);
+        let input = volume(dim, 0);
+        let convolutions = [
+            Conv2D::new(dim, 3, (3, 3), (1, 1), (1, 1)),
+            Conv2D::grouped(dim, 4, (3, 2), Padding::Same, (2, 1), (1, 2), 2),
+        ];
+        for mut conv in convolutions {
+            let shape = (conv.weights[0].len(), conv.kernel.0, conv.kernel.1);
+            for (o, filters) in conv.weights.iter_mut().enumerate() {
+                *filters = volume(shape, 200 * o);
+                for (k, w) in filters.iter_mut().flatten().flatten().enumerate() {
+                    if (k + o).is_multiple_of(3) {
+                        *w = 0.0;
+                    }
+                }
+            }
+            conv.bias = (0..conv.bias.len()).map(|k| 0.1 * k as f32).collect();
+            let error = volume(conv.dim_out, 500);
+            let mut sparse = SparseConv2D::new(&conv);
+            assert_eq!(sparse.dim_out(), conv.dim_out);
+            assert!((sparse.density() - 2.0 / 3.0).abs() < 0.05);
+
+            let flat =
+                |x: Vec<Vec<Vec<f32>>>| -> Vec<f32> { x.into_iter().flatten().flatten().collect() };
+            let expected = flat(conv.forward(&input));
+            assert!(largest_difference(&flat(sparse.forward(&input)), &expected) < 1e-5);
+
+            let (input_error, gradients) = sparse.gradients(&input, &error);
+            let (expected_error, expected) = conv.gradients(&input, &error);
+            assert!(largest_difference(&flat(input_error), &flat(expected_error)) < 1e-5);
+            assert_eq!(sparse.parameters().len(), gradients.len());
+            let weights: Vec<f32> = conv
+                .weights
+                .iter()
+                .flatten()
+                .flatten()
+                .flatten()
+                .copied()
+                .collect();
+            let kept: Vec<f32> = expected[..expected.len() - 1]
+                .iter()
+                .flatten()
+                .zip(weights.iter())
+                .filter(|(_, w)| **w != 0.0)
+                .map(|(g, _)| *g)
+                .collect();
+            assert!(largest_difference(&gradients[..conv.groups].concat(), &kept) < 1e-4);
+            assert_eq!(gradients[conv.groups], expected[expected.len() - 1]);
+        }
+    }
+
+    fn weights(model: &mut Sequential) -> Vec<Vec<f32>> {
+        model.parameters().iter().map(|p| p.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_magnitude_pruning() {
+        let mut model = classifier(16);
+        // conv has 4 * 3 weight rows then a bias, the hidden layer 16 rows and
+        // a bias, the output 2 rows and a bias
+        let mut layer = classifier(16);
+        let mask = prune(&mut layer, Pruning::Magnitude, Scope::Layer, 0.5);
+        let pruned = weights(&mut layer);
+        let zeros = |rows: &[Vec<f32>]| rows.iter().flatten().filter(|x| **x == 0.0).count();
+        assert_eq!(zeros(&pruned[..12]), 18);
+        assert_eq!(zeros(&pruned[13..29]), 16 * 36 / 2);
+        assert_eq!(zeros(&pruned[30..32]), 16);
+        // biases are kept, and nothing kept is smaller than anything pruned
+        assert_eq!(mask.rows[12], vec![true; 4]);
+        assert_eq!(mask.pruned(), 18 + 288 + 16);
+        let original = weights(&mut model);
+        let largest_pruned = original[..12]
+            .iter()
+            .flatten()
+            .zip(mask.rows[..12].iter().flatten())
+            .filter(|(_, keep)| !**keep)
+            .fold(0.0f32, |acc, (w, _)| acc.max(w.abs()));
+        assert!(pruned[..12]
+            .iter()
+            .flatten()
+            .filter(|w| **w != 0.0)
+            .all(|w| w.abs() >= largest_pruned));
+
+        let global = prune(&mut model, Pruning::Magnitude, Scope::Global, 0.5);
+        assert!((sparsity(&mut model) - 0.5).abs() < 0.01);
+        assert_ne!(global, mask);
+        // pruning further keeps what was pruned before
+        let further = prune(&mut model, Pruning::Magnitude, Scope::Global, 0.8);
+        for (before, after) in global
+            .rows
+            .iter()
+            .flatten()
+            .zip(further.rows.iter().flatten())
+        {
+            assert!(*before || !*after);
+        }
+        assert!((sparsity(&mut model) - 0.8).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_structured_pruning() {
+        let mut model = classifier(16);
+        let mask = prune(&mut model, Pruning::Structured, Scope::Layer, 0.5);
+        let pruned = weights(&mut model);
+        // two whole conv channels and eight hidden neurons with their biases,
+        // and one of the two outputs
+        let dead = |rows: &[Vec<f32>]| rows.iter().all(|row| row.iter().all(|x| *x == 0.0));
+        let channels: Vec<bool> = (0..4)
+            .map(|o| dead(&pruned[(3 * o)..(3 * o + 3)]))
+            .collect();
+        assert_eq!(channels.iter().filter(|d| **d).count(), 2);
+        for (o, d) in channels.iter().enumerate() {
+            assert_eq!(mask.rows[12][o], !d);
+        }
+        let neurons = (13..29).filter(|r| dead(&pruned[*r..(*r + 1)])).count();
+        assert_eq!(neurons, 8);
+        assert_eq!(mask.rows[29].iter().filter(|k| !**k).count(), 8);
+        assert_eq!(mask.rows[32].iter().filter(|k| !**k).count(), 1);
+
+        let mut full = classifier(16);
+        assert_eq!(Mask::full(&mut full).pruned(), 0);
+        prune(&mut full, Pruning::Structured, Scope::Global, 0.25);
+        // 22 units in all
+        let units = weights(&mut full)
+            .iter()
+            .filter(|row| row.iter().all(|x| *x == 0.0))
+            .count();
+        assert!(units >= 5);
+    }
+
+    #[test]
+    fn test_prune_and_finetune() {
+        let schedule = PruningSchedule::new(Pruning::Magnitude, Scope::Layer, 0.8, 4, 3);
+        assert_eq!(schedule.fraction(0), 0.0);
+        assert!(schedule.fraction(1) > 0.8 / 4.0);
+        assert_eq!(schedule.fraction(4), 0.8);
+        assert_eq!(schedule.fraction(9), 0.8);
+
+        let (inputs, targets) = bars(48, 0);
+        let mut model = classifier(16);
+        let mut trainer = Trainer::new(cross_entropy, 10, 8, 0);
+        let mut optimizer = Adam::new(0.01, (0.9, 0.999), 1e-8);
+        trainer.fit(&mut model, &mut optimizer, &inputs, &targets, &mut []);
+        let mask = schedule.run(&mut model, &mut trainer, &mut optimizer, &inputs, &targets);
+        assert_eq!(trainer.epoch, 10 + 4 * 3);
+        // the optimiser's momentum didn't bring any pruned weight back
+        for (p, keep) in model.parameters().iter().zip(mask.rows.iter()) {
+            assert!(p.iter().zip(keep.iter()).all(|(x, k)| *k || *x == 0.0));
+        }
+        assert!((sparsity(&mut model) - 0.8).abs() < 0.01);
+
+        let (test_inputs, test_targets) = bars(40, 5000);
+        let outputs = |model: &Sequential| -> Vec<Vec<f32>> {
+            test_inputs
+                .iter()
+                .map(|input| model.forward(input).flat().to_vec())
+                .collect()
+        };
+        let expected = outputs(&model);
+        assert!(accuracy(&expected, &test_targets) >= 0.9);
+
+        // the conv and both linear layers go sparse with the same outputs
+        let parameters = model.parameters().len();
+        let mut sparse = sparsify_model(model, 0.5);
+        assert_eq!(sparse.parameters().len(), parameters - 12 - 16 - 2 + 3);
+        let actual = outputs(&sparse);
+        assert!(largest_difference(&actual.concat(), &expected.concat()) < 1e-5);
+    }
+}
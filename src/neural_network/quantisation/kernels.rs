@@ -0,0 +1,63 @@
+use super::Quantiser;
+
+// the bias in the units of the product of input and weight scales, so it adds
+// straight onto the i32 sums
+pub fn quantise_bias(bias: &[f32], input: Quantiser, weights: &[Quantiser]) -> Vec<i32> {
+    bias.iter()
+        .zip(weights.iter())
+        .map(|(b, w)| (b / (input.scale * w.scale)).round() as i32)
+        .collect()
+}
+
+// int8 weights times the int8 input less its zero point, summed in i32 with
+// the bias
+pub fn integer_matvec(
+    weights: &[Vec<i8>],
+    input: &[i8],
+    zero_point: i32,
+    bias: &[i32],
+) -> Vec<i32> {
+    weights
+        .iter()
+        .zip(bias.iter())
+        .map(|(row, b)| {
+            row.iter()
+                .zip(input.iter())
+                .map(|(w, x)| *w as i32 * (*x as i32 - zero_point))
+                .sum::<i32>()
+                + b
+        })
+        .collect()
+}
+
+// the same for every column of an im2col matrix, [weight column][position]
+pub fn integer_matmul(
+    weights: &[Vec<i8>],
+    columns: &[Vec<i8>],
+    zero_point: i32,
+    bias: &[i32],
+) -> Vec<Vec<i32>> {
+    weights
+        .iter()
+        .zip(bias.iter())
+        .map(|(row, b)| {
+            let mut sums = vec![*b; columns[0].len()];
+            for (w, column) in row.iter().zip(columns.iter()) {
+                let w = *w as i32;
+                if w == 0 {
+                    continue;
+                }
+                for (sum, x) in sums.iter_mut().zip(column.iter()) {
+                    *sum += w * (*x as i32 - zero_point);
+                }
+            }
+            sums
+        })
+        .collect()
+}
+
+// the i32 sums back to reals, one weight quantiser per row
+pub fn rescale(sums: &[i32], input: Quantiser, weights: Quantiser) -> Vec<f32> {
+    let scale = input.scale * weights.scale;
+    sums.iter().map(|x| *x as f32 * scale).collect()
+}
@@ -0,0 +1,155 @@
+use crate::neural_network::convolutional::{im2col, Conv2D};
+use crate::neural_network::core::{stack, transpose_transform, Layer, Layer2D};
+use crate::neural_network::linear::linear::Linear;
+
+use super::{
+    dequantise_rows, integer_matmul, integer_matvec, quantise_bias, quantise_rows, rescale,
+    Granularity, Quantiser,
+};
+
+// Linear with int8 weights and input; the input is quantised with the range
+// found in calibration, multiplied and summed in i32 and scaled back to f32;
+// it has no parameters to train and backward goes straight through the
+// dequantised weights
+pub struct QuantisedLinear {
+    pub dim_in: usize,
+    pub dim_out: usize,
+    pub input: Quantiser,
+    pub weights: Vec<Vec<i8>>,
+    pub scales: Vec<Quantiser>,
+    pub bias: Vec<i32>,
+}
+
+impl QuantisedLinear {
+    pub fn new(linear: &Linear, input: Quantiser, granularity: Granularity) -> Self {
+        let (weights, scales) = quantise_rows(&linear.weights, granularity);
+        QuantisedLinear {
+            dim_in: linear.dim_in,
+            dim_out: linear.dim_out,
+            input,
+            bias: quantise_bias(&linear.bias, input, &scales),
+            weights,
+            scales,
+        }
+    }
+}
+
+impl Layer for QuantisedLinear {
+    fn dim_in(&self) -> usize {
+        self.dim_in
+    }
+    fn dim_out(&self) -> usize {
+        self.dim_out
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        let sums = integer_matvec(
+            &self.weights,
+            &self.input.quantise_all(input),
+            self.input.zero_point,
+            &self.bias,
+        );
+        sums.iter()
+            .zip(self.scales.iter())
+            .map(|(sum, scale)| *sum as f32 * self.input.scale * scale.scale)
+            .collect()
+    }
+
+    fn backward(
+        &self,
+        _input: &[f32],
+        error: &[f32],
+    ) -> (Vec<f32>, Option<Vec<Vec<f32>>>, Option<Vec<f32>>) {
+        let weights = dequantise_rows(&self.weights, &self.scales);
+        (transpose_transform(&weights, error), None, None)
+    }
+}
+
+// Conv2D through im2col with int8 columns and filters; conv keeps the layout
+// and the dequantised filters for backward
+pub struct QuantisedConv2D {
+    pub conv: Conv2D,
+    pub input: Quantiser,
+    // [out_channel][in_channel / groups * rows * cols]
+    pub weights: Vec<Vec<i8>>,
+    pub scales: Vec<Quantiser>,
+    pub bias: Vec<i32>,
+}
+
+impl QuantisedConv2D {
+    pub fn new(conv: &Conv2D, input: Quantiser, granularity: Granularity) -> Self {
+        let rows: Vec<Vec<f32>> = conv
+            .weights
+            .iter()
+            .map(|filters| filters.iter().flatten().flatten().copied().collect())
+            .collect();
+        let (weights, scales) = quantise_rows(&rows, granularity);
+        let mut conv = conv.clone();
+        for (filters, row) in conv
+            .weights
+            .iter_mut()
+            .zip(dequantise_rows(&weights, &scales))
+        {
+            for (x, y) in filters.iter_mut().flatten().flatten().zip(row) {
+                *x = y;
+            }
+        }
+        QuantisedConv2D {
+            bias: quantise_bias(&conv.bias, input, &scales),
+            conv,
+            input,
+            weights,
+            scales,
+        }
+    }
+}
+
+impl Layer2D for QuantisedConv2D {
+    fn dim_in(&self) -> (usize, usize, usize) {
+        self.conv.dim_in
+    }
+    fn dim_out(&self) -> (usize, usize, usize) {
+        self.conv.dim_out
+    }
+
+    fn forward(&self, input: &[Vec<Vec<f32>>]) -> Vec<Vec<Vec<f32>>> {
+        let (in_channels, out_channels) = self.conv.group_channels();
+        let input = self.conv.pad_input(input);
+        let size = (self.conv.dim_out.1, self.conv.dim_out.2);
+        (0..self.conv.groups)
+            .flat_map(|g| {
+                let columns: Vec<Vec<i8>> = im2col(
+                    &input[(g * in_channels)..((g + 1) * in_channels)],
+                    self.conv.kernel,
+                    (0, 0),
+                    self.conv.stride,
+                    self.conv.dilation,
+                )
+                .iter()
+                .map(|column| self.input.quantise_all(column))
+                .collect();
+                let outputs = (g * out_channels)..((g + 1) * out_channels);
+                integer_matmul(
+                    &self.weights[outputs.clone()],
+                    &columns,
+                    self.input.zero_point,
+                    &self.bias[outputs],
+                )
+            })
+            .zip(self.scales.iter())
+            .map(|(sums, scale)| stack(&rescale(&sums, self.input, *scale), size))
+            .collect()
+    }
+
+    fn back(
+        &self,
+        input: &[Vec<Vec<f32>>],
+        error: &[Vec<Vec<f32>>],
+    ) -> (
+        Vec<Vec<Vec<f32>>>,
+        Option<Vec<Vec<Vec<f32>>>>,
+        Option<Vec<f32>>,
+    ) {
+        (self.conv.back(input, error).0, None, None)
+    }
+}
@@ -0,0 +1,10 @@
+pub mod kernels;
+pub mod layers;
+pub mod model;
+pub mod quantiser;
+mod test;
+
+pub use kernels::*;
+pub use layers::*;
+pub use model::*;
+pub use quantiser::*;
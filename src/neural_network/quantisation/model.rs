@@ -0,0 +1,99 @@
+use crate::metrics::argmax;
+use crate::neural_network::convolutional::Conv2D;
+use crate::neural_network::linear::linear::Linear;
+use crate::neural_network::sequential::{Node, Sequential, Tensor};
+use crate::neural_network::training::Metric;
+
+use super::{Granularity, QuantisedConv2D, QuantisedLinear, Quantiser};
+
+fn values(tensor: &Tensor) -> Vec<f32> {
+    match tensor {
+        Tensor::Flat(x) => x.clone(),
+        Tensor::Spatial(x) => x.iter().flatten().flatten().copied().collect(),
+    }
+}
+
+// swaps every Linear and Conv2D for its integer version, calibrating the input
+// quantiser of each on the range its input takes over the calibration samples
+// in the f32 model
+pub fn quantise_model(
+    model: Sequential,
+    calibration: &[Tensor],
+    granularity: Granularity,
+) -> Sequential {
+    let activations: Vec<Vec<Tensor>> = calibration.iter().map(|x| model.activations(x)).collect();
+    let range = |i: usize| {
+        let seen: Vec<f32> = activations.iter().flat_map(|a| values(&a[i])).collect();
+        Quantiser::calibrate(&seen)
+    };
+    let nodes = model
+        .nodes
+        .into_iter()
+        .enumerate()
+        .map(|(i, node)| match node {
+            Node::Dense(layer) => match layer.as_ref().as_any().downcast_ref::<Linear>() {
+                Some(linear) => Node::Dense(Box::new(QuantisedLinear::new(
+                    linear,
+                    range(i),
+                    granularity,
+                ))),
+                None => Node::Dense(layer),
+            },
+            Node::Spatial(layer) => match layer.as_ref().as_any().downcast_ref::<Conv2D>() {
+                Some(conv) => {
+                    Node::Spatial(Box::new(QuantisedConv2D::new(conv, range(i), granularity)))
+                }
+                None => Node::Spatial(layer),
+            },
+            other => other,
+        })
+        .collect();
+    Sequential::new(nodes)
+}
+
+// how the quantised model compares with the original on a labelled set:
+// the metric of each, how much it dropped, how often both pick the same
+// largest output and the mean absolute difference of the outputs
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantisationReport {
+    pub reference: f32,
+    pub quantised: f32,
+    pub drop: f32,
+    pub agreement: f32,
+    pub output_error: f32,
+}
+
+pub fn compare(
+    reference: &Sequential,
+    quantised: &Sequential,
+    inputs: &[Tensor],
+    targets: &[Vec<f32>],
+    metric: Metric,
+) -> QuantisationReport {
+    let run = |model: &Sequential| -> Vec<Vec<f32>> {
+        inputs
+            .iter()
+            .map(|input| model.forward(input).flat().to_vec())
+            .collect()
+    };
+    let (expected, actual) = (run(reference), run(quantised));
+    let agreeing = expected
+        .iter()
+        .zip(actual.iter())
+        .filter(|(x, y)| argmax(x) == argmax(y))
+        .count();
+    let differences: Vec<f32> = expected
+        .iter()
+        .flatten()
+        .zip(actual.iter().flatten())
+        .map(|(x, y)| (x - y).abs())
+        .collect();
+    let (reference, quantised) = (metric(&expected, targets), metric(&actual, targets));
+    QuantisationReport {
+        reference,
+        quantised,
+        drop: reference - quantised,
+        agreement: agreeing as f32 / inputs.len() as f32,
+        output_error: differences.iter().sum::<f32>() / differences.len() as f32,
+    }
+}
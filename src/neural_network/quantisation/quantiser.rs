@@ -0,0 +1,91 @@
+// maps reals to int8 as round(x / scale) + zero_point, clamped to the range
+// of i8, and back as (q - zero_point) * scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantiser {
+    pub scale: f32,
+    pub zero_point: i32,
+}
+
+impl Quantiser {
+    pub fn new(scale: f32, zero_point: i32) -> Self {
+        assert!(scale > 0.0 && (-128..=127).contains(&zero_point));
+        Quantiser { scale, zero_point }
+    }
+
+    // zero at zero over [-limit, limit], leaving out -128 so negating stays in range
+    pub fn symmetric(limit: f32) -> Self {
+        Quantiser::new(if limit > 0.0 { limit / 127.0 } else { 1.0 }, 0)
+    }
+
+    // the range is widened to take in zero, so zero padding and the zeros out
+    // of a ReLU are represented exactly
+    pub fn asymmetric(min: f32, max: f32) -> Self {
+        let (min, max) = (min.min(0.0), max.max(0.0));
+        if max == min {
+            return Quantiser::new(1.0, 0);
+        }
+        let scale = (max - min) / 255.0;
+        let zero_point = (-128.0 - min / scale).round().clamp(-128.0, 127.0) as i32;
+        Quantiser::new(scale, zero_point)
+    }
+
+    // asymmetric over the smallest and largest of everything seen
+    pub fn calibrate<'a>(values: impl IntoIterator<Item = &'a f32>) -> Self {
+        let (min, max) = values
+            .into_iter()
+            .fold((0.0f32, 0.0f32), |(lo, hi), x| (lo.min(*x), hi.max(*x)));
+        Quantiser::asymmetric(min, max)
+    }
+
+    pub fn quantise(&self, x: f32) -> i8 {
+        ((x / self.scale).round() as i32 + self.zero_point).clamp(-128, 127) as i8
+    }
+
+    pub fn dequantise(&self, q: i8) -> f32 {
+        (q as i32 - self.zero_point) as f32 * self.scale
+    }
+
+    pub fn quantise_all(&self, x: &[f32]) -> Vec<i8> {
+        x.iter().map(|y| self.quantise(*y)).collect()
+    }
+}
+
+// one weight scale for the whole tensor, or one per output row or channel,
+// which keeps small filters from losing their precision to large ones
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Granularity {
+    PerTensor,
+    PerChannel,
+}
+
+fn largest(rows: &[Vec<f32>]) -> f32 {
+    rows.iter().flatten().fold(0.0, |acc, x| acc.max(x.abs()))
+}
+
+// symmetric int8 weights and the quantiser of each row, shared by every row
+// per tensor
+pub fn quantise_rows(
+    rows: &[Vec<f32>],
+    granularity: Granularity,
+) -> (Vec<Vec<i8>>, Vec<Quantiser>) {
+    let quantisers: Vec<Quantiser> = match granularity {
+        Granularity::PerTensor => vec![Quantiser::symmetric(largest(rows)); rows.len()],
+        Granularity::PerChannel => rows
+            .iter()
+            .map(|row| Quantiser::symmetric(largest(std::slice::from_ref(row))))
+            .collect(),
+    };
+    let quantised = rows
+        .iter()
+        .zip(quantisers.iter())
+        .map(|(row, q)| q.quantise_all(row))
+        .collect();
+    (quantised, quantisers)
+}
+
+pub fn dequantise_rows(rows: &[Vec<i8>], quantisers: &[Quantiser]) -> Vec<Vec<f32>> {
+    rows.iter()
+        .zip(quantisers.iter())
+        .map(|(row, q)| row.iter().map(|x| q.dequantise(*x)).collect())
+        .collect()
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod test_quantisation {
+    use crate::neural_network::{
+        convolutional::{Conv2D, Padding},
+        core::{cross_entropy, Layer, Layer2D},
+        fixtures::{bars, classifier, largest_difference, volume, wave},
+        linear::linear::Linear,
+        optimiser::Adam,
+        quantisation::{
+            compare, integer_matmul, integer_matvec, quantise_model, quantise_rows, Granularity,
+            QuantisedConv2D, QuantisedLinear, Quantiser,
+        },
+        training::{accuracy, Trainer},
+    };
+
+    #[test]
+    fn test_quantiser() {
+        let q = Quantiser::asymmetric(-1.0, 3.0);
+        assert_eq!(q.scale, 4.0 / 255.0);
+        assert_eq!(q.dequantise(q.quantise(0.0)), 0.0);
+        for x in [-1.0, -0.3, 0.5, 2.9, 3.0] {
+            assert!((q.dequantise(q.quantise(x)) - x).abs() <= q.scale / 2.0 + 1e-6);
+        }
+        // outside the range clamps
+        assert_eq!(q.quantise(10.0), 127);
+        assert_eq!(q.quantise(-10.0), -128);
+
+        // positive values alone still cover zero
+        let relu = Quantiser::calibrate(&[0.5, 2.0, 1.0]);
+        assert_eq!((relu.zero_point, relu.quantise(0.0)), (-128, -128));
+        let symmetric = Quantiser::symmetric(2.54);
+        assert_eq!(
+            (symmetric.quantise(2.54), symmetric.quantise(-2.54)),
+            (127, -127)
+        );
+    }
+
+    #[test]
+    fn test_quantise_rows() {
+        let rows = vec![vec![1.0, -0.5, 0.25], vec![0.01, -0.02, 0.005]];
+        let (tensor, shared) = quantise_rows(&rows, Granularity::PerTensor);
+        let (channel, own) = quantise_rows(&rows, Granularity::PerChannel);
+        assert_eq!(shared[0], shared[1]);
+        assert_eq!(tensor[0], channel[0]);
+        assert_eq!(channel[1][1], -127);
+        // the small row all but vanishes sharing the large row's scale
+        let error = |q: &[i8], s: Quantiser| {
+            let row: Vec<f32> = q.iter().map(|x| s.dequantise(*x)).collect();
+            largest_difference(&row, &rows[1])
+        };
+        assert!(error(&channel[1], own[1]) < 1e-4);
+        assert!(error(&tensor[1], shared[1]) > 10.0 * error(&channel[1], own[1]));
+    }
+
+    #[test]
+    fn test_integer_kernels() {
+        let weights = vec![vec![3i8, -2, 127], vec![-128, 0, 5]];
+        let input = [10i8, -20, 1];
+        let expected: Vec<i32> = weights
+            .iter()
+            .zip([7, -9])
+            .map(|(row, b)| {
+                row.iter()
+                    .zip(input.iter())
+                    .map(|(w, x)| *w as i32 * (*x as i32 + 4))
+                    .sum::<i32>()
+                    + b
+            })
+            .collect();
+        assert_eq!(integer_matvec(&weights, &input, -4, &[7, -9]), expected);
+        let columns: Vec<Vec<i8>> = input.iter().map(|x| vec![*x, 0]).collect();
+        let product = integer_matmul(&weights, &columns, -4, &[7, -9]);
+        assert_eq!(product[0][0], expected[0]);
+        assert_eq!(product[1][0], expected[1]);
+        assert_eq!(product[0][1], 4 * (3 - 2 + 127) + 7);
+    }
+
+    #[test]
+    fn test_quantised_linear() {
+        let mut linear = Linear::new(12, 5);
+        for (r, row) in linear.weights.iter_mut().enumerate() {
+            for (j, w) in row.iter_mut().enumerate() {
+                *w = wave(r * 12 + j) * (r + 1) as f32 * 0.2;
+            }
+        }
+        linear.bias = (0..5).map(|k| wave(k + 100)).collect();
+        let input: Vec<f32> = (0..12).map(|k| wave(k + 50) * 2.0).collect();
+        let expected = linear.forward(&input);
+        for granularity in [Granularity::PerTensor, Granularity::PerChannel] {
+            let quantised =
+                QuantisedLinear::new(&linear, Quantiser::calibrate(&input), granularity);
+            assert!(largest_difference(&quantised.forward(&input), &expected) < 0.05);
+        }
+        let mut quantised = QuantisedLinear::new(
+            &linear,
+            Quantiser::calibrate(&input),
+            Granularity::PerChannel,
+        );
+        assert!(quantised.parameters().is_empty());
+        let (input_error, gradients) = quantised.gradients(&input, &[1.0; 5]);
+        assert!(gradients.is_empty());
+        assert!(largest_difference(&input_error, &linear.backward(&input, &[1.0; 5]).0) < 0.01);
+    }
+
+    #[test]
+    fn test_quantised_conv2d() {
+        let dim = (4, 7, 6);
+        let input = volume(dim, 0);
+        let seen: Vec<f32> = input.iter().flatten().flatten().copied().collect();
+        let convolutions = [
+            Conv2D::new(dim, 3, (3, 3), (1, 1), (1, 1)),
+            Conv2D::grouped(dim, 4, (3, 2), Padding::Same, (2, 1), (1, 2), 2),
+        ];
+        for mut conv in convolutions {
+            let shape = (conv.weights[0].len(), conv.kernel.0, conv.kernel.1);
+            for (o, filters) in conv.weights.iter_mut().enumerate() {
+                *filters = volume(shape, 200 * o);
+            }
+            conv.bias = (0..conv.bias.len()).map(|k| 0.1 * k as f32).collect();
+            let expected: Vec<f32> = conv
+                .forward(&input)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect();
+            let quantised =
+                QuantisedConv2D::new(&conv, Quantiser::calibrate(&seen), Granularity::PerChannel);
+            assert_eq!(quantised.dim_out(), conv.dim_out);
+            let actual: Vec<f32> = quantised
+                .forward(&input)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect();
+            assert!(largest_difference(&actual, &expected) < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_quantise_model() {
+        let (inputs, targets) = bars(48, 0);
+        let mut model = classifier(8);
+        Trainer::new(cross_entropy, 10, 8, 0).fit(
+            &mut model,
+            &mut Adam::new(0.01, (0.9, 0.999), 1e-8),
+            &inputs,
+            &targets,
+            &mut [],
+        );
+        let (test_inputs, test_targets) = bars(40, 5000);
+
+        // the trained weights, since model is consumed by quantising it
+        let mut reference = classifier(8);
+        for (p, q) in reference.parameters().into_iter().zip(model.parameters()) {
+            p.copy_from_slice(q);
+        }
+        let mut quantised = quantise_model(model, &inputs[..16], Granularity::PerChannel);
+        // conv and both linear layers are now integer and have no parameters
+        assert!(quantised.parameters().is_empty());
+        let report = compare(
+            &reference,
+            &quantised,
+            &test_inputs,
+            &test_targets,
+            accuracy,
+        );
+        assert!(report.reference >= 0.9);
+        assert!(report.drop <= 0.05 && report.agreement >= 0.95);
+        assert!(report.output_error < 0.02);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::neural_network::core::{Recurrent, RecurrentGradients};
+
+// runs one layer over the sequence and another over the reversed sequence,
+// the outputs and every state vector are the concatenation [forward, backward]
+#[derive(Clone)]
+pub struct Bidirectional<R: Recurrent> {
+    pub forward_layer: R,
+    pub backward_layer: R,
+}
+
+impl<R: Recurrent> Bidirectional<R> {
+    pub fn new(forward_layer: R, backward_layer: R) -> Self {
+        assert_eq!(forward_layer.dim_in(), backward_layer.dim_in());
+        assert_eq!(forward_layer.dim_hidden(), backward_layer.dim_hidden());
+        Bidirectional {
+            forward_layer,
+            backward_layer,
+        }
+    }
+
+    fn split(&self, vectors: &[Vec<f32>]) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let n = self.forward_layer.dim_hidden();
+        vectors
+            .iter()
+            .map(|x| (x[..n].to_vec(), x[n..].to_vec()))
+            .unzip()
+    }
+
+    // one error vector per step, a single error vector belongs to forward_last,
+    // so its forward half goes to the last step and its backward half to the
+    // first, where the backward layer has read the whole sequence
+    fn step_errors(&self, error: &[Vec<f32>], len: usize) -> Vec<Vec<f32>> {
+        if error.len() == len {
+            return error.to_vec();
+        }
+        assert_eq!(error.len(), 1);
+        assert!(len > 0, "a last step error needs a non-empty sequence");
+        let n = self.forward_layer.dim_hidden();
+        let mut errors = vec![vec![0.0; 2 * n]; len];
+        errors[len - 1][..n].copy_from_slice(&error[0][..n]);
+        errors[0][n..].copy_from_slice(&error[0][n..]);
+        errors
+    }
+}
+
+fn join(a: &[Vec<f32>], b: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| [x.clone(), y.clone()].concat())
+        .collect()
+}
+
+fn reversed(x: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    x.iter().rev().cloned().collect()
+}
+
+impl<R: Recurrent> Recurrent for Bidirectional<R> {
+    fn dim_in(&self) -> usize {
+        self.forward_layer.dim_in()
+    }
+    fn dim_hidden(&self) -> usize {
+        2 * self.forward_layer.dim_hidden()
+    }
+    fn initial_state(&self) -> Vec<Vec<f32>> {
+        join(
+            &self.forward_layer.initial_state(),
+            &self.backward_layer.initial_state(),
+        )
+    }
+
+    fn forward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let (forward_state, backward_state) = match state {
+            Some(s) => {
+                let (f, b) = self.split(s);
+                (Some(f), Some(b))
+            }
+            None => (None, None),
+        };
+        let (forward_out, forward_final) =
+            self.forward_layer.forward(input, forward_state.as_deref());
+        let (backward_out, backward_final) = self
+            .backward_layer
+            .forward(&reversed(input), backward_state.as_deref());
+        (
+            join(&forward_out, &reversed(&backward_out)),
+            join(&forward_final, &backward_final),
+        )
+    }
+
+    // each direction's hidden state after reading the whole sequence
+    fn forward_last(&self, input: &[Vec<f32>], state: Option<&[Vec<f32>]>) -> Vec<f32> {
+        let (output, _) = self.forward(input, state);
+        let n = self.forward_layer.dim_hidden();
+        [
+            output[output.len() - 1][..n].to_vec(),
+            output[0][n..].to_vec(),
+        ]
+        .concat()
+    }
+
+    fn backward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+        error: &[Vec<f32>],
+        state_error: Option<&[Vec<f32>]>,
+        truncation: Option<usize>,
+    ) -> RecurrentGradients {
+        let (forward_state, backward_state) = match state {
+            Some(s) => {
+                let (f, b) = self.split(s);
+                (Some(f), Some(b))
+            }
+            None => (None, None),
+        };
+        let (forward_state_error, backward_state_error) = match state_error {
+            Some(s) => {
+                let (f, b) = self.split(s);
+                (Some(f), Some(b))
+            }
+            None => (None, None),
+        };
+        let (forward_error, backward_error) = self.split(&self.step_errors(error, input.len()));
+
+        let forward = self.forward_layer.backward(
+            input,
+            forward_state.as_deref(),
+            &forward_error,
+            forward_state_error.as_deref(),
+            truncation,
+        );
+        let backward = self.backward_layer.backward(
+            &reversed(input),
+            backward_state.as_deref(),
+            &reversed(&backward_error),
+            backward_state_error.as_deref(),
+            truncation,
+        );
+
+        let input_error = forward
+            .0
+            .iter()
+            .zip(reversed(&backward.0).iter())
+            .map(|(x, y)| x.iter().zip(y.iter()).map(|(i, j)| i + j).collect())
+            .collect();
+        (
+            input_error,
+            [forward.1, backward.1].concat(),
+            [forward.2, backward.2].concat(),
+            join(&forward.3, &backward.3),
+        )
+    }
+}
@@ -0,0 +1,157 @@
+use crate::neural_network::core::{
+    add, linear_transform, matmul, transpose_transform, Activation, Derivative, Function,
+    Recurrent, RecurrentGradients,
+};
+
+use super::{accumulate, add_to, initialise, step_errors, truncated};
+
+// gates are stacked in the order reset, update, new; the reset gate is applied
+// after the hidden projection so W_hh and its bias are kept separate from W_ih
+#[derive(Clone)]
+pub struct Gru {
+    pub dim_in: usize,
+    pub dim_hidden: usize,
+    pub w_ih: Vec<Vec<f32>>,
+    pub w_hh: Vec<Vec<f32>>,
+    pub b_ih: Vec<f32>,
+    pub b_hh: Vec<f32>,
+}
+
+struct GruStep {
+    pre_r: Vec<f32>,
+    pre_z: Vec<f32>,
+    pre_n: Vec<f32>,
+    r: Vec<f32>,
+    z: Vec<f32>,
+    n: Vec<f32>,
+    // W_hn h + b_hn, before the reset gate
+    hn: Vec<f32>,
+}
+
+impl Gru {
+    pub fn new(dim_in: usize, dim_hidden: usize) -> Self {
+        Gru {
+            dim_in,
+            dim_hidden,
+            w_ih: initialise(3 * dim_hidden, dim_in),
+            w_hh: initialise(3 * dim_hidden, dim_hidden),
+            b_ih: vec![0.0; 3 * dim_hidden],
+            b_hh: vec![0.0; 3 * dim_hidden],
+        }
+    }
+
+    fn run(&self, input: &[Vec<f32>], state: Option<&[Vec<f32>]>) -> (Vec<GruStep>, Vec<Vec<f32>>) {
+        let n = self.dim_hidden;
+        let projected = matmul(input, &self.w_ih, None, true);
+        let mut hidden = vec![match state {
+            Some(s) => s[0].clone(),
+            None => vec![0.0; n],
+        }];
+        let mut steps = Vec::new();
+        for x in projected {
+            let h = &hidden[hidden.len() - 1];
+            let gi = add(&x, &self.b_ih);
+            let gh = add(&linear_transform(&self.w_hh, h, None), &self.b_hh);
+            let pre_r = add(&gi[..n], &gh[..n]);
+            let pre_z = add(&gi[n..(2 * n)], &gh[n..(2 * n)]);
+            let r = Function::Sigmoid.activation()(&pre_r);
+            let z = Function::Sigmoid.activation()(&pre_z);
+            let hn = gh[(2 * n)..].to_vec();
+            let pre_n: Vec<f32> = (0..n).map(|k| gi[2 * n + k] + r[k] * hn[k]).collect();
+            let new = Function::Tanh.activation()(&pre_n);
+            let next = (0..n)
+                .map(|k| (1.0 - z[k]) * new[k] + z[k] * h[k])
+                .collect();
+            hidden.push(next);
+            steps.push(GruStep {
+                pre_r,
+                pre_z,
+                pre_n,
+                r,
+                z,
+                n: new,
+                hn,
+            });
+        }
+        (steps, hidden)
+    }
+}
+
+impl Recurrent for Gru {
+    fn dim_in(&self) -> usize {
+        self.dim_in
+    }
+    fn dim_hidden(&self) -> usize {
+        self.dim_hidden
+    }
+    fn initial_state(&self) -> Vec<Vec<f32>> {
+        vec![vec![0.0; self.dim_hidden]]
+    }
+
+    fn forward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let (_, mut hidden) = self.run(input, state);
+        let last = hidden[hidden.len() - 1].clone();
+        hidden.remove(0);
+        (hidden, vec![last])
+    }
+
+    fn backward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+        error: &[Vec<f32>],
+        state_error: Option<&[Vec<f32>]>,
+        truncation: Option<usize>,
+    ) -> RecurrentGradients {
+        let n = self.dim_hidden;
+        let (steps, hidden) = self.run(input, state);
+        let errors = step_errors(error, input.len(), n);
+        let mut w_ih_error = vec![vec![0.0; self.dim_in]; 3 * n];
+        let mut w_hh_error = vec![vec![0.0; n]; 3 * n];
+        let mut b_ih_error = vec![0.0; 3 * n];
+        let mut b_hh_error = vec![0.0; 3 * n];
+        let mut input_error = vec![Vec::new(); input.len()];
+        let mut h_error = match state_error {
+            Some(s) => s[0].clone(),
+            None => vec![0.0; n],
+        };
+
+        for t in (0..input.len()).rev() {
+            let step = &steps[t];
+            let h = &hidden[t];
+            let dh = add(&h_error, &errors[t]);
+            let dn: Vec<f32> = (0..n).map(|k| dh[k] * (1.0 - step.z[k])).collect();
+            let dz: Vec<f32> = (0..n).map(|k| dh[k] * (h[k] - step.n[k])).collect();
+            let dn_pre = Function::Tanh.derivative()(&step.pre_n, &dn);
+            let dr: Vec<f32> = (0..n).map(|k| dn_pre[k] * step.hn[k]).collect();
+            let dr_pre = Function::Sigmoid.derivative()(&step.pre_r, &dr);
+            let dz_pre = Function::Sigmoid.derivative()(&step.pre_z, &dz);
+            let dhn: Vec<f32> = (0..n).map(|k| dn_pre[k] * step.r[k]).collect();
+
+            let gi = [dr_pre.clone(), dz_pre.clone(), dn_pre].concat();
+            let gh = [dr_pre, dz_pre, dhn].concat();
+            accumulate(&mut w_ih_error, &gi, &input[t]);
+            accumulate(&mut w_hh_error, &gh, h);
+            add_to(&mut b_ih_error, &gi);
+            add_to(&mut b_hh_error, &gh);
+            input_error[t] = transpose_transform(&self.w_ih, &gi);
+            h_error = if truncated(t, truncation) {
+                vec![0.0; n]
+            } else {
+                let direct: Vec<f32> = (0..n).map(|k| dh[k] * step.z[k]).collect();
+                add(&direct, &transpose_transform(&self.w_hh, &gh))
+            };
+        }
+
+        (
+            input_error,
+            vec![w_ih_error, w_hh_error],
+            vec![b_ih_error, b_hh_error],
+            vec![h_error],
+        )
+    }
+}
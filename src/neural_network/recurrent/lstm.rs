@@ -0,0 +1,175 @@
+use crate::neural_network::core::{
+    add, linear_transform, matmul, transpose_transform, Activation, Derivative, Function,
+    Recurrent, RecurrentGradients,
+};
+
+use super::{accumulate, add_to, initialise, step_errors, truncated};
+
+// gates are stacked in the order input, forget, cell, output
+#[derive(Clone)]
+pub struct Lstm {
+    pub dim_in: usize,
+    pub dim_hidden: usize,
+    pub w_ih: Vec<Vec<f32>>,
+    pub w_hh: Vec<Vec<f32>>,
+    pub b: Vec<f32>,
+}
+
+struct LstmStep {
+    pre: Vec<f32>,
+    i: Vec<f32>,
+    f: Vec<f32>,
+    g: Vec<f32>,
+    o: Vec<f32>,
+    c: Vec<f32>,
+}
+
+impl Lstm {
+    pub fn new(dim_in: usize, dim_hidden: usize) -> Self {
+        let mut b = vec![0.0; 4 * dim_hidden];
+        // start with the forget gate open so early gradients reach the cell state
+        for x in b[dim_hidden..(2 * dim_hidden)].iter_mut() {
+            *x = 1.0;
+        }
+        Lstm {
+            dim_in,
+            dim_hidden,
+            w_ih: initialise(4 * dim_hidden, dim_in),
+            w_hh: initialise(4 * dim_hidden, dim_hidden),
+            b,
+        }
+    }
+
+    // per step gate values and the (h, c) states, starting with the initial state
+    fn run(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<LstmStep>, Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let n = self.dim_hidden;
+        let projected = matmul(input, &self.w_ih, None, true);
+        let (mut hidden, mut cell) = match state {
+            Some(s) => (vec![s[0].clone()], vec![s[1].clone()]),
+            None => (vec![vec![0.0; n]], vec![vec![0.0; n]]),
+        };
+        let mut steps = Vec::new();
+        for x in projected {
+            let pre = add(
+                &add(
+                    &x,
+                    &linear_transform(&self.w_hh, &hidden[hidden.len() - 1], None),
+                ),
+                &self.b,
+            );
+            let i = Function::Sigmoid.activation()(&pre[..n]);
+            let f = Function::Sigmoid.activation()(&pre[n..(2 * n)]);
+            let g = Function::Tanh.activation()(&pre[(2 * n)..(3 * n)]);
+            let o = Function::Sigmoid.activation()(&pre[(3 * n)..]);
+            let c: Vec<f32> = (0..n)
+                .map(|k| f[k] * cell[cell.len() - 1][k] + i[k] * g[k])
+                .collect();
+            let h = o
+                .iter()
+                .zip(Function::Tanh.activation()(&c).iter())
+                .map(|(x, y)| x * y)
+                .collect();
+            hidden.push(h);
+            cell.push(c.clone());
+            steps.push(LstmStep { pre, i, f, g, o, c });
+        }
+        (steps, hidden, cell)
+    }
+}
+
+impl Recurrent for Lstm {
+    fn dim_in(&self) -> usize {
+        self.dim_in
+    }
+    fn dim_hidden(&self) -> usize {
+        self.dim_hidden
+    }
+    fn initial_state(&self) -> Vec<Vec<f32>> {
+        vec![vec![0.0; self.dim_hidden], vec![0.0; self.dim_hidden]]
+    }
+
+    fn forward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let (_, mut hidden, cell) = self.run(input, state);
+        let last = vec![
+            hidden[hidden.len() - 1].clone(),
+            cell[cell.len() - 1].clone(),
+        ];
+        hidden.remove(0);
+        (hidden, last)
+    }
+
+    fn backward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+        error: &[Vec<f32>],
+        state_error: Option<&[Vec<f32>]>,
+        truncation: Option<usize>,
+    ) -> RecurrentGradients {
+        let n = self.dim_hidden;
+        let (steps, hidden, cell) = self.run(input, state);
+        let errors = step_errors(error, input.len(), n);
+        let mut w_ih_error = vec![vec![0.0; self.dim_in]; 4 * n];
+        let mut w_hh_error = vec![vec![0.0; n]; 4 * n];
+        let mut b_error = vec![0.0; 4 * n];
+        let mut input_error = vec![Vec::new(); input.len()];
+        let (mut h_error, mut c_error) = match state_error {
+            Some(s) => (s[0].clone(), s[1].clone()),
+            None => (vec![0.0; n], vec![0.0; n]),
+        };
+
+        for t in (0..input.len()).rev() {
+            let step = &steps[t];
+            let dh = add(&h_error, &errors[t]);
+            let tanh_c = Function::Tanh.activation()(&step.c);
+            let dc = add(
+                &c_error,
+                &Function::Tanh.derivative()(
+                    &step.c,
+                    &dh.iter()
+                        .zip(step.o.iter())
+                        .map(|(x, y)| x * y)
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            let di: Vec<f32> = dc.iter().zip(step.g.iter()).map(|(x, y)| x * y).collect();
+            let df: Vec<f32> = dc.iter().zip(cell[t].iter()).map(|(x, y)| x * y).collect();
+            let dg: Vec<f32> = dc.iter().zip(step.i.iter()).map(|(x, y)| x * y).collect();
+            let d_o: Vec<f32> = dh.iter().zip(tanh_c.iter()).map(|(x, y)| x * y).collect();
+            let dz = [
+                Function::Sigmoid.derivative()(&step.pre[..n], &di),
+                Function::Sigmoid.derivative()(&step.pre[n..(2 * n)], &df),
+                Function::Tanh.derivative()(&step.pre[(2 * n)..(3 * n)], &dg),
+                Function::Sigmoid.derivative()(&step.pre[(3 * n)..], &d_o),
+            ]
+            .concat();
+
+            accumulate(&mut w_ih_error, &dz, &input[t]);
+            accumulate(&mut w_hh_error, &dz, &hidden[t]);
+            add_to(&mut b_error, &dz);
+            input_error[t] = transpose_transform(&self.w_ih, &dz);
+            if truncated(t, truncation) {
+                h_error = vec![0.0; n];
+                c_error = vec![0.0; n];
+            } else {
+                h_error = transpose_transform(&self.w_hh, &dz);
+                c_error = dc.iter().zip(step.f.iter()).map(|(x, y)| x * y).collect();
+            }
+        }
+
+        (
+            input_error,
+            vec![w_ih_error, w_hh_error],
+            vec![b_error],
+            vec![h_error, c_error],
+        )
+    }
+}
@@ -0,0 +1,55 @@
+pub mod bidirectional;
+pub mod gru;
+pub mod lstm;
+pub mod packed;
+pub mod rnn;
+pub mod stacked;
+mod test;
+
+pub use bidirectional::*;
+pub use gru::*;
+pub use lstm::*;
+pub use packed::*;
+pub use rnn::*;
+pub use stacked::*;
+
+use crate::neural_network::core::he_initialise;
+
+fn initialise(rows: usize, cols: usize) -> Vec<Vec<f32>> {
+    (0..rows).map(|_| he_initialise(cols, cols)).collect()
+}
+
+// one error vector per step, a single error vector belongs to the last step
+fn step_errors(error: &[Vec<f32>], len: usize, dim: usize) -> Vec<Vec<f32>> {
+    if error.len() == len {
+        return error.to_vec();
+    }
+    assert_eq!(error.len(), 1);
+    assert!(len > 0, "a last step error needs a non-empty sequence");
+    let mut errors = vec![vec![0.0; dim]; len];
+    errors[len - 1] = error[0].clone();
+    errors
+}
+
+// the gradient through the state is dropped at the start of every chunk of k steps
+fn truncated(t: usize, truncation: Option<usize>) -> bool {
+    match truncation {
+        Some(k) => t.is_multiple_of(k),
+        None => false,
+    }
+}
+
+// acc += a b^T
+fn accumulate(acc: &mut [Vec<f32>], a: &[f32], b: &[f32]) {
+    for (row, ai) in acc.iter_mut().zip(a.iter()) {
+        for (r, bj) in row.iter_mut().zip(b.iter()) {
+            *r += ai * bj;
+        }
+    }
+}
+
+fn add_to(acc: &mut [f32], a: &[f32]) {
+    for (x, y) in acc.iter_mut().zip(a.iter()) {
+        *x += y;
+    }
+}
@@ -0,0 +1,143 @@
+use crate::neural_network::core::Recurrent;
+
+use super::{add_to, step_errors};
+
+// a batch of sequences of different lengths, zero padded to the longest;
+// the recurrent layers only ever see the first length steps of each sequence
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedSequence {
+    pub data: Vec<Vec<Vec<f32>>>,
+    pub lengths: Vec<usize>,
+}
+
+impl PackedSequence {
+    pub fn new(sequences: &[Vec<Vec<f32>>]) -> Self {
+        let max_len = sequences.iter().map(|x| x.len()).max().unwrap_or(0);
+        let data = sequences
+            .iter()
+            .map(|x| {
+                let dim = x.first().map_or(0, |y| y.len());
+                let mut padded = x.clone();
+                padded.resize(max_len, vec![0.0; dim]);
+                padded
+            })
+            .collect();
+        PackedSequence {
+            data,
+            lengths: sequences.iter().map(|x| x.len()).collect(),
+        }
+    }
+
+    // 1.0 for real steps and 0.0 for padding
+    pub fn mask(&self) -> Vec<Vec<f32>> {
+        let max_len = self.data.first().map_or(0, |x| x.len());
+        self.lengths
+            .iter()
+            .map(|len| {
+                (0..max_len)
+                    .map(|t| if t < *len { 1.0 } else { 0.0 })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn unpack(&self) -> Vec<Vec<Vec<f32>>> {
+        self.data
+            .iter()
+            .zip(self.lengths.iter())
+            .map(|(x, len)| x[..*len].to_vec())
+            .collect()
+    }
+}
+
+// runs the layer over every sequence in the batch, the outputs are padded
+// with zeros and the final state of each sequence is taken at its own length
+pub fn forward_packed<R: Recurrent>(
+    layer: &R,
+    input: &PackedSequence,
+    state: Option<&[Vec<Vec<f32>>]>,
+) -> (PackedSequence, Vec<Vec<Vec<f32>>>) {
+    let (outputs, states): (Vec<_>, Vec<_>) = input
+        .unpack()
+        .iter()
+        .enumerate()
+        .map(|(i, x)| layer.forward(x, state.map(|s| &s[i][..])))
+        .unzip();
+    (pad_like(&outputs, input, layer.dim_hidden()), states)
+}
+
+// (input error, weight gradients, bias gradients, initial state error of each sequence)
+pub type PackedGradients = (
+    PackedSequence,
+    Vec<Vec<Vec<f32>>>,
+    Vec<Vec<f32>>,
+    Vec<Vec<Vec<f32>>>,
+);
+
+// the error is padded like the output, anything past a sequence's length is ignored;
+// weight and bias gradients are summed over the batch
+pub fn backward_packed<R: Recurrent>(
+    layer: &R,
+    input: &PackedSequence,
+    state: Option<&[Vec<Vec<f32>>]>,
+    error: &PackedSequence,
+    state_error: Option<&[Vec<Vec<f32>>]>,
+    truncation: Option<usize>,
+) -> PackedGradients {
+    let mut input_errors = Vec::new();
+    let mut weight_error: Vec<Vec<Vec<f32>>> = Vec::new();
+    let mut bias_error: Vec<Vec<f32>> = Vec::new();
+    let mut state_errors = Vec::new();
+    for (i, x) in input.unpack().iter().enumerate() {
+        let len = input.lengths[i];
+        let sequence_error = if error.data[i].len() == 1 {
+            step_errors(&error.data[i], len, layer.dim_hidden())
+        } else {
+            error.data[i][..len].to_vec()
+        };
+        let (x_error, weights, bias, s_error) = layer.backward(
+            x,
+            state.map(|s| &s[i][..]),
+            &sequence_error,
+            state_error.map(|s| &s[i][..]),
+            truncation,
+        );
+        if weight_error.is_empty() {
+            weight_error = weights;
+            bias_error = bias;
+        } else {
+            for (acc, w) in weight_error.iter_mut().zip(weights.iter()) {
+                for (acc_row, row) in acc.iter_mut().zip(w.iter()) {
+                    add_to(acc_row, row);
+                }
+            }
+            for (acc, b) in bias_error.iter_mut().zip(bias.iter()) {
+                add_to(acc, b);
+            }
+        }
+        input_errors.push(x_error);
+        state_errors.push(s_error);
+    }
+    (
+        pad_like(&input_errors, input, layer.dim_in()),
+        weight_error,
+        bias_error,
+        state_errors,
+    )
+}
+
+// pad to the same shape as another batch
+fn pad_like(sequences: &[Vec<Vec<f32>>], other: &PackedSequence, dim: usize) -> PackedSequence {
+    let max_len = other.data.first().map_or(0, |x| x.len());
+    PackedSequence {
+        data: sequences
+            .iter()
+            .map(|x| {
+                let mut padded = x.clone();
+                padded.resize(max_len, vec![0.0; dim]);
+                padded
+            })
+            .collect(),
+        lengths: other.lengths.clone(),
+    }
+}
@@ -0,0 +1,118 @@
+use crate::neural_network::core::{
+    add, linear_transform, matmul, transpose_transform, Activation, Derivative, Function,
+    Recurrent, RecurrentGradients,
+};
+
+use super::{accumulate, add_to, initialise, step_errors, truncated};
+
+// h_t = tanh(W_ih x_t + W_hh h_{t-1} + b)
+#[derive(Clone)]
+pub struct Rnn {
+    pub dim_in: usize,
+    pub dim_hidden: usize,
+    pub w_ih: Vec<Vec<f32>>,
+    pub w_hh: Vec<Vec<f32>>,
+    pub b: Vec<f32>,
+}
+
+impl Rnn {
+    pub fn new(dim_in: usize, dim_hidden: usize) -> Self {
+        Rnn {
+            dim_in,
+            dim_hidden,
+            w_ih: initialise(dim_hidden, dim_in),
+            w_hh: initialise(dim_hidden, dim_hidden),
+            b: vec![0.0; dim_hidden],
+        }
+    }
+
+    // pre-activations per step and the hidden states, starting with the initial state
+    fn run(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let projected = matmul(input, &self.w_ih, None, true);
+        let mut hidden = vec![match state {
+            Some(s) => s[0].clone(),
+            None => vec![0.0; self.dim_hidden],
+        }];
+        let mut pre = Vec::new();
+        for x in projected {
+            let z = add(
+                &add(
+                    &x,
+                    &linear_transform(&self.w_hh, &hidden[hidden.len() - 1], None),
+                ),
+                &self.b,
+            );
+            hidden.push(Function::Tanh.activation()(&z));
+            pre.push(z);
+        }
+        (pre, hidden)
+    }
+}
+
+impl Recurrent for Rnn {
+    fn dim_in(&self) -> usize {
+        self.dim_in
+    }
+    fn dim_hidden(&self) -> usize {
+        self.dim_hidden
+    }
+    fn initial_state(&self) -> Vec<Vec<f32>> {
+        vec![vec![0.0; self.dim_hidden]]
+    }
+
+    fn forward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let (_, mut hidden) = self.run(input, state);
+        let last = hidden[hidden.len() - 1].clone();
+        hidden.remove(0);
+        (hidden, vec![last])
+    }
+
+    fn backward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+        error: &[Vec<f32>],
+        state_error: Option<&[Vec<f32>]>,
+        truncation: Option<usize>,
+    ) -> RecurrentGradients {
+        let (pre, hidden) = self.run(input, state);
+        let errors = step_errors(error, input.len(), self.dim_hidden);
+        let mut w_ih_error = vec![vec![0.0; self.dim_in]; self.dim_hidden];
+        let mut w_hh_error = vec![vec![0.0; self.dim_hidden]; self.dim_hidden];
+        let mut b_error = vec![0.0; self.dim_hidden];
+        let mut input_error = vec![Vec::new(); input.len()];
+        let mut h_error = match state_error {
+            Some(s) => s[0].clone(),
+            None => vec![0.0; self.dim_hidden],
+        };
+
+        for t in (0..input.len()).rev() {
+            let dh = add(&h_error, &errors[t]);
+            let dz = Function::Tanh.derivative()(&pre[t], &dh);
+            accumulate(&mut w_ih_error, &dz, &input[t]);
+            accumulate(&mut w_hh_error, &dz, &hidden[t]);
+            add_to(&mut b_error, &dz);
+            input_error[t] = transpose_transform(&self.w_ih, &dz);
+            h_error = if truncated(t, truncation) {
+                vec![0.0; self.dim_hidden]
+            } else {
+                transpose_transform(&self.w_hh, &dz)
+            };
+        }
+
+        (
+            input_error,
+            vec![w_ih_error, w_hh_error],
+            vec![b_error],
+            vec![h_error],
+        )
+    }
+}
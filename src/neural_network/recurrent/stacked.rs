@@ -0,0 +1,177 @@
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::neural_network::core::{Recurrent, RecurrentGradients};
+
+// each layer reads the hidden states of the layer below, the state is the
+// states of every layer in order; dropout is applied between layers while
+// training, with masks drawn from the seed so forward and backward agree, and
+// next_batch draws the seed for the masks of the next batch from rng
+#[derive(Clone)]
+pub struct Stacked<R: Recurrent> {
+    pub layers: Vec<R>,
+    pub dropout: f32,
+    pub training: bool,
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+}
+
+impl<R: Recurrent> Stacked<R> {
+    pub fn new(layers: Vec<R>, dropout: f32) -> Self {
+        for i in 1..layers.len() {
+            assert_eq!(layers[i].dim_in(), layers[i - 1].dim_hidden());
+        }
+        let mut rng = ChaCha8Rng::from_rng(thread_rng()).unwrap();
+        Stacked {
+            layers,
+            dropout,
+            training: true,
+            seed: rng.gen(),
+            rng,
+        }
+    }
+
+    // fresh masks for every batch, called once per batch before its forward
+    // and backward passes
+    pub fn next_batch(&mut self) {
+        self.seed = self.rng.gen();
+    }
+
+    // inverted dropout masks for the output of every layer but the last
+    fn masks(&self, len: usize) -> Vec<Vec<Vec<f32>>> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let keep = 1.0 - self.dropout;
+        self.layers[..(self.layers.len() - 1)]
+            .iter()
+            .map(|layer| {
+                (0..len)
+                    .map(|_| {
+                        (0..layer.dim_hidden())
+                            .map(|_| {
+                                if !self.training || self.dropout == 0.0 {
+                                    1.0
+                                } else if rng.gen_range(0.0..1.0) < keep {
+                                    1.0 / keep
+                                } else {
+                                    0.0
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn split(&self, state: Option<&[Vec<f32>]>) -> Vec<Option<Vec<Vec<f32>>>> {
+        let mut start = 0;
+        self.layers
+            .iter()
+            .map(|layer| {
+                let size = layer.initial_state().len();
+                start += size;
+                state.map(|s| s[(start - size)..start].to_vec())
+            })
+            .collect()
+    }
+
+    // the input to every layer, the final output and the final states
+    fn run(&self, input: &[Vec<f32>], state: Option<&[Vec<f32>]>) -> StackActivations {
+        let masks = self.masks(input.len());
+        let states = self.split(state);
+        let mut inputs = vec![input.to_vec()];
+        let mut final_state = Vec::new();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let (mut output, layer_state) = layer.forward(&inputs[i], states[i].as_deref());
+            final_state.extend(layer_state);
+            if i < masks.len() {
+                output = apply(&output, &masks[i]);
+            }
+            inputs.push(output);
+        }
+        let output = inputs.pop().unwrap();
+        (inputs, output, final_state)
+    }
+}
+
+type StackActivations = (Vec<Vec<Vec<f32>>>, Vec<Vec<f32>>, Vec<Vec<f32>>);
+
+fn apply(x: &[Vec<f32>], mask: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    x.iter()
+        .zip(mask.iter())
+        .map(|(a, b)| a.iter().zip(b.iter()).map(|(i, j)| i * j).collect())
+        .collect()
+}
+
+impl<R: Recurrent> Recurrent for Stacked<R> {
+    fn dim_in(&self) -> usize {
+        self.layers[0].dim_in()
+    }
+    fn dim_hidden(&self) -> usize {
+        self.layers[self.layers.len() - 1].dim_hidden()
+    }
+    fn initial_state(&self) -> Vec<Vec<f32>> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.initial_state())
+            .collect()
+    }
+
+    fn forward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+    ) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
+        let (_, output, final_state) = self.run(input, state);
+        (output, final_state)
+    }
+
+    // forward_last of the top layer, which also takes a single error vector
+    fn forward_last(&self, input: &[Vec<f32>], state: Option<&[Vec<f32>]>) -> Vec<f32> {
+        let (inputs, _, _) = self.run(input, state);
+        let top = self.layers.len() - 1;
+        self.layers[top].forward_last(&inputs[top], self.split(state)[top].as_deref())
+    }
+
+    fn backward(
+        &self,
+        input: &[Vec<f32>],
+        state: Option<&[Vec<f32>]>,
+        error: &[Vec<f32>],
+        state_error: Option<&[Vec<f32>]>,
+        truncation: Option<usize>,
+    ) -> RecurrentGradients {
+        let masks = self.masks(input.len());
+        let states = self.split(state);
+        let state_errors = self.split(state_error);
+        let (inputs, _, _) = self.run(input, state);
+
+        let mut error = error.to_vec();
+        let mut weight_error = Vec::new();
+        let mut bias_error = Vec::new();
+        let mut initial_error = Vec::new();
+        for i in (0..self.layers.len()).rev() {
+            if i < masks.len() {
+                error = apply(&error, &masks[i]);
+            }
+            let (input_error, weights, bias, layer_state_error) = self.layers[i].backward(
+                &inputs[i],
+                states[i].as_deref(),
+                &error,
+                state_errors[i].as_deref(),
+                truncation,
+            );
+            weight_error.insert(0, weights);
+            bias_error.insert(0, bias);
+            initial_error.insert(0, layer_state_error);
+            error = input_error;
+        }
+
+        (
+            error,
+            weight_error.concat(),
+            bias_error.concat(),
+            initial_error.concat(),
+        )
+    }
+}
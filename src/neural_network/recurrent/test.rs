@@ -0,0 +1,456 @@
+#[cfg(test)]
+mod test_recurrent {
+    use crate::neural_network::{
+        core::Recurrent,
+        recurrent::{
+            backward_packed, forward_packed, Bidirectional, Gru, Lstm, PackedSequence, Rnn, Stacked,
+        },
+    };
+
+    const EPSILON: f32 = 1e-3;
+    const TOLERANCE: f32 = 1e-2;
+
+    fn sequence(len: usize, dim: usize, offset: f32) -> Vec<Vec<f32>> {
+        (0..len)
+            .map(|t| {
+                (0..dim)
+                    .map(|k| (offset + (t * dim + k) as f32 * 0.7).sin())
+                    .collect()
+            })
+            .collect()
+    }
+
+    // a weighted sum of every hidden state and the final state, so that the
+    // error for each of them is known
+    fn loss<R: Recurrent>(
+        layer: &R,
+        input: &[Vec<f32>],
+        state: &[Vec<f32>],
+        error: &[Vec<f32>],
+        state_error: &[Vec<f32>],
+    ) -> f32 {
+        let (outputs, final_state) = layer.forward(input, Some(state));
+        let dot = |a: &[Vec<f32>], b: &[Vec<f32>]| -> f32 {
+            a.iter()
+                .zip(b.iter())
+                .map(|(x, y)| x.iter().zip(y.iter()).map(|(i, j)| i * j).sum::<f32>())
+                .sum()
+        };
+        dot(&outputs, error) + dot(&final_state, state_error)
+    }
+
+    fn check_gradients<R: Recurrent>(
+        layer: &R,
+        weights: fn(&mut R) -> Vec<&mut Vec<Vec<f32>>>,
+        biases: fn(&mut R) -> Vec<&mut Vec<f32>>,
+    ) {
+        let input = sequence(5, layer.dim_in(), 0.0);
+        let error = sequence(5, layer.dim_hidden(), 1.0);
+        let state: Vec<Vec<f32>> = layer
+            .initial_state()
+            .iter()
+            .map(|s| s.iter().enumerate().map(|(k, _)| 0.1 * k as f32).collect())
+            .collect();
+        let state_error: Vec<Vec<f32>> = state
+            .iter()
+            .enumerate()
+            .map(|(p, s)| (0..s.len()).map(|k| (2.0 + (p + k) as f32).cos()).collect())
+            .collect();
+        let (input_error, weight_error, bias_error, initial_error) =
+            layer.backward(&input, Some(&state), &error, Some(&state_error), None);
+        let numeric = |l: &R, x: &[Vec<f32>], s: &[Vec<f32>]| loss(l, x, s, &error, &state_error);
+
+        for t in 0..input.len() {
+            for k in 0..input[t].len() {
+                let mut up = input.clone();
+                let mut down = input.clone();
+                up[t][k] += EPSILON;
+                down[t][k] -= EPSILON;
+                let expected =
+                    (numeric(layer, &up, &state) - numeric(layer, &down, &state)) / (2.0 * EPSILON);
+                assert!((expected - input_error[t][k]).abs() < TOLERANCE);
+            }
+        }
+        for p in 0..state.len() {
+            for k in 0..state[p].len() {
+                let mut up = state.clone();
+                let mut down = state.clone();
+                up[p][k] += EPSILON;
+                down[p][k] -= EPSILON;
+                let expected =
+                    (numeric(layer, &input, &up) - numeric(layer, &input, &down)) / (2.0 * EPSILON);
+                assert!((expected - initial_error[p][k]).abs() < TOLERANCE);
+            }
+        }
+        let mut perturbed = layer.clone();
+        for (p, matrix) in weight_error.iter().enumerate() {
+            for (i, row) in matrix.iter().enumerate() {
+                for (j, value) in row.iter().enumerate() {
+                    weights(&mut perturbed)[p][i][j] += EPSILON;
+                    let up = numeric(&perturbed, &input, &state);
+                    weights(&mut perturbed)[p][i][j] -= 2.0 * EPSILON;
+                    let down = numeric(&perturbed, &input, &state);
+                    weights(&mut perturbed)[p][i][j] += EPSILON;
+                    let expected = (up - down) / (2.0 * EPSILON);
+                    assert!((expected - value).abs() < TOLERANCE);
+                }
+            }
+        }
+        for (p, bias) in bias_error.iter().enumerate() {
+            for (i, value) in bias.iter().enumerate() {
+                biases(&mut perturbed)[p][i] += EPSILON;
+                let up = numeric(&perturbed, &input, &state);
+                biases(&mut perturbed)[p][i] -= 2.0 * EPSILON;
+                let down = numeric(&perturbed, &input, &state);
+                biases(&mut perturbed)[p][i] += EPSILON;
+                let expected = (up - down) / (2.0 * EPSILON);
+                assert!((expected - value).abs() < TOLERANCE);
+            }
+        }
+    }
+
+    // the gradients of a weighted sum of forward_last from a single error vector
+    fn check_last_gradients<R: Recurrent>(
+        layer: &R,
+        weights: fn(&mut R) -> Vec<&mut Vec<Vec<f32>>>,
+    ) {
+        let input = sequence(5, layer.dim_in(), 0.0);
+        let error = sequence(1, layer.dim_hidden(), 1.0);
+        let (input_error, weight_error, _, _) = layer.backward(&input, None, &error, None, None);
+        let numeric = |l: &R, x: &[Vec<f32>]| -> f32 {
+            l.forward_last(x, None)
+                .iter()
+                .zip(error[0].iter())
+                .map(|(a, b)| a * b)
+                .sum()
+        };
+        for t in 0..input.len() {
+            for k in 0..input[t].len() {
+                let mut up = input.clone();
+                let mut down = input.clone();
+                up[t][k] += EPSILON;
+                down[t][k] -= EPSILON;
+                let expected = (numeric(layer, &up) - numeric(layer, &down)) / (2.0 * EPSILON);
+                assert!((expected - input_error[t][k]).abs() < TOLERANCE);
+            }
+        }
+        let mut perturbed = layer.clone();
+        for (p, matrix) in weight_error.iter().enumerate() {
+            for (i, row) in matrix.iter().enumerate() {
+                for (j, value) in row.iter().enumerate() {
+                    weights(&mut perturbed)[p][i][j] += EPSILON;
+                    let up = numeric(&perturbed, &input);
+                    weights(&mut perturbed)[p][i][j] -= 2.0 * EPSILON;
+                    let down = numeric(&perturbed, &input);
+                    weights(&mut perturbed)[p][i][j] += EPSILON;
+                    let expected = (up - down) / (2.0 * EPSILON);
+                    assert!((expected - value).abs() < TOLERANCE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rnn_gradients() {
+        check_gradients(
+            &Rnn::new(3, 4),
+            |l| vec![&mut l.w_ih, &mut l.w_hh],
+            |l| vec![&mut l.b],
+        );
+    }
+
+    #[test]
+    fn test_lstm_gradients() {
+        check_gradients(
+            &Lstm::new(3, 4),
+            |l| vec![&mut l.w_ih, &mut l.w_hh],
+            |l| vec![&mut l.b],
+        );
+    }
+
+    #[test]
+    fn test_gru_gradients() {
+        check_gradients(
+            &Gru::new(3, 4),
+            |l| vec![&mut l.w_ih, &mut l.w_hh],
+            |l| vec![&mut l.b_ih, &mut l.b_hh],
+        );
+    }
+
+    #[test]
+    fn test_state_carries_over() {
+        let lstm = Lstm::new(2, 3);
+        let input = sequence(6, 2, 0.0);
+        let (outputs, final_state) = lstm.forward(&input, None);
+        let (first, state) = lstm.forward(&input[..4], None);
+        let (second, split_state) = lstm.forward(&input[4..], Some(&state));
+        assert_eq!(outputs.len(), 6);
+        assert_eq!(outputs, [first, second].concat());
+        assert_eq!(final_state, split_state);
+        assert_eq!(lstm.forward_last(&input, None), outputs[5]);
+    }
+
+    #[test]
+    fn test_last_step_error() {
+        let gru = Gru::new(2, 3);
+        let input = sequence(4, 2, 0.0);
+        let mut error = vec![vec![0.0; 3]; 4];
+        error[3] = vec![1.0, -1.0, 0.5];
+        let all = gru.backward(&input, None, &error, None, None);
+        let last = gru.backward(&input, None, &error[3..], None, None);
+        assert_eq!(all.0, last.0);
+        assert_eq!(all.1, last.1);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-empty sequence")]
+    fn test_last_step_error_without_steps() {
+        let gru = Gru::new(2, 3);
+        gru.backward(&[], None, &[vec![1.0, -1.0, 0.5]], None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-empty sequence")]
+    fn test_bidirectional_last_error_without_steps() {
+        let layer = Bidirectional::new(Gru::new(2, 3), Gru::new(2, 3));
+        layer.backward(&[], None, &[vec![0.0; 6]], None, None);
+    }
+
+    #[test]
+    fn test_truncated_bptt() {
+        let rnn = Rnn::new(2, 3);
+        let input = sequence(6, 2, 0.0);
+        let error = vec![vec![1.0, 1.0, 1.0]];
+        let full = rnn.backward(&input, None, &error, None, None);
+        let whole = rnn.backward(&input, None, &error, None, Some(6));
+        assert_eq!(full.0, whole.0);
+        assert_eq!(full.1, whole.1);
+
+        // chunks [0, 1, 2] and [3, 4, 5]; the error on the last step stops at step 3
+        let truncated = rnn.backward(&input, None, &error, None, Some(3));
+        for t in 0..3 {
+            assert!(truncated.0[t].iter().all(|x| *x == 0.0));
+        }
+        for t in 3..6 {
+            assert_eq!(truncated.0[t], full.0[t]);
+        }
+        assert!(truncated.3[0].iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn test_bidirectional_gradients() {
+        check_gradients(
+            &Bidirectional::new(Lstm::new(3, 2), Lstm::new(3, 2)),
+            |l| {
+                vec![
+                    &mut l.forward_layer.w_ih,
+                    &mut l.forward_layer.w_hh,
+                    &mut l.backward_layer.w_ih,
+                    &mut l.backward_layer.w_hh,
+                ]
+            },
+            |l| vec![&mut l.forward_layer.b, &mut l.backward_layer.b],
+        );
+    }
+
+    #[test]
+    fn test_bidirectional_last_gradients() {
+        let bidirectional = Bidirectional::new(Gru::new(3, 2), Gru::new(3, 2));
+        let input = sequence(4, 3, 0.0);
+        let (_, final_state) = bidirectional.forward(&input, None);
+        assert_eq!(bidirectional.forward_last(&input, None), final_state[0]);
+        check_last_gradients(&bidirectional, |l| {
+            vec![
+                &mut l.forward_layer.w_ih,
+                &mut l.forward_layer.w_hh,
+                &mut l.backward_layer.w_ih,
+                &mut l.backward_layer.w_hh,
+            ]
+        });
+        let stacked = Stacked::new(
+            vec![
+                Bidirectional::new(Lstm::new(3, 2), Lstm::new(3, 2)),
+                Bidirectional::new(Lstm::new(4, 2), Lstm::new(4, 2)),
+            ],
+            0.0,
+        );
+        check_last_gradients(&stacked, |l| {
+            l.layers
+                .iter_mut()
+                .flat_map(|x| {
+                    [
+                        &mut x.forward_layer.w_ih,
+                        &mut x.forward_layer.w_hh,
+                        &mut x.backward_layer.w_ih,
+                        &mut x.backward_layer.w_hh,
+                    ]
+                })
+                .collect()
+        });
+    }
+
+    #[test]
+    fn test_bidirectional_forward() {
+        let bidirectional = Bidirectional::new(Gru::new(2, 3), Gru::new(2, 3));
+        let input = sequence(4, 2, 0.0);
+        let (outputs, final_state) = bidirectional.forward(&input, None);
+        let reversed: Vec<Vec<f32>> = input.iter().rev().cloned().collect();
+        let (forward, _) = bidirectional.forward_layer.forward(&input, None);
+        let (backward, _) = bidirectional.backward_layer.forward(&reversed, None);
+        assert_eq!(outputs.len(), 4);
+        for t in 0..4 {
+            assert_eq!(
+                outputs[t],
+                [forward[t].clone(), backward[3 - t].clone()].concat()
+            );
+        }
+        assert_eq!(
+            final_state[0],
+            [forward[3].clone(), backward[3].clone()].concat()
+        );
+    }
+
+    #[test]
+    fn test_stacked_gradients() {
+        let mut stacked = Stacked::new(vec![Rnn::new(3, 4), Rnn::new(4, 4), Rnn::new(4, 2)], 0.5);
+        stacked.seed = 7;
+        check_gradients(
+            &stacked,
+            |l| {
+                l.layers
+                    .iter_mut()
+                    .flat_map(|x| [&mut x.w_ih, &mut x.w_hh])
+                    .collect()
+            },
+            |l| l.layers.iter_mut().map(|x| &mut x.b).collect(),
+        );
+    }
+
+    #[test]
+    fn test_stacked_bidirectional_gradients() {
+        let stacked = Stacked::new(
+            vec![
+                Bidirectional::new(Gru::new(3, 2), Gru::new(3, 2)),
+                Bidirectional::new(Gru::new(4, 2), Gru::new(4, 2)),
+            ],
+            0.0,
+        );
+        check_gradients(
+            &stacked,
+            |l| {
+                l.layers
+                    .iter_mut()
+                    .flat_map(|x| {
+                        [
+                            &mut x.forward_layer.w_ih,
+                            &mut x.forward_layer.w_hh,
+                            &mut x.backward_layer.w_ih,
+                            &mut x.backward_layer.w_hh,
+                        ]
+                    })
+                    .collect()
+            },
+            |l| {
+                l.layers
+                    .iter_mut()
+                    .flat_map(|x| {
+                        [
+                            &mut x.forward_layer.b_ih,
+                            &mut x.forward_layer.b_hh,
+                            &mut x.backward_layer.b_ih,
+                            &mut x.backward_layer.b_hh,
+                        ]
+                    })
+                    .collect()
+            },
+        );
+    }
+
+    #[test]
+    fn test_stacked_dropout() {
+        let mut stacked = Stacked::new(vec![Lstm::new(2, 8), Lstm::new(8, 3)], 0.5);
+        let input = sequence(5, 2, 0.0);
+        let error = sequence(5, 3, 1.0);
+        // within a batch forward and backward share masks
+        let (first, _) = stacked.forward(&input, None);
+        assert_eq!(first, stacked.forward(&input, None).0);
+        let gradients = stacked.backward(&input, None, &error, None, None);
+        assert_eq!(
+            gradients,
+            stacked.backward(&input, None, &error, None, None)
+        );
+        // and every batch gets new ones
+        let mut outputs = vec![first];
+        for _ in 0..3 {
+            stacked.next_batch();
+            let (output, _) = stacked.forward(&input, None);
+            assert!(outputs.iter().all(|x| *x != output));
+            outputs.push(output);
+        }
+        assert_ne!(
+            gradients,
+            stacked.backward(&input, None, &error, None, None)
+        );
+        stacked.training = false;
+        let (inner, _) = stacked.layers[0].forward(&input, None);
+        assert_eq!(
+            stacked.forward(&input, None).0,
+            stacked.layers[1].forward(&inner, None).0
+        );
+    }
+
+    #[test]
+    fn test_packed_sequence() {
+        let sequences = vec![
+            sequence(3, 2, 0.0),
+            sequence(5, 2, 1.0),
+            sequence(1, 2, 2.0),
+        ];
+        let packed = PackedSequence::new(&sequences);
+        assert_eq!(packed.lengths, [3, 5, 1]);
+        assert_eq!(packed.data[0].len(), 5);
+        assert_eq!(packed.mask()[0], [1.0, 1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(packed.unpack(), sequences);
+    }
+
+    #[test]
+    fn test_packed_forward_ignores_padding() {
+        let lstm = Lstm::new(2, 3);
+        let sequences = vec![sequence(3, 2, 0.0), sequence(5, 2, 1.0)];
+        let (outputs, states) = forward_packed(&lstm, &PackedSequence::new(&sequences), None);
+        for (i, x) in sequences.iter().enumerate() {
+            let (output, state) = lstm.forward(x, None);
+            assert_eq!(states[i], state);
+            assert_eq!(outputs.data[i][..x.len()], output[..]);
+        }
+        assert!(outputs.data[0][3..].iter().flatten().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn test_packed_backward() {
+        let bidirectional = Bidirectional::new(Rnn::new(2, 3), Rnn::new(2, 3));
+        let sequences = vec![sequence(4, 2, 0.0), sequence(2, 2, 1.0)];
+        let input = PackedSequence::new(&sequences);
+        let mut error = PackedSequence::new(&[sequence(4, 6, 2.0), sequence(2, 6, 3.0)]);
+        // anything written into the padding must not leak into the gradients
+        error.data[1][2] = vec![5.0; 6];
+        error.data[1][3] = vec![5.0; 6];
+        let (input_error, weight_error, bias_error, _) =
+            backward_packed(&bidirectional, &input, None, &error, None, None);
+
+        let first = bidirectional.backward(&sequences[0], None, &error.data[0], None, None);
+        let second = bidirectional.backward(&sequences[1], None, &error.data[1][..2], None, None);
+        assert_eq!(input_error.data[0], first.0);
+        assert_eq!(input_error.data[1][..2], second.0[..]);
+        assert_eq!(input_error.data[1][2], [0.0, 0.0]);
+        let expected = first.1.iter().zip(second.1.iter());
+        for (matrix, (a, b)) in weight_error.iter().zip(expected) {
+            for (row, (ra, rb)) in matrix.iter().zip(a.iter().zip(b.iter())) {
+                for (x, (y, z)) in row.iter().zip(ra.iter().zip(rb.iter())) {
+                    assert!((x - (y + z)).abs() < 1e-5);
+                }
+            }
+        }
+        assert_eq!(bias_error.len(), 2);
+    }
+}
@@ -0,0 +1,297 @@
+mod test;
+
+use std::io;
+
+use crate::neural_network::core::{
+    Flatten, Layer, Layer2D, LossFunction, ParameterKind, Record, Unflatten,
+};
+use crate::neural_network::optimiser::{minimize_with, Minimum, Optimizer, Options};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tensor {
+    Flat(Vec<f32>),
+    Spatial(Vec<Vec<Vec<f32>>>),
+}
+
+impl Tensor {
+    pub fn flat(&self) -> &[f32] {
+        match self {
+            Tensor::Flat(x) => x,
+            Tensor::Spatial(_) => panic!("expected a flat tensor"),
+        }
+    }
+
+    pub fn spatial(&self) -> &[Vec<Vec<f32>>] {
+        match self {
+            Tensor::Spatial(x) => x,
+            Tensor::Flat(_) => panic!("expected a spatial tensor"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Flat(usize),
+    Spatial((usize, usize, usize)),
+}
+
+pub enum Node {
+    Dense(Box<dyn Layer>),
+    Spatial(Box<dyn Layer2D>),
+    Flatten(Flatten),
+    Unflatten(Unflatten),
+}
+
+impl Node {
+    pub fn dim_in(&self) -> Shape {
+        match self {
+            Node::Dense(layer) => Shape::Flat(layer.dim_in()),
+            Node::Spatial(layer) => Shape::Spatial(layer.dim_in()),
+            Node::Flatten(flatten) => Shape::Spatial(flatten.dim_in),
+            Node::Unflatten(unflatten) => Shape::Flat(unflatten.dim_in()),
+        }
+    }
+
+    pub fn dim_out(&self) -> Shape {
+        match self {
+            Node::Dense(layer) => Shape::Flat(layer.dim_out()),
+            Node::Spatial(layer) => Shape::Spatial(layer.dim_out()),
+            Node::Flatten(flatten) => Shape::Flat(flatten.dim_out()),
+            Node::Unflatten(unflatten) => Shape::Spatial(unflatten.dim_out),
+        }
+    }
+
+    pub fn forward(&self, input: &Tensor) -> Tensor {
+        match self {
+            Node::Dense(layer) => Tensor::Flat(layer.forward(input.flat())),
+            Node::Spatial(layer) => Tensor::Spatial(layer.forward(input.spatial())),
+            Node::Flatten(flatten) => Tensor::Flat(flatten.forward(input.spatial())),
+            Node::Unflatten(unflatten) => Tensor::Spatial(unflatten.forward(input.flat())),
+        }
+    }
+
+    // the input error and the gradient rows, in the order of parameters
+    pub fn backward(&self, input: &Tensor, error: &Tensor) -> (Tensor, Vec<Vec<f32>>) {
+        match self {
+            Node::Dense(layer) => {
+                let (input_error, gradients) = layer.gradients(input.flat(), error.flat());
+                (Tensor::Flat(input_error), gradients)
+            }
+            Node::Spatial(layer) => {
+                let (input_error, gradients) = layer.gradients(input.spatial(), error.spatial());
+                (Tensor::Spatial(input_error), gradients)
+            }
+            Node::Flatten(flatten) => (Tensor::Spatial(flatten.back(error.flat())), Vec::new()),
+            Node::Unflatten(unflatten) => {
+                (Tensor::Flat(unflatten.back(error.spatial())), Vec::new())
+            }
+        }
+    }
+
+    pub fn parameters(&mut self) -> Vec<&mut [f32]> {
+        match self {
+            Node::Dense(layer) => layer.parameters(),
+            Node::Spatial(layer) => layer.parameters(),
+            Node::Flatten(_) | Node::Unflatten(_) => Vec::new(),
+        }
+    }
+
+    pub fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        match self {
+            Node::Dense(layer) => layer.parameter_kinds(),
+            Node::Spatial(layer) => layer.parameter_kinds(),
+            Node::Flatten(_) | Node::Unflatten(_) => Vec::new(),
+        }
+    }
+}
+
+// a chain of layers where flat and spatial layers meet through Flatten/Unflatten
+pub struct Sequential {
+    pub nodes: Vec<Node>,
+}
+
+impl Sequential {
+    pub fn new(nodes: Vec<Node>) -> Self {
+        for pair in nodes.windows(2) {
+            assert_eq!(pair[0].dim_out(), pair[1].dim_in());
+        }
+        Sequential { nodes }
+    }
+
+    pub fn forward(&self, input: &Tensor) -> Tensor {
+        self.nodes
+            .iter()
+            .fold(input.clone(), |acc, node| node.forward(&acc))
+    }
+
+    // the input to every node followed by the output
+    pub fn activations(&self, input: &Tensor) -> Vec<Tensor> {
+        let mut activations = vec![input.clone()];
+        for node in &self.nodes {
+            activations.push(node.forward(&activations[activations.len() - 1]));
+        }
+        activations
+    }
+
+    // the input error and the gradients of every parameter row, in the order of parameters
+    pub fn backward(&self, input: &Tensor, error: &Tensor) -> (Tensor, Vec<Vec<f32>>) {
+        self.backward_from(&self.activations(input), error)
+    }
+
+    // the same from activations already worked out on the way forward
+    pub fn backward_from(&self, activations: &[Tensor], error: &Tensor) -> (Tensor, Vec<Vec<f32>>) {
+        let mut error = error.clone();
+        let mut gradients = Vec::new();
+        for (node, node_input) in self.nodes.iter().zip(activations.iter()).rev() {
+            let (input_error, node_gradients) = node.backward(node_input, &error);
+            gradients.insert(0, node_gradients);
+            error = input_error;
+        }
+        (error, gradients.concat())
+    }
+
+    pub fn parameters(&mut self) -> Vec<&mut [f32]> {
+        self.nodes
+            .iter_mut()
+            .flat_map(|node| node.parameters())
+            .collect()
+    }
+
+    pub fn parameter_kinds(&mut self) -> Vec<ParameterKind> {
+        self.nodes
+            .iter_mut()
+            .flat_map(|node| node.parameter_kinds())
+            .collect()
+    }
+
+    // every parameter row, loaded back into a model built the same way
+    pub fn save(&mut self) -> Record {
+        let rows: Vec<Vec<f32>> = self.parameters().iter().map(|p| p.to_vec()).collect();
+        let mut record = Record::new();
+        record.push_rows("parameters", &rows);
+        record
+    }
+
+    pub fn load(&mut self, record: &Record) -> io::Result<()> {
+        let rows = record.rows("parameters")?;
+        let mut parameters = self.parameters();
+        let fits = rows.len() == parameters.len()
+            && rows
+                .iter()
+                .zip(parameters.iter())
+                .all(|(row, p)| row.len() == p.len());
+        if !fits {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "parameters don't match the model",
+            ));
+        }
+        for (p, row) in parameters.iter_mut().zip(rows.iter()) {
+            p.copy_from_slice(row);
+        }
+        Ok(())
+    }
+
+    // plain gradient descent on every parameter
+    pub fn update(&mut self, gradients: &[Vec<f32>], learning_rate: f32) {
+        for (parameter, gradient) in self.parameters().into_iter().zip(gradients.iter()) {
+            for (p, g) in parameter.iter_mut().zip(gradient.iter()) {
+                *p -= learning_rate * g;
+            }
+        }
+    }
+
+    // the mean loss over the batch and the mean gradients
+    pub fn loss_and_gradients(
+        &self,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+        loss: LossFunction,
+    ) -> (f32, Vec<Vec<f32>>) {
+        assert!(!inputs.is_empty(), "an empty batch has no mean loss");
+        let scale = 1.0 / inputs.len() as f32;
+        let mut total = 0.0;
+        let mut gradients: Vec<Vec<f32>> = Vec::new();
+        for (input, target) in inputs.iter().zip(targets.iter()) {
+            let activations = self.activations(input);
+            let (value, error) = loss(activations[activations.len() - 1].flat(), target);
+            let (_, sample) = self.backward_from(&activations, &Tensor::Flat(error));
+            total += value * scale;
+            if gradients.is_empty() {
+                gradients = sample
+                    .iter()
+                    .map(|row| row.iter().map(|x| x * scale).collect())
+                    .collect();
+            } else {
+                for (acc, row) in gradients.iter_mut().zip(sample.iter()) {
+                    for (a, x) in acc.iter_mut().zip(row.iter()) {
+                        *a += x * scale;
+                    }
+                }
+            }
+        }
+        (total, gradients)
+    }
+
+    pub fn train_step(
+        &mut self,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+        loss: LossFunction,
+        learning_rate: f32,
+    ) -> f32 {
+        let (value, gradients) = self.loss_and_gradients(inputs, targets, loss);
+        self.update(&gradients, learning_rate);
+        value
+    }
+
+    // train_step with the update left to an optimiser
+    pub fn optimise(
+        &mut self,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+        loss: LossFunction,
+        optimizer: &mut dyn Optimizer,
+    ) -> f32 {
+        let (value, gradients) = self.loss_and_gradients(inputs, targets, loss);
+        optimizer.step(self.parameters(), &gradients);
+        value
+    }
+
+    // full batch minimisation of the mean loss with L-BFGS or conjugate
+    // gradient over every parameter at once, which suits small problems better
+    // than an optimiser; the search runs in f64 and the model is left at the
+    // minimum found
+    pub fn minimise(
+        &mut self,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+        loss: LossFunction,
+        options: &Options,
+    ) -> Minimum {
+        let x0: Vec<f64> = self
+            .parameters()
+            .iter()
+            .flat_map(|p| p.iter().map(|x| *x as f64))
+            .collect();
+        let minimum = minimize_with(
+            |x| {
+                self.set_flat_parameters(x);
+                let (value, gradients) = self.loss_and_gradients(inputs, targets, loss);
+                let gradient = gradients.iter().flatten().map(|g| *g as f64).collect();
+                (value as f64, gradient)
+            },
+            &x0,
+            options,
+        );
+        self.set_flat_parameters(&minimum.x);
+        minimum
+    }
+
+    fn set_flat_parameters(&mut self, x: &[f64]) {
+        let mut values = x.iter();
+        for p in self.parameters().into_iter().flatten() {
+            *p = *values.next().unwrap() as f32;
+        }
+    }
+}
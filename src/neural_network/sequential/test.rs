@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod test_sequential {
+    use crate::neural_network::{
+        convolutional::{Conv2D, MaxPool2D},
+        core::{cross_entropy, Flatten, Layer, Layer2D, Record, Softmax},
+        linear::linear::Linear,
+        sequential::{Node, Sequential, Tensor},
+    };
+
+    fn classifier() -> Sequential {
+        let conv = Conv2D::new((1, 6, 6), 3, (3, 3), (1, 1), (1, 1));
+        let pool = MaxPool2D::new(conv.dim_out(), (2, 2), (0, 0), (2, 2));
+        let flatten = Flatten::new(pool.dim_out());
+        let linear = Linear::new(flatten.dim_out(), 2);
+        let softmax = Softmax::new(linear.dim_out());
+        Sequential::new(vec![
+            Node::Spatial(Box::new(conv)),
+            Node::Spatial(Box::new(pool)),
+            Node::Flatten(flatten),
+            Node::Dense(Box::new(linear)),
+            Node::Dense(Box::new(softmax)),
+        ])
+    }
+
+    // a vertical bar is class 0 and a horizontal bar is class 1
+    fn bars() -> (Vec<Tensor>, Vec<Vec<f32>>) {
+        let mut inputs = Vec::new();
+        let mut targets = Vec::new();
+        for k in 0..6 {
+            let mut vertical = vec![vec![0.0; 6]; 6];
+            let mut horizontal = vec![vec![0.0; 6]; 6];
+            for i in 0..6 {
+                vertical[i][k] = 1.0;
+                horizontal[k][i] = 1.0;
+            }
+            inputs.push(Tensor::Spatial(vec![vertical]));
+            targets.push(vec![1.0, 0.0]);
+            inputs.push(Tensor::Spatial(vec![horizontal]));
+            targets.push(vec![0.0, 1.0]);
+        }
+        (inputs, targets)
+    }
+
+    #[test]
+    fn test_sequential_shapes() {
+        let model = classifier();
+        let output = model.forward(&bars().0[0]);
+        assert_eq!(output.flat().len(), 2);
+        assert!((output.flat().iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sequential_checks_shapes() {
+        Sequential::new(vec![
+            Node::Dense(Box::new(Linear::new(4, 3))),
+            Node::Dense(Box::new(Linear::new(2, 1))),
+        ]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_batch() {
+        let model = Sequential::new(vec![Node::Dense(Box::new(Linear::new(4, 3)))]);
+        model.loss_and_gradients(&[], &[], cross_entropy);
+    }
+
+    #[test]
+    fn test_sequential_gradients() {
+        let mut model = classifier();
+        // fixed weights and images with distinct pixels keep every max pooling
+        // window clear of ties that central differences could straddle
+        for (r, row) in model.parameters().into_iter().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = 0.5 * ((7 * r + j) as f32 * 0.61).sin();
+            }
+        }
+        let inputs: Vec<Tensor> = (0..2)
+            .map(|k| {
+                Tensor::Spatial(vec![(0..6)
+                    .map(|i| {
+                        (0..6)
+                            .map(|j| ((36 * k + 6 * i + j) as f32 * 0.37).sin())
+                            .collect()
+                    })
+                    .collect()])
+            })
+            .collect();
+        let targets = bars().1;
+        let (_, gradients) = model.loss_and_gradients(&inputs[..2], &targets[..2], cross_entropy);
+        let epsilon = 1e-3;
+        assert_eq!(model.parameters().len(), gradients.len());
+        for (row, gradient) in gradients.iter().enumerate() {
+            for (k, actual) in gradient.iter().enumerate() {
+                model.parameters()[row][k] += epsilon;
+                let (up, _) = model.loss_and_gradients(&inputs[..2], &targets[..2], cross_entropy);
+                model.parameters()[row][k] -= 2.0 * epsilon;
+                let (down, _) =
+                    model.loss_and_gradients(&inputs[..2], &targets[..2], cross_entropy);
+                model.parameters()[row][k] += epsilon;
+                let expected = (up - down) / (2.0 * epsilon);
+                assert!((expected - actual).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_train_cnn() {
+        let mut model = classifier();
+        let (inputs, targets) = bars();
+        let (initial, _) = model.loss_and_gradients(&inputs, &targets, cross_entropy);
+        for _ in 0..100 {
+            model.train_step(&inputs, &targets, cross_entropy, 0.5);
+        }
+        let (trained, _) = model.loss_and_gradients(&inputs, &targets, cross_entropy);
+        assert!(trained < initial);
+        for (input, target) in inputs.iter().zip(targets.iter()) {
+            let output = model.forward(input);
+            let predicted = if output.flat()[0] > output.flat()[1] {
+                0
+            } else {
+                1
+            };
+            assert_eq!(target[predicted], 1.0);
+        }
+    }
+
+    #[test]
+    fn test_sequential_save_load() {
+        let mut model = classifier();
+        let record = Record::from_text(&model.save().to_text()).unwrap();
+        let mut other = classifier();
+        other.load(&record).unwrap();
+        assert_eq!(other.parameters(), model.parameters());
+
+        let mut wrong = Sequential::new(vec![Node::Dense(Box::new(Linear::new(4, 3)))]);
+        assert!(wrong.load(&record).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::neural_network::optimiser::Optimizer;
+use crate::neural_network::sequential::Sequential;
+
+use super::Trainer;
+
+// hooks into Trainer::fit; the trainer is passed as it stands, with its
+// counters, history and scheduler up to date
+pub trait Callback {
+    // straight after every optimiser step, with the model, to hold its
+    // parameters to some constraint
+    fn on_step_end(&mut self, _trainer: &Trainer, _model: &mut Sequential) {}
+
+    // after every optimiser step, with the mean loss of the batch
+    fn on_batch_end(&mut self, _trainer: &Trainer, _loss: f32) {}
+
+    // after the epoch is recorded in the history, returning false stops training
+    fn on_epoch_end(
+        &mut self,
+        _trainer: &Trainer,
+        _model: &mut Sequential,
+        _optimizer: &mut dyn Optimizer,
+    ) -> bool {
+        true
+    }
+}
+
+// prints the latest of everything in the history every few epochs
+pub struct Logger {
+    pub every: usize,
+}
+
+impl Logger {
+    pub fn new(every: usize) -> Self {
+        assert!(every > 0);
+        Logger { every }
+    }
+}
+
+impl Callback for Logger {
+    fn on_epoch_end(
+        &mut self,
+        trainer: &Trainer,
+        _model: &mut Sequential,
+        _optimizer: &mut dyn Optimizer,
+    ) -> bool {
+        if trainer.epoch.is_multiple_of(self.every) {
+            let values: Vec<String> = trainer
+                .history
+                .series
+                .iter()
+                .map(|(name, values)| format!("{name} {:.4}", values[values.len() - 1]))
+                .collect();
+            println!("epoch {}: {}", trainer.epoch, values.join(", "));
+        }
+        true
+    }
+}
@@ -0,0 +1,80 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::neural_network::core::Record;
+use crate::neural_network::optimiser::Optimizer;
+use crate::neural_network::sequential::Sequential;
+
+use super::{Callback, Trainer};
+
+// the model, optimiser and trainer in one record; checkpoints are taken between
+// epochs, so resuming from one and fitting again makes the same updates an
+// uninterrupted run would have
+pub fn checkpoint(trainer: &Trainer, model: &mut Sequential, optimizer: &dyn Optimizer) -> Record {
+    let mut record = Record::new();
+    record.extend("model", model.save());
+    record.extend("optimizer", optimizer.save());
+    record.extend("trainer", trainer.save());
+    record
+}
+
+// puts a checkpoint back into a trainer, model and optimiser built the same way
+// as the ones it was taken from
+pub fn resume(
+    record: &Record,
+    trainer: &mut Trainer,
+    model: &mut Sequential,
+    optimizer: &mut dyn Optimizer,
+) -> io::Result<()> {
+    model.load(&record.section("model"))?;
+    optimizer.load(&record.section("optimizer"))?;
+    trainer.load(&record.section("trainer"))
+}
+
+pub fn resume_from(
+    path: impl AsRef<Path>,
+    trainer: &mut Trainer,
+    model: &mut Sequential,
+    optimizer: &mut dyn Optimizer,
+) -> io::Result<()> {
+    resume(&Record::load(path)?, trainer, model, optimizer)
+}
+
+// writes a checkpoint to path every few epochs, replacing the last one; a
+// failed write stops training and is kept in error
+pub struct Checkpoint {
+    pub path: PathBuf,
+    pub every: usize,
+    pub error: Option<io::Error>,
+}
+
+impl Checkpoint {
+    pub fn new(path: impl Into<PathBuf>, every: usize) -> Self {
+        assert!(every > 0);
+        Checkpoint {
+            path: path.into(),
+            every,
+            error: None,
+        }
+    }
+}
+
+impl Callback for Checkpoint {
+    fn on_epoch_end(
+        &mut self,
+        trainer: &Trainer,
+        model: &mut Sequential,
+        optimizer: &mut dyn Optimizer,
+    ) -> bool {
+        if !trainer.epoch.is_multiple_of(self.every) {
+            return true;
+        }
+        match checkpoint(trainer, model, optimizer).save(&self.path) {
+            Ok(()) => true,
+            Err(error) => {
+                self.error = Some(error);
+                false
+            }
+        }
+    }
+}
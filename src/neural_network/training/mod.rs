@@ -0,0 +1,8 @@
+pub mod callback;
+pub mod checkpoint;
+mod test;
+pub mod trainer;
+
+pub use callback::*;
+pub use checkpoint::*;
+pub use trainer::*;
@@ -0,0 +1,360 @@
+#[cfg(test)]
+mod test_training {
+    use crate::neural_network::{
+        core::{cross_entropy, mean_squared_error, Layer, Record, Softmax},
+        linear::linear::Linear,
+        optimiser::{Adam, Optimizer, Schedule, Scheduler, Sgd},
+        sequential::{Node, Sequential, Tensor},
+        training::{
+            accuracy, checkpoint, macro_f1, resume, resume_from, Callback, Checkpoint,
+            EarlyStopping, Trainer,
+        },
+    };
+
+    fn regressor() -> Sequential {
+        let mut linear = Linear::new(2, 1);
+        linear.weights = vec![vec![0.3, -0.2]];
+        Sequential::new(vec![Node::Dense(Box::new(linear))])
+    }
+
+    // y = 2 a - b + 0.5 on a small grid
+    fn line() -> (Vec<Tensor>, Vec<Vec<f32>>) {
+        (0..20)
+            .map(|k| {
+                let (a, b) = ((k % 5) as f32 * 0.5 - 1.0, (k / 5) as f32 * 0.5 - 0.75);
+                (Tensor::Flat(vec![a, b]), vec![2.0 * a - b + 0.5])
+            })
+            .unzip()
+    }
+
+    fn classifier() -> Sequential {
+        let mut linear = Linear::new(2, 2);
+        linear.weights = vec![vec![0.1, 0.2], vec![-0.3, 0.1]];
+        let softmax = Softmax::new(linear.dim_out());
+        Sequential::new(vec![
+            Node::Dense(Box::new(linear)),
+            Node::Dense(Box::new(softmax)),
+        ])
+    }
+
+    // class 1 above the line a = b, interleaved so the held out end has both
+    fn halves() -> (Vec<Tensor>, Vec<Vec<f32>>) {
+        (0..40)
+            .map(|k| {
+                let (a, b) = (
+                    (k * 7 % 11) as f32 / 5.0 - 1.0,
+                    (k * 3 % 13) as f32 / 6.0 - 1.0,
+                );
+                let target = if b > a {
+                    vec![0.0, 1.0]
+                } else {
+                    vec![1.0, 0.0]
+                };
+                (Tensor::Flat(vec![a, b]), target)
+            })
+            .unzip()
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        batches: Vec<usize>,
+        epochs: Vec<usize>,
+        stop_after: Option<usize>,
+    }
+
+    impl Callback for Counter {
+        fn on_batch_end(&mut self, trainer: &Trainer, loss: f32) {
+            assert!(loss.is_finite());
+            self.batches.push(trainer.step);
+        }
+
+        fn on_epoch_end(
+            &mut self,
+            trainer: &Trainer,
+            _model: &mut Sequential,
+            _optimizer: &mut dyn Optimizer,
+        ) -> bool {
+            self.epochs.push(trainer.epoch);
+            self.stop_after != Some(trainer.epoch)
+        }
+    }
+
+    #[test]
+    fn test_trainer_fits() {
+        let (inputs, targets) = line();
+        let mut model = regressor();
+        let mut optimizer = Sgd::new(0.1, 0.9, false);
+        let mut trainer = Trainer::new(mean_squared_error, 30, 6, 0);
+        let mut counter = Counter::default();
+        let history = trainer.fit(
+            &mut model,
+            &mut optimizer,
+            &inputs,
+            &targets,
+            &mut [&mut counter],
+        );
+        let loss = history.get("loss").unwrap();
+        assert_eq!(loss.len(), 30);
+        assert!(loss[29] < 1e-3 * loss[0]);
+        assert_eq!(history.get("learning_rate").unwrap(), [0.1; 30]);
+        assert_eq!(history.get("val_loss"), None);
+        // 20 samples in batches of 6 is 4 steps an epoch
+        assert_eq!((trainer.epoch, trainer.step), (30, 120));
+        assert_eq!(counter.batches, (1..=120).collect::<Vec<usize>>());
+        assert_eq!(counter.epochs, (1..=30).collect::<Vec<usize>>());
+
+        // the shuffles come from the seed
+        let run = |seed| {
+            let mut model = regressor();
+            let mut optimizer = Sgd::new(0.1, 0.9, false);
+            let mut trainer = Trainer::new(mean_squared_error, 3, 6, seed);
+            trainer.fit(&mut model, &mut optimizer, &inputs, &targets, &mut []);
+            model
+                .parameters()
+                .iter()
+                .map(|p| p.to_vec())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(0), run(0));
+        assert_ne!(run(0), run(1));
+    }
+
+    #[test]
+    fn test_trainer_validation_and_metrics() {
+        let (inputs, targets) = halves();
+        let mut model = classifier();
+        let mut optimizer = Sgd::new(0.5, 0.0, false);
+        let mut trainer = Trainer::new(cross_entropy, 40, 4, 3);
+        trainer.validation_split = 0.25;
+        trainer.metrics = vec![("accuracy".to_string(), accuracy)];
+        trainer.scheduler = Some(
+            Scheduler::new(
+                0.5,
+                Schedule::Step {
+                    size: 10,
+                    gamma: 0.5,
+                },
+            )
+            .unwrap(),
+        );
+        let history = trainer.fit(&mut model, &mut optimizer, &inputs, &targets, &mut []);
+
+        assert_eq!(trainer.split(inputs.len()), 30);
+        assert_eq!(trainer.step, 40 * 8);
+        let rates = history.get("learning_rate").unwrap();
+        assert_eq!((rates[0], rates[10], rates[39]), (0.5, 0.25, 0.0625));
+        assert_eq!(history.get("val_loss").unwrap().len(), 40);
+        assert_eq!(history.get("accuracy"), None);
+        assert!(history.last("val_accuracy").unwrap() >= 0.9);
+        let scores = trainer.evaluate(&model, &inputs[30..], &targets[30..]);
+        assert_eq!(
+            scores[0],
+            ("loss".to_string(), history.last("val_loss").unwrap())
+        );
+
+        // without a validation set the metrics are on the training samples
+        let mut trainer = Trainer::new(cross_entropy, 2, 4, 3);
+        trainer.metrics = vec![("accuracy".to_string(), accuracy)];
+        let history = trainer.fit(
+            &mut classifier(),
+            &mut optimizer,
+            &inputs,
+            &targets,
+            &mut [],
+        );
+        assert_eq!(history.get("accuracy").unwrap().len(), 2);
+        assert_eq!(history.get("val_accuracy"), None);
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let outputs = [
+            vec![0.9, 0.1],
+            vec![0.2, 0.8],
+            vec![0.6, 0.4],
+            vec![0.3, 0.7],
+        ];
+        let targets = [
+            vec![1.0, 0.0],
+            vec![0.0, 1.0],
+            vec![0.0, 1.0],
+            vec![0.0, 1.0],
+        ];
+        assert_eq!(accuracy(&outputs, &targets), 0.75);
+        // F1 of 2 / 3 for the first class and 4 / 5 for the second
+        assert!((macro_f1(&outputs, &targets) - 11.0 / 15.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        let mut model = regressor();
+        let mut stopping = EarlyStopping::new("val_loss", 2, 0.01, false);
+        stopping.restore_best = true;
+        let mut observe = |value: f32, model: &mut Sequential| {
+            model.parameters()[0][0] = value;
+            stopping.observe(value, model)
+        };
+        assert!(!observe(1.0, &mut model));
+        assert!(!observe(0.5, &mut model));
+        // not better by more than min_delta
+        assert!(!observe(0.495, &mut model));
+        assert!(observe(0.7, &mut model));
+        assert_eq!(model.parameters()[0][0], 0.5);
+
+        let mut stopping = EarlyStopping::new("val_accuracy", 1, 0.0, true);
+        assert!(!stopping.observe(0.5, &mut model));
+        assert!(!stopping.observe(0.6, &mut model));
+        assert!(stopping.observe(0.6, &mut model));
+        assert_eq!(model.parameters()[0][0], 0.5);
+
+        // a learning rate of zero never improves, so training stops after the
+        // first epoch and patience more
+        let (inputs, targets) = line();
+        let mut trainer = Trainer::new(mean_squared_error, 50, 5, 0);
+        trainer.early_stopping = Some(EarlyStopping::new("loss", 3, 0.0, false));
+        let history = trainer.fit(
+            &mut regressor(),
+            &mut Sgd::new(0.0, 0.0, false),
+            &inputs,
+            &targets,
+            &mut [],
+        );
+        assert_eq!(history.get("loss").unwrap().len(), 4);
+        assert!(trainer.stopped);
+
+        // a callback can stop training too
+        let mut counter = Counter {
+            stop_after: Some(2),
+            ..Counter::default()
+        };
+        let mut trainer = Trainer::new(mean_squared_error, 50, 5, 0);
+        trainer.fit(
+            &mut regressor(),
+            &mut Sgd::new(0.1, 0.0, false),
+            &inputs,
+            &targets,
+            &mut [&mut counter],
+        );
+        assert_eq!((trainer.epoch, counter.epochs.len()), (2, 2));
+    }
+
+    // the same settings every time, whatever the seed
+    fn configured(epochs: usize, seed: u64) -> Trainer {
+        let mut trainer = Trainer::new(cross_entropy, epochs, 4, seed);
+        trainer.validation_split = 0.25;
+        trainer.metrics = vec![("accuracy".to_string(), accuracy)];
+        trainer.scheduler =
+            Some(Scheduler::new(0.05, Schedule::Exponential { gamma: 0.8 }).unwrap());
+        let mut stopping = EarlyStopping::new("val_loss", 100, 0.0, false);
+        stopping.restore_best = true;
+        trainer.early_stopping = Some(stopping);
+        trainer
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let (inputs, targets) = halves();
+        let mut model = classifier();
+        let mut optimizer = Adam::new(0.05, (0.9, 0.999), 1e-8);
+        let mut trainer = configured(6, 5);
+        let history = trainer.fit(&mut model, &mut optimizer, &inputs, &targets, &mut []);
+
+        let path = std::env::temp_dir().join(format!("checkpoint-{}.txt", std::process::id()));
+        let mut first = classifier();
+        let mut checkpoint = Checkpoint::new(&path, 3);
+        configured(3, 5).fit(
+            &mut first,
+            &mut Adam::new(0.05, (0.9, 0.999), 1e-8),
+            &inputs,
+            &targets,
+            &mut [&mut checkpoint],
+        );
+        assert!(checkpoint.error.is_none());
+
+        // everything that matters comes from the checkpoint, not from how
+        // these were made
+        let mut resumed = classifier();
+        resumed.parameters()[0][0] = 9.0;
+        let mut resumed_optimizer = Adam::new(1.0, (0.5, 0.5), 1.0);
+        let mut resumed_trainer = configured(6, 77);
+        resume_from(
+            &path,
+            &mut resumed_trainer,
+            &mut resumed,
+            &mut resumed_optimizer,
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((resumed_trainer.epoch, resumed_trainer.step), (3, 24));
+        let resumed_history = resumed_trainer.fit(
+            &mut resumed,
+            &mut resumed_optimizer,
+            &inputs,
+            &targets,
+            &mut [],
+        );
+
+        assert_eq!(resumed_history, history);
+        assert_eq!(resumed.parameters(), model.parameters());
+        assert_eq!(resumed_optimizer.save(), optimizer.save());
+        assert_eq!(resumed_trainer.save(), trainer.save());
+    }
+
+    // counters past 2^24, where an f32 no longer holds every whole number
+    #[test]
+    fn test_checkpoint_large_counters() {
+        let big: usize = 1 << 24;
+        let mut model = classifier();
+        let mut optimizer = Adam::new(0.05, (0.9, 0.999), 1e-8);
+        optimizer.steps = big as i32 + 9;
+        let mut trainer = configured(6, 5);
+        trainer.epoch = big + 1;
+        trainer.step = 3 * big + 1;
+        trainer.scheduler.as_mut().unwrap().steps = big + 5;
+        trainer.early_stopping.as_mut().unwrap().wait = big + 7;
+        let text = checkpoint(&trainer, &mut model, &optimizer).to_text();
+
+        let mut resumed_optimizer = Adam::new(0.05, (0.9, 0.999), 1e-8);
+        let mut resumed_trainer = configured(6, 5);
+        resume(
+            &Record::from_text(&text).unwrap(),
+            &mut resumed_trainer,
+            &mut classifier(),
+            &mut resumed_optimizer,
+        )
+        .unwrap();
+        assert_eq!(resumed_optimizer.steps, big as i32 + 9);
+        assert_eq!(
+            (resumed_trainer.epoch, resumed_trainer.step),
+            (big + 1, 3 * big + 1)
+        );
+        assert_eq!(resumed_trainer.scheduler.unwrap().steps, big + 5);
+        assert_eq!(resumed_trainer.early_stopping.unwrap().wait, big + 7);
+    }
+
+    #[test]
+    fn test_checkpoint_errors() {
+        let (inputs, targets) = halves();
+        let path = std::env::temp_dir()
+            .join("no-such-directory")
+            .join("checkpoint.txt");
+        let mut checkpoint = Checkpoint::new(&path, 1);
+        let mut trainer = configured(5, 0);
+        trainer.fit(
+            &mut classifier(),
+            &mut Adam::new(0.05, (0.9, 0.999), 1e-8),
+            &inputs,
+            &targets,
+            &mut [&mut checkpoint],
+        );
+        assert_eq!(trainer.epoch, 1);
+        assert!(checkpoint.error.is_some());
+        assert!(resume_from(
+            &path,
+            &mut configured(5, 0),
+            &mut classifier(),
+            &mut Adam::new(0.05, (0.9, 0.999), 1e-8),
+        )
+        .is_err());
+    }
+}
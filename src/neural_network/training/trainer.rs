@@ -0,0 +1,360 @@
+use std::io;
+
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::metrics::{self, classes_of, Average};
+use crate::neural_network::core::{from_pieces, pieces, LossFunction, Record};
+use crate::neural_network::optimiser::{Optimizer, Scheduler};
+use crate::neural_network::sequential::{Sequential, Tensor};
+
+use super::Callback;
+
+// a score over a whole set of outputs and their targets
+pub type Metric = fn(&[Vec<f32>], &[Vec<f32>]) -> f32;
+
+// the fraction of outputs whose largest entry is where the one hot target is
+pub fn accuracy(outputs: &[Vec<f32>], targets: &[Vec<f32>]) -> f32 {
+    metrics::accuracy(&classes_of(outputs), &classes_of(targets)) as f32
+}
+
+// F1 averaged evenly over the classes of the one hot targets, which unlike
+// accuracy is not flattered by a model that only predicts the common classes
+pub fn macro_f1(outputs: &[Vec<f32>], targets: &[Vec<f32>]) -> f32 {
+    let classes = targets.first().map_or(0, |target| target.len());
+    metrics::f1_score(
+        &classes_of(outputs),
+        &classes_of(targets),
+        classes,
+        Average::Macro,
+    ) as f32
+}
+
+// one value per epoch under each name, such as loss, learning_rate, the
+// metrics and the same with val_ in front for the validation set
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    pub series: Vec<(String, Vec<f32>)>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History { series: Vec::new() }
+    }
+
+    pub fn push(&mut self, name: &str, value: f32) {
+        match self.series.iter_mut().find(|(n, _)| n == name) {
+            Some((_, values)) => values.push(value),
+            None => self.series.push((name.to_string(), vec![value])),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[f32]> {
+        self.series
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    pub fn last(&self, name: &str) -> Option<f32> {
+        self.get(name).and_then(|values| values.last().copied())
+    }
+
+    pub fn save(&self) -> Record {
+        Record {
+            entries: self.series.clone(),
+        }
+    }
+
+    pub fn load(record: &Record) -> History {
+        History {
+            series: record.entries.clone(),
+        }
+    }
+}
+
+// stops once the monitored history entry has gone patience epochs without
+// improving on the best by more than min_delta, lower being better unless
+// maximise; with restore_best the parameters from the best epoch are put back
+#[derive(Clone, Debug, PartialEq)]
+pub struct EarlyStopping {
+    pub monitor: String,
+    pub patience: usize,
+    pub min_delta: f32,
+    pub maximise: bool,
+    pub restore_best: bool,
+    pub best: Option<f32>,
+    pub wait: usize,
+    pub best_parameters: Vec<Vec<f32>>,
+}
+
+impl EarlyStopping {
+    pub fn new(monitor: &str, patience: usize, min_delta: f32, maximise: bool) -> Self {
+        EarlyStopping {
+            monitor: monitor.to_string(),
+            patience,
+            min_delta,
+            maximise,
+            restore_best: false,
+            best: None,
+            wait: 0,
+            best_parameters: Vec::new(),
+        }
+    }
+
+    fn improves(&self, value: f32) -> bool {
+        match self.best {
+            None => true,
+            Some(best) if self.maximise => value > best + self.min_delta,
+            Some(best) => value < best - self.min_delta,
+        }
+    }
+
+    // true when training should stop
+    pub fn observe(&mut self, value: f32, model: &mut Sequential) -> bool {
+        if self.improves(value) {
+            self.best = Some(value);
+            self.wait = 0;
+            if self.restore_best {
+                self.best_parameters = model.parameters().iter().map(|p| p.to_vec()).collect();
+            }
+            return false;
+        }
+        self.wait += 1;
+        if self.wait < self.patience {
+            return false;
+        }
+        if self.restore_best {
+            for (p, best) in model
+                .parameters()
+                .into_iter()
+                .zip(self.best_parameters.iter())
+            {
+                p.copy_from_slice(best);
+            }
+        }
+        true
+    }
+
+    // only the running state, the settings come from whoever built it; no best
+    // yet is saved as NaN
+    pub fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push("best", &[self.best.unwrap_or(f32::NAN)]);
+        record.push_count("wait", self.wait as u64);
+        record.push_rows("best_parameters", &self.best_parameters);
+        record
+    }
+
+    pub fn load(&mut self, record: &Record) -> io::Result<()> {
+        let best = record.scalar("best")?;
+        self.best = (!best.is_nan()).then_some(best);
+        self.wait = record.count("wait")? as usize;
+        self.best_parameters = record.rows("best_parameters")?;
+        Ok(())
+    }
+}
+
+fn save_rng(rng: &ChaCha8Rng) -> Record {
+    let mut record = Record::new();
+    let seed: Vec<f32> = rng.get_seed().iter().map(|x| *x as f32).collect();
+    record.push("seed", &seed);
+    record.push("stream", &pieces(rng.get_stream() as u128, 4));
+    record.push("position", &pieces(rng.get_word_pos(), 8));
+    record
+}
+
+fn load_rng(record: &Record) -> io::Result<ChaCha8Rng> {
+    let mut seed = [0; 32];
+    let values = record.get("seed")?;
+    if values.len() != seed.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad seed"));
+    }
+    for (byte, x) in seed.iter_mut().zip(values.iter()) {
+        *byte = *x as u8;
+    }
+    let mut rng = ChaCha8Rng::from_seed(seed);
+    rng.set_stream(from_pieces(record.get("stream")?) as u64);
+    rng.set_word_pos(from_pieces(record.get("position")?));
+    Ok(rng)
+}
+
+// runs epochs of shuffled minibatches through an optimiser; the last
+// validation_split of the samples is held out and evaluated after every epoch,
+// along with the metrics, which are taken on the training samples when nothing
+// is held out; the scheduler, if any, steps once per epoch on the validation
+// loss or else the training loss; epoch and step count what has been done, so
+// fit carries on from there when called again
+pub struct Trainer {
+    pub loss: LossFunction,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub shuffle: bool,
+    pub validation_split: f32,
+    pub metrics: Vec<(String, Metric)>,
+    pub early_stopping: Option<EarlyStopping>,
+    pub scheduler: Option<Scheduler>,
+    pub rng: ChaCha8Rng,
+    pub epoch: usize,
+    pub step: usize,
+    pub stopped: bool,
+    pub history: History,
+}
+
+impl Trainer {
+    pub fn new(loss: LossFunction, epochs: usize, batch_size: usize, seed: u64) -> Self {
+        Trainer {
+            loss,
+            epochs,
+            batch_size,
+            shuffle: true,
+            validation_split: 0.0,
+            metrics: Vec::new(),
+            early_stopping: None,
+            scheduler: None,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            epoch: 0,
+            step: 0,
+            stopped: false,
+            history: History::new(),
+        }
+    }
+
+    // the number of samples trained on, the rest being held out
+    pub fn split(&self, samples: usize) -> usize {
+        samples - (samples as f32 * self.validation_split).round() as usize
+    }
+
+    // the mean loss and then every metric over a set of samples
+    pub fn evaluate(
+        &self,
+        model: &Sequential,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+    ) -> Vec<(String, f32)> {
+        let outputs: Vec<Vec<f32>> = inputs
+            .iter()
+            .map(|input| model.forward(input).flat().to_vec())
+            .collect();
+        let loss = outputs
+            .iter()
+            .zip(targets.iter())
+            .map(|(output, target)| (self.loss)(output, target).0)
+            .sum::<f32>()
+            / inputs.len() as f32;
+        let mut scores = vec![("loss".to_string(), loss)];
+        for (name, metric) in &self.metrics {
+            scores.push((name.clone(), metric(&outputs, targets)));
+        }
+        scores
+    }
+
+    pub fn fit(
+        &mut self,
+        model: &mut Sequential,
+        optimizer: &mut dyn Optimizer,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+        callbacks: &mut [&mut dyn Callback],
+    ) -> History {
+        assert_eq!(inputs.len(), targets.len());
+        let split = self.split(inputs.len());
+        assert!(split > 0 && self.batch_size > 0);
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.apply(optimizer);
+        }
+        while self.epoch < self.epochs && !self.stopped {
+            let mut order: Vec<usize> = (0..split).collect();
+            if self.shuffle {
+                order.shuffle(&mut self.rng);
+            }
+            let rate = optimizer.learning_rate();
+            let mut total = 0.0;
+            for batch in order.chunks(self.batch_size) {
+                let batch_inputs: Vec<Tensor> = batch.iter().map(|i| inputs[*i].clone()).collect();
+                let batch_targets: Vec<Vec<f32>> =
+                    batch.iter().map(|i| targets[*i].clone()).collect();
+                let loss = model.optimise(&batch_inputs, &batch_targets, self.loss, optimizer);
+                total += loss * batch.len() as f32;
+                self.step += 1;
+                for callback in callbacks.iter_mut() {
+                    callback.on_step_end(self, model);
+                    callback.on_batch_end(self, loss);
+                }
+            }
+            self.epoch += 1;
+            self.history.push("loss", total / split as f32);
+            self.history.push("learning_rate", rate);
+            if split < inputs.len() {
+                for (name, value) in self.evaluate(model, &inputs[split..], &targets[split..]) {
+                    self.history.push(&format!("val_{name}"), value);
+                }
+            } else if !self.metrics.is_empty() {
+                for (name, value) in self.evaluate(model, inputs, targets).into_iter().skip(1) {
+                    self.history.push(&name, value);
+                }
+            }
+            self.end_epoch(model, optimizer);
+            for callback in callbacks.iter_mut() {
+                if !callback.on_epoch_end(self, model, optimizer) {
+                    self.stopped = true;
+                }
+            }
+        }
+        self.history.clone()
+    }
+
+    // where training has got to, the random number generator, counters,
+    // history and the state of the scheduler and early stopping; the settings
+    // are left to whoever builds the trainer
+    pub fn save(&self) -> Record {
+        let mut record = Record::new();
+        record.push_count("epoch", self.epoch as u64);
+        record.push_count("step", self.step as u64);
+        record.push("stopped", &[self.stopped as u8 as f32]);
+        record.extend("rng", save_rng(&self.rng));
+        record.extend("history", self.history.save());
+        if let Some(scheduler) = &self.scheduler {
+            record.extend("scheduler", scheduler.save());
+        }
+        if let Some(stopping) = &self.early_stopping {
+            record.extend("early_stopping", stopping.save());
+        }
+        record
+    }
+
+    pub fn load(&mut self, record: &Record) -> io::Result<()> {
+        self.epoch = record.count("epoch")? as usize;
+        self.step = record.count("step")? as usize;
+        self.stopped = record.scalar("stopped")? != 0.0;
+        self.rng = load_rng(&record.section("rng"))?;
+        self.history = History::load(&record.section("history"));
+        if self.scheduler.is_some() {
+            self.scheduler = Some(Scheduler::load(&record.section("scheduler"))?);
+        }
+        if let Some(stopping) = &mut self.early_stopping {
+            stopping.load(&record.section("early_stopping"))?;
+        }
+        Ok(())
+    }
+
+    // early stopping and the scheduler look at the epoch just recorded
+    fn end_epoch(&mut self, model: &mut Sequential, optimizer: &mut dyn Optimizer) {
+        if let Some(stopping) = &mut self.early_stopping {
+            let value = self
+                .history
+                .last(&stopping.monitor)
+                .unwrap_or_else(|| panic!("nothing recorded as {}", stopping.monitor));
+            if stopping.observe(value, model) {
+                self.stopped = true;
+            }
+        }
+        if let Some(scheduler) = &mut self.scheduler {
+            let metric = self
+                .history
+                .last("val_loss")
+                .or_else(|| self.history.last("loss"));
+            scheduler.step(metric, optimizer);
+        }
+    }
+}
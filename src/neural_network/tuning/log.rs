@@ -0,0 +1,106 @@
+use std::{fs, io, path::Path};
+
+use super::Trial;
+
+// the names of the config and results, in the order the first trial has them
+fn columns(trials: &[Trial]) -> (Vec<String>, Vec<String>) {
+    let names = |values: &[(String, f32)]| values.iter().map(|(name, _)| name.clone()).collect();
+    match trials.first() {
+        Some(trial) => (names(&trial.config.values), names(&trial.results)),
+        None => (Vec::new(), Vec::new()),
+    }
+}
+
+fn lookup(values: &[(String, f32)], name: &str) -> Option<f32> {
+    values.iter().find(|(n, _)| n == name).map(|(_, x)| *x)
+}
+
+// a row per trial: id, epochs and score, then the config and then the results
+pub fn trials_to_csv(trials: &[Trial]) -> String {
+    let (config, results) = columns(trials);
+    let mut header = vec!["id".to_string(), "epochs".to_string(), "score".to_string()];
+    header.extend(config.iter().cloned());
+    header.extend(results.iter().cloned());
+    let mut text = header.join(",");
+    text.push('\n');
+    for trial in trials {
+        let mut row = vec![
+            trial.id.to_string(),
+            trial.epochs.to_string(),
+            trial.score.to_string(),
+        ];
+        let value = |x: Option<f32>| x.map_or(String::new(), |x| x.to_string());
+        row.extend(
+            config
+                .iter()
+                .map(|name| value(lookup(&trial.config.values, name))),
+        );
+        row.extend(
+            results
+                .iter()
+                .map(|name| value(lookup(&trial.results, name))),
+        );
+        text.push_str(&row.join(","));
+        text.push('\n');
+    }
+    text
+}
+
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// JSON has no NaN or infinity, so they are written as null
+fn json_number(x: f32) -> String {
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_object(values: &[(String, f32)]) -> String {
+    let fields: Vec<String> = values
+        .iter()
+        .map(|(name, x)| format!("{}: {}", json_string(name), json_number(*x)))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+// an array with an object per trial
+pub fn trials_to_json(trials: &[Trial]) -> String {
+    let objects: Vec<String> = trials
+        .iter()
+        .map(|trial| {
+            format!(
+                "  {{\"id\": {}, \"epochs\": {}, \"score\": {}, \"config\": {}, \"results\": {}}}",
+                trial.id,
+                trial.epochs,
+                json_number(trial.score),
+                json_object(&trial.config.values),
+                json_object(&trial.results)
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+// JSON when the path ends in .json, CSV otherwise
+pub fn save_trials(path: impl AsRef<Path>, trials: &[Trial]) -> io::Result<()> {
+    let path = path.as_ref();
+    let text = match path.extension() {
+        Some(extension) if extension == "json" => trials_to_json(trials),
+        _ => trials_to_csv(trials),
+    };
+    fs::write(path, text)
+}
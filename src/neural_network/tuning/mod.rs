@@ -0,0 +1,10 @@
+pub mod log;
+pub mod space;
+pub mod strategy;
+mod test;
+pub mod tuner;
+
+pub use log::*;
+pub use space::*;
+pub use strategy::*;
+pub use tuner::*;
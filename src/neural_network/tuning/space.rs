@@ -0,0 +1,179 @@
+use rand::{Rng, RngCore};
+
+// the range of one hyperparameter; a log range is uniform in the logarithm,
+// which suits learning rates and penalties spanning orders of magnitude;
+// integer ranges include both ends and choices are picked from as given
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parameter {
+    Real { low: f32, high: f32, log: bool },
+    Integer { low: i64, high: i64 },
+    Choice(Vec<f32>),
+}
+
+impl Parameter {
+    pub fn uniform(low: f32, high: f32) -> Self {
+        assert!(low <= high);
+        Parameter::Real {
+            low,
+            high,
+            log: false,
+        }
+    }
+
+    pub fn log_uniform(low: f32, high: f32) -> Self {
+        assert!(0.0 < low && low <= high);
+        Parameter::Real {
+            low,
+            high,
+            log: true,
+        }
+    }
+
+    pub fn integer(low: i64, high: i64) -> Self {
+        assert!(low <= high);
+        Parameter::Integer { low, high }
+    }
+
+    pub fn choice(options: &[f32]) -> Self {
+        assert!(!options.is_empty());
+        Parameter::Choice(options.to_vec())
+    }
+
+    // where the search works: the logarithm for a log range, the index of a
+    // choice and the value itself otherwise
+    pub fn internal(&self, value: f32) -> f32 {
+        match self {
+            Parameter::Real { log: true, .. } => value.ln(),
+            Parameter::Choice(options) => (0..options.len())
+                .min_by(|i, j| {
+                    (options[*i] - value)
+                        .abs()
+                        .total_cmp(&(options[*j] - value).abs())
+                })
+                .unwrap() as f32,
+            _ => value,
+        }
+    }
+
+    // the inverse of internal, rounding and clamping into the range
+    pub fn external(&self, x: f32) -> f32 {
+        let (low, high) = self.bounds();
+        let x = x.clamp(low, high);
+        match self {
+            Parameter::Real { log: true, .. } => x.exp(),
+            Parameter::Real { .. } => x,
+            Parameter::Integer { .. } => x.round(),
+            Parameter::Choice(options) => options[x.round() as usize],
+        }
+    }
+
+    // the internal range
+    pub fn bounds(&self) -> (f32, f32) {
+        match self {
+            Parameter::Real {
+                low,
+                high,
+                log: true,
+            } => (low.ln(), high.ln()),
+            Parameter::Real { low, high, .. } => (*low, *high),
+            Parameter::Integer { low, high } => (*low as f32, *high as f32),
+            Parameter::Choice(options) => (0.0, (options.len() - 1) as f32),
+        }
+    }
+
+    pub fn sample(&self, rng: &mut dyn RngCore) -> f32 {
+        match self {
+            Parameter::Integer { low, high } => rng.gen_range(*low..=*high) as f32,
+            Parameter::Choice(options) => options[rng.gen_range(0..options.len())],
+            _ => {
+                let (low, high) = self.bounds();
+                self.external(low + (high - low) * rng.gen::<f32>())
+            }
+        }
+    }
+
+    // steps evenly spaced values across a real range, every integer or choice
+    // when there are no more than steps of them
+    pub fn grid(&self, steps: usize) -> Vec<f32> {
+        assert!(steps > 0);
+        let (low, high) = self.bounds();
+        let count = match self {
+            Parameter::Real { .. } => steps,
+            _ => steps.min((high - low) as usize + 1),
+        };
+        if count == 1 {
+            return vec![self.external((low + high) / 2.0)];
+        }
+        let mut values: Vec<f32> = (0..count)
+            .map(|k| self.external(low + (high - low) * k as f32 / (count - 1) as f32))
+            .collect();
+        values.dedup();
+        values
+    }
+}
+
+// one value for every hyperparameter of a space, by name
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    pub values: Vec<(String, f32)>,
+}
+
+impl Config {
+    pub fn get(&self, name: &str) -> f32 {
+        self.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| *value)
+            .unwrap_or_else(|| panic!("no hyperparameter named {name}"))
+    }
+
+    pub fn integer(&self, name: &str) -> usize {
+        self.get(name).round() as usize
+    }
+}
+
+// the hyperparameters to search over, in the order configs list them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Space {
+    pub parameters: Vec<(String, Parameter)>,
+}
+
+impl Space {
+    pub fn new(parameters: Vec<(&str, Parameter)>) -> Self {
+        Space {
+            parameters: parameters
+                .into_iter()
+                .map(|(name, parameter)| (name.to_string(), parameter))
+                .collect(),
+        }
+    }
+
+    pub fn sample(&self, rng: &mut dyn RngCore) -> Config {
+        Config {
+            values: self
+                .parameters
+                .iter()
+                .map(|(name, parameter)| (name.clone(), parameter.sample(rng)))
+                .collect(),
+        }
+    }
+
+    // every combination of the grid of each parameter, the last varying fastest
+    pub fn grid(&self, steps: usize) -> Vec<Config> {
+        self.parameters
+            .iter()
+            .fold(vec![Config::default()], |configs, (name, parameter)| {
+                let values = parameter.grid(steps);
+                configs
+                    .iter()
+                    .flat_map(|config| {
+                        values.iter().map(move |value| {
+                            let mut config = config.clone();
+                            config.values.push((name.clone(), *value));
+                            config
+                        })
+                    })
+                    .collect()
+            })
+    }
+}
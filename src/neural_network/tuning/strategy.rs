@@ -0,0 +1,167 @@
+use std::f32::consts::PI;
+
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal};
+
+use super::{Config, Parameter, Space};
+
+// how configs are proposed: every point of a grid with steps values per real
+// range, independent random draws, or the tree structured Parzen estimator,
+// which after startup random trials draws candidates for each hyperparameter
+// from a density fitted to the best gamma of the trials so far and keeps the
+// one most likely under it relative to a density fitted to the rest
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    Grid {
+        steps: usize,
+    },
+    Random {
+        trials: usize,
+    },
+    Tpe {
+        trials: usize,
+        startup: usize,
+        gamma: f32,
+        candidates: usize,
+    },
+}
+
+impl Strategy {
+    pub fn tpe(trials: usize) -> Self {
+        Strategy::Tpe {
+            trials,
+            startup: trials.min(10),
+            gamma: 0.25,
+            candidates: 24,
+        }
+    }
+
+    // the next batch of configs given the ones tried and their scores, lower
+    // being better; every remaining config at once for a grid or random search,
+    // but a Parzen estimator only proposes batch at a time after the random
+    // start so it can learn from each; empty once the search is done
+    pub fn propose(
+        &self,
+        space: &Space,
+        observed: &[(Config, f32)],
+        batch: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<Config> {
+        let done = observed.len();
+        match *self {
+            Strategy::Grid { steps } => space.grid(steps).into_iter().skip(done).collect(),
+            Strategy::Random { trials } => (done..trials).map(|_| space.sample(rng)).collect(),
+            Strategy::Tpe { startup, .. } if done < startup => {
+                (done..startup).map(|_| space.sample(rng)).collect()
+            }
+            Strategy::Tpe {
+                trials,
+                gamma,
+                candidates,
+                ..
+            } => (done..trials.min(done + batch.max(1)))
+                .map(|_| parzen_config(space, observed, gamma, candidates, rng))
+                .collect(),
+        }
+    }
+}
+
+// a mixture of a normal around every point and a uniform prior over the range,
+// all in the internal values of a parameter; for choices the normals are
+// replaced by the count of each option
+struct Parzen<'a> {
+    points: &'a [f32],
+    low: f32,
+    high: f32,
+    width: f32,
+    categorical: bool,
+}
+
+impl<'a> Parzen<'a> {
+    fn new(parameter: &Parameter, points: &'a [f32]) -> Self {
+        let (low, high) = parameter.bounds();
+        Parzen {
+            points,
+            low,
+            high,
+            // narrows as points come in, as a kernel density estimate would
+            width: (high - low).max(f32::EPSILON) / (points.len() as f32 + 1.0).sqrt(),
+            categorical: matches!(parameter, Parameter::Choice(_)),
+        }
+    }
+
+    fn density(&self, x: f32) -> f32 {
+        let weight = 1.0 / (self.points.len() as f32 + 1.0);
+        let prior = if self.categorical {
+            1.0 / (self.high + 1.0)
+        } else {
+            1.0 / (self.high - self.low).max(f32::EPSILON)
+        };
+        let around: f32 = self
+            .points
+            .iter()
+            .map(|p| {
+                if self.categorical {
+                    (*p == x) as u8 as f32
+                } else {
+                    let z = (x - p) / self.width;
+                    (-0.5 * z * z).exp() / (self.width * (2.0 * PI).sqrt())
+                }
+            })
+            .sum();
+        weight * (prior + around)
+    }
+
+    fn sample(&self, rng: &mut dyn RngCore) -> f32 {
+        let i = rng.gen_range(0..=self.points.len());
+        if i == self.points.len() {
+            return if self.categorical {
+                rng.gen_range(0..=(self.high as usize)) as f32
+            } else {
+                self.low + (self.high - self.low) * rng.gen::<f32>()
+            };
+        }
+        if self.categorical {
+            return self.points[i];
+        }
+        let normal = Normal::new(self.points[i], self.width).unwrap();
+        normal.sample(rng).clamp(self.low, self.high)
+    }
+}
+
+fn parzen_config(
+    space: &Space,
+    observed: &[(Config, f32)],
+    gamma: f32,
+    candidates: usize,
+    rng: &mut dyn RngCore,
+) -> Config {
+    if observed.len() < 2 {
+        return space.sample(rng);
+    }
+    let mut ranked: Vec<&(Config, f32)> = observed.iter().collect();
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let split = ((gamma * ranked.len() as f32).ceil() as usize).clamp(1, ranked.len() - 1);
+    let values = space
+        .parameters
+        .iter()
+        .map(|(name, parameter)| {
+            let internal = |trials: &[&(Config, f32)]| -> Vec<f32> {
+                trials
+                    .iter()
+                    .map(|(config, _)| parameter.internal(config.get(name)))
+                    .collect()
+            };
+            let (good, bad) = (internal(&ranked[..split]), internal(&ranked[split..]));
+            let (l, g) = (Parzen::new(parameter, &good), Parzen::new(parameter, &bad));
+            let best = (0..candidates.max(1))
+                .map(|_| l.sample(rng))
+                .max_by(|x, y| {
+                    (l.density(*x) / g.density(*x)).total_cmp(&(l.density(*y) / g.density(*y)))
+                })
+                .unwrap();
+            (name.clone(), parameter.external(best))
+        })
+        .collect();
+    Config { values }
+}
@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod test_tuning {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::neural_network::{
+        core::{mean_squared_error, Layer, ReLU},
+        linear::linear::Linear,
+        optimiser::{AdamW, Optimizer},
+        sequential::{Node, Sequential, Tensor},
+        training::Trainer,
+        tuning::{
+            trials_to_csv, trials_to_json, Build, Config, Halving, Parameter, Space, Strategy,
+            Trial, Tuner,
+        },
+    };
+
+    #[test]
+    fn test_space() {
+        let rate = Parameter::log_uniform(1e-4, 1e-1);
+        let grid = rate.grid(4);
+        assert_eq!(grid.len(), 4);
+        for (x, y) in grid.iter().zip([1e-4, 1e-3, 1e-2, 1e-1]) {
+            assert!((x / y - 1.0).abs() < 1e-4);
+        }
+        assert_eq!(Parameter::integer(2, 4).grid(5), vec![2.0, 3.0, 4.0]);
+        assert_eq!(Parameter::integer(0, 100).grid(3), vec![0.0, 50.0, 100.0]);
+        let sizes = Parameter::choice(&[16.0, 32.0, 64.0]);
+        assert_eq!(sizes.grid(10), vec![16.0, 32.0, 64.0]);
+        assert_eq!(sizes.internal(32.0), 1.0);
+        assert_eq!(sizes.external(1.6), 64.0);
+
+        let space = Space::new(vec![
+            ("rate", rate),
+            ("hidden", Parameter::integer(2, 4)),
+            ("sizes", sizes),
+        ]);
+        let configs = space.grid(2);
+        assert_eq!(configs.len(), 2 * 2 * 2);
+        assert_eq!(configs[1].values[2], ("sizes".to_string(), 64.0));
+        assert_eq!(configs[2].integer("hidden"), 4);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let config = space.sample(&mut rng);
+            assert!((1e-4..=1e-1).contains(&config.get("rate")));
+            assert!((2..=4).contains(&config.integer("hidden")));
+            assert!([16.0, 32.0, 64.0].contains(&config.get("sizes")));
+        }
+    }
+
+    fn bowl(config: &Config) -> f32 {
+        (config.get("x") - 0.3).powi(2) + (config.get("y").log10() + 2.0).powi(2)
+    }
+
+    #[test]
+    fn test_strategies() {
+        let space = Space::new(vec![
+            ("x", Parameter::uniform(0.0, 1.0)),
+            ("y", Parameter::log_uniform(1e-4, 1.0)),
+        ]);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let grid = Strategy::Grid { steps: 3 };
+        assert_eq!(grid.propose(&space, &[], 2, &mut rng).len(), 9);
+        let random = Strategy::Random { trials: 5 };
+        let observed: Vec<(Config, f32)> = (0..2).map(|_| (space.sample(&mut rng), 0.0)).collect();
+        assert_eq!(random.propose(&space, &observed, 2, &mut rng).len(), 3);
+
+        // the Parzen estimator proposes a batch at a time after the random start
+        // and homes in on the bottom of the bowl
+        let tpe = Strategy::tpe(60);
+        let mut observed: Vec<(Config, f32)> = Vec::new();
+        loop {
+            let configs = tpe.propose(&space, &observed, 2, &mut rng);
+            if configs.is_empty() {
+                break;
+            }
+            assert_eq!(configs.len(), if observed.is_empty() { 10 } else { 2 });
+            observed.extend(configs.into_iter().map(|config| {
+                let loss = bowl(&config);
+                (config, loss)
+            }));
+        }
+        assert_eq!(observed.len(), 60);
+        let mean = |trials: &[(Config, f32)]| {
+            trials.iter().map(|(_, loss)| loss).sum::<f32>() / trials.len() as f32
+        };
+        assert!(mean(&observed[50..]) < mean(&observed[..10]) / 4.0);
+        let best = observed
+            .iter()
+            .map(|(_, loss)| *loss)
+            .fold(f32::INFINITY, f32::min);
+        assert!(best < 0.02);
+    }
+
+    // y = relu(a - b) + 0.5 on a small grid, with the last quarter held out
+    fn ramp() -> (Vec<Tensor>, Vec<Vec<f32>>) {
+        (0..32)
+            .map(|k| {
+                let (a, b) = (
+                    (k * 5 % 8) as f32 / 4.0 - 1.0,
+                    (k * 3 % 7) as f32 / 3.0 - 1.0,
+                );
+                (Tensor::Flat(vec![a, b]), vec![(a - b).max(0.0) + 0.5])
+            })
+            .unzip()
+    }
+
+    // fixed weights so every run of a config trains the same way
+    fn build(config: &Config) -> (Sequential, Box<dyn Optimizer>, Trainer) {
+        let hidden = config.integer("hidden");
+        let mut first = Linear::new(2, hidden);
+        for (r, row) in first.weights.iter_mut().enumerate() {
+            *row = vec![0.5 - 0.1 * r as f32, 0.2 * r as f32 - 0.4];
+        }
+        let relu = ReLU::new(first.dim_out());
+        let mut second = Linear::new(hidden, 1);
+        second.weights = vec![(0..hidden).map(|r| 0.3 - 0.05 * r as f32).collect()];
+        let model = Sequential::new(vec![
+            Node::Dense(Box::new(first)),
+            Node::Dense(Box::new(relu)),
+            Node::Dense(Box::new(second)),
+        ]);
+        let optimizer = AdamW::new(config.get("rate"), (0.9, 0.999), 1e-8, config.get("decay"));
+        let mut trainer = Trainer::new(mean_squared_error, 0, 8, 7);
+        trainer.validation_split = 0.25;
+        (model, Box::new(optimizer), trainer)
+    }
+
+    fn space() -> Space {
+        Space::new(vec![
+            ("rate", Parameter::log_uniform(1e-4, 1e-1)),
+            ("hidden", Parameter::choice(&[2.0, 4.0])),
+            ("decay", Parameter::uniform(0.0, 0.01)),
+        ])
+    }
+
+    fn run(strategy: Strategy, threads: usize, halving: Option<Halving>) -> (Trial, Vec<Trial>) {
+        let mut tuner = Tuner::new(space(), strategy, "val_loss", false, 8, 3);
+        tuner.threads = threads;
+        tuner.halving = halving;
+        let (inputs, targets) = ramp();
+        let build: &Build = &build;
+        let best = tuner.run(build, &inputs, &targets).unwrap();
+        (best, tuner.trials)
+    }
+
+    #[test]
+    fn test_tuner() {
+        let random = Strategy::Random { trials: 8 };
+        let (best, trials) = run(random, 1, None);
+        assert_eq!(trials.len(), 8);
+        assert!(trials.iter().all(|trial| trial.epochs == 8));
+        assert_eq!(best.results.len(), 3);
+        assert!(trials.iter().all(|trial| trial.score >= best.score));
+        // the small rates have hardly moved off the starting point
+        let slowest = trials
+            .iter()
+            .min_by(|a, b| a.config.get("rate").total_cmp(&b.config.get("rate")))
+            .unwrap();
+        assert!(slowest.score > best.score);
+
+        // threads change nothing but the speed
+        assert_eq!(run(random, 4, None), (best.clone(), trials.clone()));
+
+        // 8 configs for 2 epochs, the best 4 carry on to 4 and the best 2 to 8,
+        // ending the same as training those straight through
+        let (halved_best, halved) = run(random, 3, Some(Halving::new(2, 2)));
+        let rungs: Vec<usize> = [2, 4, 8]
+            .iter()
+            .map(|epochs| halved.iter().filter(|t| t.epochs == *epochs).count())
+            .collect();
+        assert_eq!(rungs, vec![8, 4, 2]);
+        let straight = &trials[halved_best.id];
+        assert_eq!(halved_best.config, straight.config);
+        assert_eq!(halved_best.score, straight.score);
+    }
+
+    #[test]
+    fn test_tpe_halving() {
+        let tpe = Strategy::Tpe {
+            trials: 12,
+            startup: 4,
+            gamma: 0.25,
+            candidates: 24,
+        };
+        // on one thread the estimator still proposes brackets of 4 configs, of
+        // which 2 carry on to 4 epochs and 1 to 8
+        let (best, trials) = run(tpe, 1, Some(Halving::new(2, 2)));
+        let rungs: Vec<usize> = [2, 4, 8]
+            .iter()
+            .map(|epochs| trials.iter().filter(|t| t.epochs == *epochs).count())
+            .collect();
+        assert_eq!(rungs, vec![12, 6, 3]);
+        assert_eq!(best.epochs, 8);
+        assert_eq!(run(tpe, 3, Some(Halving::new(2, 2))), (best, trials));
+    }
+
+    #[test]
+    fn test_log() {
+        let (_, trials) = run(Strategy::Random { trials: 8 }, 2, Some(Halving::new(4, 2)));
+        let csv = trials_to_csv(&trials);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 8 + 4);
+        assert_eq!(
+            lines[0],
+            "id,epochs,score,rate,hidden,decay,loss,learning_rate,val_loss"
+        );
+        assert!(lines[1].starts_with("0,4,"));
+        assert_eq!(lines[1].split(',').count(), 9);
+
+        let mut odd = trials[0].clone();
+        odd.score = f32::NAN;
+        odd.config.values = vec![("a \"quoted\" name".to_string(), 1.5)];
+        let json = trials_to_json(&[odd, trials[1].clone()]);
+        assert!(json.starts_with("[\n  {\"id\": 0, \"epochs\": 4, \"score\": null, "));
+        assert!(json.contains("\"config\": {\"a \\\"quoted\\\" name\": 1.5}"));
+        assert!(json.contains("\"results\": {\"loss\": "));
+        assert!(json.ends_with("}\n]\n"));
+
+        let path = std::env::temp_dir().join(format!("tuning-{}.json", std::process::id()));
+        let mut tuner = Tuner::new(
+            space(),
+            Strategy::Random { trials: 2 },
+            "val_loss",
+            false,
+            2,
+            0,
+        );
+        tuner.log = Some(path.clone());
+        let (inputs, targets) = ramp();
+        tuner.run(&build, &inputs, &targets).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, trials_to_json(&tuner.trials));
+    }
+}
@@ -0,0 +1,274 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::neural_network::core::Record;
+use crate::neural_network::optimiser::Optimizer;
+use crate::neural_network::sequential::{Sequential, Tensor};
+use crate::neural_network::training::{checkpoint, resume, Trainer};
+
+use super::{save_trials, Config, Space, Strategy};
+
+// successive halving: every config of a batch trains for min_epochs, the best
+// 1 / factor of them carry on to factor times as many, and so on up to the
+// full epochs, so poor configs stop early; the ones carrying on pick up from
+// a checkpoint rather than starting over; a Parzen estimator proposes at least
+// factor^(rungs - 1) configs per batch so halving stops some even on one thread
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Halving {
+    pub min_epochs: usize,
+    pub factor: usize,
+}
+
+impl Halving {
+    pub fn new(min_epochs: usize, factor: usize) -> Self {
+        assert!(min_epochs > 0 && factor > 1);
+        Halving { min_epochs, factor }
+    }
+}
+
+// a config trained to some number of epochs, with the monitored score and the
+// last value of everything in its history; a config stopped by halving has a
+// trial for every rung it reached, all with the same id
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trial {
+    pub id: usize,
+    pub config: Config,
+    pub epochs: usize,
+    pub score: f32,
+    pub results: Vec<(String, f32)>,
+}
+
+// how to train a config, building the model, optimiser and trainer from
+// scratch; the trainer's epochs are set by the tuner
+pub type Build<'a> = dyn Fn(&Config) -> (Sequential, Box<dyn Optimizer>, Trainer) + Sync + 'a;
+
+// searches the space with the strategy, training each config for epochs and
+// scoring it on the last value of the monitored history entry, lower being
+// better unless maximise; batches of trials run on up to threads threads, and
+// after each batch every trial so far is written to the log, as JSON if its
+// name ends in .json and as CSV otherwise
+pub struct Tuner {
+    pub space: Space,
+    pub strategy: Strategy,
+    pub monitor: String,
+    pub maximise: bool,
+    pub epochs: usize,
+    pub halving: Option<Halving>,
+    pub threads: usize,
+    pub log: Option<PathBuf>,
+    pub rng: ChaCha8Rng,
+    pub trials: Vec<Trial>,
+}
+
+// runs f on every item over up to threads threads, the results in the order
+// of the items whichever finishes first
+fn parallel<T: Sync, R: Send>(items: &[T], threads: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= items.len() {
+                    break;
+                }
+                let result = f(&items[i]);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.unwrap())
+        .collect()
+}
+
+impl Tuner {
+    pub fn new(
+        space: Space,
+        strategy: Strategy,
+        monitor: &str,
+        maximise: bool,
+        epochs: usize,
+        seed: u64,
+    ) -> Self {
+        assert!(epochs > 0);
+        Tuner {
+            space,
+            strategy,
+            monitor: monitor.to_string(),
+            maximise,
+            epochs,
+            halving: None,
+            threads: 1,
+            log: None,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            trials: Vec::new(),
+        }
+    }
+
+    // the score turned around so lower is better, with NaN from a diverged
+    // run the worst of all
+    fn loss(&self, score: f32) -> f32 {
+        match score {
+            x if x.is_nan() => f32::INFINITY,
+            x if self.maximise => -x,
+            x => x,
+        }
+    }
+
+    // the epochs of each rung of halving, ending with the full epochs
+    pub fn budgets(&self) -> Vec<usize> {
+        let mut budgets = Vec::new();
+        if let Some(halving) = self.halving {
+            let mut budget = halving.min_epochs;
+            while budget < self.epochs {
+                budgets.push(budget);
+                budget *= halving.factor;
+            }
+        }
+        budgets.push(self.epochs);
+        budgets
+    }
+
+    // how many configs a Parzen estimator proposes at once after its random
+    // start, a whole halving bracket or otherwise one per thread
+    pub fn batch(&self) -> usize {
+        let rungs = self.budgets().len() as u32;
+        let bracket = self
+            .halving
+            .map_or(1, |halving| halving.factor.saturating_pow(rungs - 1));
+        bracket.max(self.threads)
+    }
+
+    // every config tried with its loss at the first rung, which they all reach
+    // on the same budget, for the strategy to learn from
+    pub fn observed(&self) -> Vec<(Config, f32)> {
+        let first = self.budgets()[0];
+        self.trials
+            .iter()
+            .filter(|trial| trial.epochs == first)
+            .map(|trial| (trial.config.clone(), self.loss(trial.score)))
+            .collect()
+    }
+
+    // the best trial that trained for the full epochs
+    pub fn best(&self) -> Option<&Trial> {
+        self.trials
+            .iter()
+            .filter(|trial| trial.epochs == self.epochs)
+            .min_by(|a, b| self.loss(a.score).total_cmp(&self.loss(b.score)))
+    }
+
+    pub fn run(
+        &mut self,
+        build: &Build,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+    ) -> io::Result<Trial> {
+        loop {
+            let observed = self.observed();
+            let batch = self.batch();
+            let configs = self
+                .strategy
+                .propose(&self.space, &observed, batch, &mut self.rng);
+            if configs.is_empty() {
+                break;
+            }
+            self.run_batch(build, configs, observed.len(), inputs, targets);
+            if let Some(path) = &self.log {
+                save_trials(path, &self.trials)?;
+            }
+        }
+        self.best()
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "nothing was tried"))
+    }
+
+    fn run_batch(
+        &mut self,
+        build: &Build,
+        configs: Vec<Config>,
+        first_id: usize,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+    ) {
+        let mut entrants: Vec<(usize, Config, Option<Record>)> = configs
+            .into_iter()
+            .enumerate()
+            .map(|(i, config)| (first_id + i, config, None))
+            .collect();
+        let budgets = self.budgets();
+        for (rung, budget) in budgets.iter().enumerate() {
+            let results = parallel(&entrants, self.threads, |(id, config, record)| {
+                self.train(
+                    build,
+                    *id,
+                    config,
+                    record.as_ref(),
+                    *budget,
+                    inputs,
+                    targets,
+                )
+            });
+            self.trials
+                .extend(results.iter().map(|(trial, _)| trial.clone()));
+            if rung + 1 == budgets.len() {
+                break;
+            }
+            let mut ranked: Vec<(Trial, Record)> = results;
+            ranked.sort_by(|a, b| self.loss(a.0.score).total_cmp(&self.loss(b.0.score)));
+            let factor = self.halving.map_or(1, |halving| halving.factor);
+            entrants = ranked
+                .into_iter()
+                .take(entrants.len().div_ceil(factor))
+                .map(|(trial, record)| (trial.id, trial.config, Some(record)))
+                .collect();
+        }
+    }
+
+    // trains a config, from a checkpoint if it has one, up to epochs, returning
+    // the trial and a checkpoint to carry on from
+    #[allow(clippy::too_many_arguments)]
+    fn train(
+        &self,
+        build: &Build,
+        id: usize,
+        config: &Config,
+        record: Option<&Record>,
+        epochs: usize,
+        inputs: &[Tensor],
+        targets: &[Vec<f32>],
+    ) -> (Trial, Record) {
+        let (mut model, mut optimizer, mut trainer) = build(config);
+        if let Some(record) = record {
+            resume(record, &mut trainer, &mut model, optimizer.as_mut())
+                .expect("a checkpoint from the same config");
+        }
+        trainer.epochs = epochs;
+        let history = trainer.fit(&mut model, optimizer.as_mut(), inputs, targets, &mut []);
+        let score = history
+            .last(&self.monitor)
+            .unwrap_or_else(|| panic!("nothing recorded as {}", self.monitor));
+        let trial = Trial {
+            id,
+            config: config.clone(),
+            epochs,
+            score,
+            results: history
+                .series
+                .iter()
+                .map(|(name, values)| (name.clone(), values[values.len() - 1]))
+                .collect(),
+        };
+        (trial, checkpoint(&trainer, &mut model, optimizer.as_ref()))
+    }
+}
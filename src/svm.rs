@@ -1,4 +1,5 @@
-use rand::thread_rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use rand_distr::{Distribution, Normal, Uniform};
 
 #[test]
@@ -10,11 +11,15 @@ fn test_svm() {
         vec![(5.0, 1.0), (1.0, 1.0)],
     );
     let model = train_svm(&points, &labels);
-    assert_eq!(svm_predict(&model.0, model.1, &points[0]), -1.0);
-    assert_eq!(
-        svm_predict(&model.0, model.1, &points[points.len() - 1]),
-        1.0
-    );
+    let predicted: Vec<usize> = points
+        .iter()
+        .map(|x| crate::metrics::class_of_sign(svm_predict(&model.0, model.1, x)))
+        .collect();
+    let actual: Vec<usize> = labels
+        .iter()
+        .map(|y| crate::metrics::class_of_sign(*y))
+        .collect();
+    assert!(crate::metrics::accuracy(&predicted, &actual) > 0.9);
 }
 
 fn sample(
@@ -25,7 +30,7 @@ fn sample(
 ) -> (Vec<Vec<f64>>, Vec<f64>) {
     let mut data: Vec<Vec<f64>> = Vec::new();
     let mut labels: Vec<f64> = Vec::new();
-    let mut rng = thread_rng();
+    let mut rng = ChaCha8Rng::seed_from_u64(22);
     for _ in 0..size1 {
         data.push(
             params1
@@ -49,7 +54,7 @@ fn sample(
 
 fn train_svm(data: &Vec<Vec<f64>>, labels: &Vec<f64>) -> (Vec<f64>, f64) {
     let learning_rate = 0.01;
-    let mut rng = thread_rng();
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
     let normal = Normal::new(0.0, 1.0).unwrap();
     let uniform: Uniform<u32> = Uniform::new(0, data.len() as u32);
     let mut w: Vec<f64> = Vec::new();
@@ -62,8 +67,6 @@ fn train_svm(data: &Vec<Vec<f64>>, labels: &Vec<f64>) -> (Vec<f64>, f64) {
         for _j in 0..16 {
             sample_batch.push(uniform.sample(&mut rng) as usize);
         }
-        print!("{sample_batch:?}");
-        print!("{w:?}");
         let mut dw: Vec<f64> = Vec::new();
         let mut db: f64 = 0.0;
         for k in sample_batch {